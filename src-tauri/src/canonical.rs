@@ -0,0 +1,213 @@
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::diff;
+
+/// RFC 8785 (JCS) form of a value and the SHA-256 of its UTF-8 bytes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Canonical {
+    pub canonical: String,
+    pub sha256: String,
+}
+
+/// Canonicalizes the subtree of `document` at the display path `at` (the
+/// whole document when empty).
+pub fn canonical_at(document: &Value, at: &str) -> Result<Canonical, String> {
+    let value = diff::value_at(document, at).ok_or_else(|| format!("path '{at}' not found"))?;
+    let canonical = canonicalize(value)?;
+    let sha256 = Sha256::digest(canonical.as_bytes())
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect();
+    Ok(Canonical { canonical, sha256 })
+}
+
+/// Serializes `value` as RFC 8785 canonical JSON: no whitespace, object
+/// members sorted by the UTF-16 code units of their names, numbers in their
+/// shortest ECMAScript form and strings with only the mandatory escapes.
+/// Numbers beyond the range of a double have no canonical form.
+pub fn canonicalize(value: &Value) -> Result<String, String> {
+    let mut out = String::new();
+    write_value(value, &mut out)?;
+    Ok(out)
+}
+
+fn write_value(value: &Value, out: &mut String) -> Result<(), String> {
+    match value {
+        Value::Null => out.push_str("null"),
+        Value::Bool(b) => out.push_str(if *b { "true" } else { "false" }),
+        Value::Number(n) => {
+            // JCS works on IEEE doubles, so integers beyond 2^53 round just
+            // like they do in JavaScript.
+            let n = n
+                .as_f64()
+                .filter(|n| n.is_finite())
+                .ok_or_else(|| format!("{n} is out of range for canonical JSON"))?;
+            out.push_str(ryu_js::Buffer::new().format_finite(n));
+        }
+        Value::String(s) => write_string(s, out),
+        Value::Array(items) => {
+            out.push('[');
+            for (idx, item) in items.iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                write_value(item, out)?;
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut members: Vec<(Vec<u16>, &String, &Value)> = map
+                .iter()
+                .map(|(name, value)| (name.encode_utf16().collect(), name, value))
+                .collect();
+            members.sort_by(|a, b| a.0.cmp(&b.0));
+            out.push('{');
+            for (idx, (_, name, value)) in members.into_iter().enumerate() {
+                if idx > 0 {
+                    out.push(',');
+                }
+                write_string(name, out);
+                out.push(':');
+                write_value(value, out)?;
+            }
+            out.push('}');
+        }
+    }
+    Ok(())
+}
+
+fn write_string(s: &str, out: &mut String) {
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\u{8}' => out.push_str("\\b"),
+            '\t' => out.push_str("\\t"),
+            '\n' => out.push_str("\\n"),
+            '\u{c}' => out.push_str("\\f"),
+            '\r' => out.push_str("\\r"),
+            c if c < ' ' => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn canonical(json: &str) -> Result<String, String> {
+        canonicalize(&serde_json::from_str(json).unwrap())
+    }
+
+    #[test]
+    fn numbers_use_the_ecmascript_form() {
+        // RFC 8785 appendix B.
+        for (input, expected) in [
+            ("0", "0"),
+            ("-0", "0"),
+            ("5e-324", "5e-324"),
+            ("-5e-324", "-5e-324"),
+            ("1.7976931348623157e308", "1.7976931348623157e+308"),
+            ("-1.7976931348623157e308", "-1.7976931348623157e+308"),
+            ("9007199254740992", "9007199254740992"),
+            ("-9007199254740992", "-9007199254740992"),
+            ("295147905179352830000", "295147905179352830000"),
+            ("999999999999999700000", "999999999999999700000"),
+            ("1e21", "1e+21"),
+            ("1e23", "1e+23"),
+            ("0.000001", "0.000001"),
+            ("1e-7", "1e-7"),
+            ("333333333.33333329", "333333333.3333333"),
+            ("4.50", "4.5"),
+            ("9007199254740993", "9007199254740992"),
+        ] {
+            assert_eq!(canonical(input).as_deref(), Ok(expected), "{input}");
+        }
+    }
+
+    #[test]
+    fn numbers_beyond_a_double_are_errors() {
+        for input in ["1e400", "-1e400", r#"{"a": [1e309]}"#] {
+            let error = canonical(input).unwrap_err();
+            assert!(error.contains("out of range"), "{input}: {error}");
+        }
+    }
+
+    #[test]
+    fn members_sort_by_utf16_code_units() {
+        // RFC 8785 section 3.2.3: U+1F600 is a surrogate pair, so it sorts
+        // before U+FB33 even though its code point is higher.
+        let input = r#"{
+            "€": "Euro Sign",
+            "\r": "Carriage Return",
+            "דּ": "Hebrew Letter Dalet With Dagesh",
+            "1": "One",
+            "😀": "Emoji: Grinning Face",
+            "\u0080": "Control",
+            "ö": "Latin Small Letter O With Diaeresis"
+        }"#;
+        let value: Value = serde_json::from_str(&canonical(input).unwrap()).unwrap();
+        let names: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(String::as_str)
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "\r",
+                "1",
+                "\u{80}",
+                "\u{f6}",
+                "\u{20ac}",
+                "\u{1f600}",
+                "\u{fb33}"
+            ]
+        );
+    }
+
+    #[test]
+    fn rfc_example_with_escapes() {
+        // RFC 8785 section 3.2.2.
+        let input = r#"{
+            "numbers": [333333333.33333329, 1E30, 4.50, 2e-3, 0.000000000000000000000000001],
+            "string": "€$\u000F\u000aA'B\"\\\\\"\/",
+            "literals": [null, true, false]
+        }"#;
+        assert_eq!(
+            canonical(input).unwrap(),
+            r#"{"literals":[null,true,false],"numbers":[333333333.3333333,1e+30,4.5,0.002,1e-27],"string":"€$\u000f\nA'B\"\\\\\"/"}"#
+        );
+        assert_eq!(
+            canonical(r#""\u0000\b\t\u001f\u007f""#).unwrap(),
+            "\"\\u0000\\b\\t\\u001f\u{7f}\""
+        );
+    }
+
+    #[test]
+    fn hashes_the_subtree_at_a_path() {
+        let document: Value = serde_json::from_str(r#"{"a": {"y": 2, "x": [1]}}"#).unwrap();
+        let subtree = canonical_at(&document, "a").unwrap();
+        assert_eq!(subtree.canonical, r#"{"x":[1],"y":2}"#);
+        assert_eq!(subtree.sha256.len(), 64);
+        // Field names may hold the characters display paths join with.
+        let document: Value =
+            serde_json::from_str(r#"{"a.b": {"c[0]": [1, 2]}, "a": {"b": 0}}"#).unwrap();
+        assert_eq!(
+            canonical_at(&document, "a.b.c[0][1]").unwrap().canonical,
+            "2"
+        );
+        assert_eq!(
+            canonical_at(&document, "a.b").unwrap().canonical,
+            r#"{"c[0]":[1,2]}"#
+        );
+        assert!(canonical_at(&document, "b").is_err());
+    }
+}
@@ -0,0 +1,1619 @@
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::{Hash, Hasher};
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use similar::{Algorithm, DiffTag};
+
+use crate::embedded::{self, Encoding};
+use crate::path::{PathPattern, Segment};
+use crate::source;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DiffOptions {
+    pub array_mode: ArrayMode,
+    /// Arrays whose elements are paired by an identity field rather than
+    /// by position. Takes precedence over `array_mode`.
+    pub array_keys: Vec<ArrayKey>,
+    /// Arrays compared as multisets, e.g. `$..tags`. Takes precedence over
+    /// `array_mode`, but not over `array_keys`.
+    pub unordered_paths: Vec<PathPattern>,
+    /// Paths left out of the diff entirely, e.g. `$..updatedAt` or
+    /// `metadata.resourceVersion`.
+    pub ignore_paths: Vec<PathPattern>,
+    pub equivalence: EquivalenceRules,
+    /// Pairs subtrees that were removed in one place and added in another
+    /// (renamed keys, subtrees moved to another parent) and reports them as
+    /// moved instead.
+    pub detect_moves: bool,
+    /// Strings holding serialized JSON, YAML or base64 JSON are parsed and
+    /// compared as structure.
+    pub decode_embedded: bool,
+}
+
+/// Loosened comparisons for primitives. A pair that only matches under one
+/// of these rules is reported as `Equivalent` rather than `Unchanged`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct EquivalenceRules {
+    /// Numbers within this absolute distance are equivalent.
+    pub absolute_tolerance: Option<f64>,
+    /// Numbers within this fraction of the larger magnitude are equivalent.
+    pub relative_tolerance: Option<f64>,
+    /// `"42"` and `42` are equivalent.
+    pub coerce_string_numbers: bool,
+    /// Strings that only differ in case are equivalent.
+    pub case_insensitive: bool,
+    /// Strings that only differ in leading, trailing or repeated whitespace
+    /// are equivalent, e.g. reflowed XML text.
+    pub collapse_whitespace: bool,
+    /// An explicit `null` and a missing entry are equivalent.
+    pub null_equals_missing: bool,
+}
+
+/// The rule that made two values equivalent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Equivalence {
+    Tolerance,
+    Coercion,
+    CaseInsensitive,
+    Whitespace,
+    NullMissing,
+}
+
+impl EquivalenceRules {
+    fn check(&self, left: &Value, right: &Value) -> Option<Equivalence> {
+        match (left, right) {
+            (Value::Number(l), Value::Number(r)) => self
+                .within_tolerance(l.as_f64()?, r.as_f64()?)
+                .then_some(Equivalence::Tolerance),
+            (Value::String(s), Value::Number(n)) | (Value::Number(n), Value::String(s))
+                if self.coerce_string_numbers =>
+            {
+                let parsed: f64 = s.trim().parse().ok()?;
+                let number = n.as_f64()?;
+                (parsed == number || self.within_tolerance(parsed, number))
+                    .then_some(Equivalence::Coercion)
+            }
+            (Value::String(l), Value::String(r)) => {
+                if self.case_insensitive && l.to_lowercase() == r.to_lowercase() {
+                    return Some(Equivalence::CaseInsensitive);
+                }
+                (self.collapse_whitespace && collapse_whitespace(l) == collapse_whitespace(r))
+                    .then_some(Equivalence::Whitespace)
+            }
+            _ => None,
+        }
+    }
+
+    fn within_tolerance(&self, left: f64, right: f64) -> bool {
+        let delta = (left - right).abs();
+        self.absolute_tolerance.is_some_and(|tol| delta <= tol)
+            || self
+                .relative_tolerance
+                .is_some_and(|tol| delta <= tol * left.abs().max(right.abs()))
+    }
+}
+
+fn collapse_whitespace(s: &str) -> String {
+    s.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+/// Pairs elements of the arrays matching `path` (e.g. `spec.containers[*]`)
+/// by the value of their `key` field.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArrayKey {
+    pub path: PathPattern,
+    pub key: String,
+}
+
+/// How elements of two arrays are paired up before comparing them.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ArrayMode {
+    /// Element `i` on the left is compared with element `i` on the right.
+    #[default]
+    Index,
+    /// Elements are aligned with a Myers diff over their content hashes, so
+    /// insertions and deletions don't shift every following element.
+    Lcs,
+    /// Order is ignored: equal elements pair up wherever they are and only
+    /// missing or extra elements are reported.
+    Multiset,
+}
+
+/// Mirrors `DiffType` in `src/utils/diffTree.ts`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiffType {
+    Added,
+    Removed,
+    Changed,
+    Unchanged,
+    /// Same content, but the element sits at a different position, or a
+    /// subtree that was renamed or moved to another parent.
+    Moved,
+    /// Different, but equal under one of the configured equivalence rules.
+    Equivalent,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NodeType {
+    Object,
+    Array,
+    Primitive,
+}
+
+/// One side of a diff row. Same shape as `JsonNode` in the webview, except
+/// that containers carry `childCount` instead of their full subtree.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SideNode {
+    pub key: String,
+    pub path: String,
+    #[serde(rename = "type")]
+    pub node_type: NodeType,
+    pub value: Value,
+    pub depth: usize,
+    pub child_count: usize,
+    pub is_last: bool,
+    /// A number as written when the webview's `JSON.parse` would round it
+    /// or can't read it, like integers beyond ±2^53 or JSON5's `Infinity`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+    /// Set when the value was a string holding a serialized document.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub decoded: Option<Encoding>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiffNode {
+    pub path: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left_node: Option<SideNode>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub right_node: Option<SideNode>,
+    pub diff_type: DiffType,
+    pub depth: usize,
+    pub is_collapsible: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub child_diffs: Option<Vec<DiffNode>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub equivalence: Option<Equivalence>,
+    /// Where a moved subtree came from. Set on the row at its new path, which
+    /// compares the old content against the new.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moved_from: Option<String>,
+    /// Where a moved subtree went. Set on the row at its old path.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub moved_to: Option<String>,
+}
+
+/// Changes in a diff, counted the way the diff view's header counts them.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct DiffStats {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub moved: usize,
+    pub equivalent: usize,
+}
+
+impl DiffStats {
+    pub fn add(&mut self, other: &DiffStats) {
+        self.added += other.added;
+        self.removed += other.removed;
+        self.changed += other.changed;
+        self.moved += other.moved;
+        self.equivalent += other.equivalent;
+    }
+}
+
+impl DiffNode {
+    /// Calls `f` on each row the stats count: added and removed leaves,
+    /// changed primitives, equivalent values and moves. A move shows up at
+    /// both ends but is visited once, at its destination.
+    pub fn for_each_change<'a>(&'a self, f: &mut impl FnMut(&'a DiffNode)) {
+        let leaf = self.child_diffs.is_none();
+        let counted = match self.diff_type {
+            DiffType::Added | DiffType::Removed => leaf,
+            DiffType::Changed => !self.is_collapsible,
+            DiffType::Moved if self.moved_to.is_some() => return,
+            DiffType::Moved => true,
+            DiffType::Equivalent => self.equivalence.is_some(),
+            DiffType::Unchanged => false,
+        };
+        if counted {
+            f(self);
+        }
+        for child in self.child_diffs.iter().flatten() {
+            child.for_each_change(f);
+        }
+    }
+
+    pub fn stats(&self) -> DiffStats {
+        let mut stats = DiffStats::default();
+        self.for_each_change(&mut |node| match node.diff_type {
+            DiffType::Added => stats.added += 1,
+            DiffType::Removed => stats.removed += 1,
+            DiffType::Changed => stats.changed += 1,
+            DiffType::Moved => stats.moved += 1,
+            DiffType::Equivalent => stats.equivalent += 1,
+            DiffType::Unchanged => {}
+        });
+        stats
+    }
+}
+
+/// A value positioned in its document: the key it sits under, its display
+/// path and whether it is the last entry of its parent.
+#[derive(Clone)]
+struct Located<'a> {
+    value: &'a Value,
+    key: Key<'a>,
+    path: String,
+    /// The path as matched by path patterns. The display path can't be
+    /// split back into these when a field name holds `.` or `[`.
+    segments: Vec<Segment>,
+    is_last: bool,
+}
+
+impl<'a> Located<'a> {
+    fn root(value: &'a Value) -> Self {
+        Located {
+            value,
+            key: Key::Root,
+            path: String::new(),
+            segments: Vec::new(),
+            is_last: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+enum Key<'a> {
+    Root,
+    Field(&'a str),
+    Index(usize),
+}
+
+impl Key<'_> {
+    fn label(&self) -> String {
+        match self {
+            Key::Root => "root".to_string(),
+            Key::Field(name) => name.to_string(),
+            Key::Index(idx) => idx.to_string(),
+        }
+    }
+}
+
+/// Diffs two parsed documents. Returns `None` when both sides are empty.
+pub fn diff_documents(
+    left: Option<&Value>,
+    right: Option<&Value>,
+    options: &DiffOptions,
+) -> Option<DiffNode> {
+    if !options.decode_embedded {
+        return diff_values(left, right, options);
+    }
+    let left = left.map(embedded::decode);
+    let right = right.map(embedded::decode);
+    let mut root = diff_values(
+        left.as_ref().map(|d| &d.value),
+        right.as_ref().map(|d| &d.value),
+        options,
+    )?;
+    let no_paths = HashMap::new();
+    mark_decoded(
+        &mut root,
+        left.as_ref().map_or(&no_paths, |d| &d.paths),
+        right.as_ref().map_or(&no_paths, |d| &d.paths),
+    );
+    Some(root)
+}
+
+fn diff_values(
+    left: Option<&Value>,
+    right: Option<&Value>,
+    options: &DiffOptions,
+) -> Option<DiffNode> {
+    match (left.map(Located::root), right.map(Located::root)) {
+        (None, None) => None,
+        (left, right) => {
+            let differ = Differ {
+                options,
+                removed: RefCell::default(),
+                added: RefCell::default(),
+            };
+            let mut root = differ.compare(left, right, 0);
+            if options.detect_moves {
+                differ.pair_moves(&mut root);
+            }
+            Some(root)
+        }
+    }
+}
+
+fn mark_decoded(
+    node: &mut DiffNode,
+    left: &HashMap<String, Encoding>,
+    right: &HashMap<String, Encoding>,
+) {
+    if let Some(side) = &mut node.left_node {
+        side.decoded = left.get(&side.path).copied();
+    }
+    if let Some(side) = &mut node.right_node {
+        side.decoded = right.get(&side.path).copied();
+    }
+    for child in node.child_diffs.iter_mut().flatten() {
+        mark_decoded(child, left, right);
+    }
+}
+
+/// Deep equality where numbers compare by value rather than as written, so
+/// `1`, `1.0` and `1e0` are equal. Numbers keep their literal text when
+/// parsed, which `==` on [`Value`] compares.
+pub(crate) fn values_equal(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(l), Value::Number(r)) => {
+            source::number_key(&l.to_string()) == source::number_key(&r.to_string())
+        }
+        (Value::Array(l), Value::Array(r)) => {
+            l.len() == r.len() && l.iter().zip(r).all(|(l, r)| values_equal(l, r))
+        }
+        (Value::Object(l), Value::Object(r)) => {
+            l.len() == r.len()
+                && l.iter()
+                    .all(|(key, l)| r.get(key).is_some_and(|r| values_equal(l, r)))
+        }
+        _ => left == right,
+    }
+}
+
+/// Structural hash of a value. Object keys are hashed in sorted order so two
+/// objects that only differ in key order hash the same.
+pub(crate) fn content_hash(value: &Value) -> u64 {
+    fn feed(value: &Value, state: &mut DefaultHasher) {
+        match value {
+            Value::Null => 0u8.hash(state),
+            Value::Bool(b) => (1u8, b).hash(state),
+            Value::Number(n) => (2u8, source::number_key(&n.to_string())).hash(state),
+            Value::String(s) => (3u8, s).hash(state),
+            Value::Array(items) => {
+                (4u8, items.len()).hash(state);
+                items.iter().for_each(|item| feed(item, state));
+            }
+            Value::Object(map) => {
+                (5u8, map.len()).hash(state);
+                let mut entries: Vec<_> = map.iter().collect();
+                entries.sort_by(|a, b| a.0.cmp(b.0));
+                for (key, value) in entries {
+                    key.hash(state);
+                    feed(value, state);
+                }
+            }
+        }
+    }
+    let mut state = DefaultHasher::new();
+    feed(value, &mut state);
+    state.finish()
+}
+
+fn node_type(value: &Value) -> NodeType {
+    match value {
+        Value::Object(_) => NodeType::Object,
+        Value::Array(_) => NodeType::Array,
+        _ => NodeType::Primitive,
+    }
+}
+
+fn side_node(node: &Located, depth: usize) -> SideNode {
+    let (value, child_count) = match node.value {
+        Value::Object(map) => (Value::Null, map.len()),
+        Value::Array(items) => (Value::Null, items.len()),
+        primitive => (primitive.clone(), 0),
+    };
+    SideNode {
+        key: node.key.label(),
+        path: node.path.clone(),
+        node_type: node_type(node.value),
+        raw: js_rounded_number(&value),
+        decoded: None,
+        value: source::webview_value(&value),
+        depth,
+        child_count,
+        is_last: node.is_last,
+    }
+}
+
+fn js_rounded_number(value: &Value) -> Option<String> {
+    let Value::Number(n) = value else {
+        return None;
+    };
+    let literal = n.to_string();
+    source::js_rounded(&literal).map(|_| literal)
+}
+
+fn child_path(parent: &str, key: Key) -> String {
+    match key {
+        Key::Index(idx) => format!("{parent}[{idx}]"),
+        Key::Field(name) if !parent.is_empty() => format!("{parent}.{name}"),
+        Key::Field(name) => name.to_string(),
+        Key::Root => parent.to_string(),
+    }
+}
+
+fn child_segments(parent: &[Segment], segment: Segment) -> Vec<Segment> {
+    let mut segments = Vec::with_capacity(parent.len() + 1);
+    segments.extend_from_slice(parent);
+    segments.push(segment);
+    segments
+}
+
+/// The value at the display path `path`. Display paths join field names
+/// with `.` unescaped, so this walks the document naming children the way
+/// the diff does rather than splitting the path.
+pub(crate) fn value_at<'a>(root: &'a Value, path: &str) -> Option<&'a Value> {
+    fn walk<'a>(node: Located<'a>, path: &str) -> Option<&'a Value> {
+        if node.path == path {
+            return Some(node.value);
+        }
+        children(&node)
+            .into_iter()
+            .filter(|child| {
+                path.strip_prefix(child.path.as_str())
+                    .is_some_and(|rest| rest.is_empty() || rest.starts_with(['.', '[']))
+            })
+            .find_map(|child| walk(child, path))
+    }
+    walk(Located::root(root), path)
+}
+
+/// Children of a container in document order.
+fn children<'a>(node: &Located<'a>) -> Vec<Located<'a>> {
+    match node.value {
+        Value::Object(map) => {
+            let len = map.len();
+            map.iter()
+                .enumerate()
+                .map(|(idx, (name, value))| Located {
+                    value,
+                    key: Key::Field(name),
+                    path: child_path(&node.path, Key::Field(name)),
+                    segments: child_segments(&node.segments, Segment::Field(name.clone())),
+                    is_last: idx + 1 == len,
+                })
+                .collect()
+        }
+        Value::Array(items) => {
+            let len = items.len();
+            items
+                .iter()
+                .enumerate()
+                .map(|(idx, value)| Located {
+                    value,
+                    key: Key::Index(idx),
+                    path: child_path(&node.path, Key::Index(idx)),
+                    segments: child_segments(&node.segments, Segment::Index(idx)),
+                    is_last: idx + 1 == len,
+                })
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+struct Differ<'o, 'v> {
+    options: &'o DiffOptions,
+    /// Roots of removed and added subtrees, collected for move detection.
+    removed: RefCell<Vec<Unpaired<'v>>>,
+    added: RefCell<Vec<Unpaired<'v>>>,
+}
+
+struct Unpaired<'v> {
+    node: Located<'v>,
+    depth: usize,
+}
+
+impl<'v> Differ<'_, 'v> {
+    /// Children of a container minus the ignored ones.
+    fn children<'a>(&self, node: &Located<'a>) -> Vec<Located<'a>> {
+        let mut children = children(node);
+        if self.options.ignore_paths.is_empty() {
+            return children;
+        }
+        children.retain(|child| {
+            !self
+                .options
+                .ignore_paths
+                .iter()
+                .any(|p| p.matches(&child.segments))
+        });
+        if let Some(last) = children.last_mut() {
+            last.is_last = true;
+        }
+        children
+    }
+
+    /// Builds a one-sided subtree where every node is added or removed.
+    fn one_sided(&self, node: &Located, depth: usize, diff_type: DiffType) -> DiffNode {
+        let child_diffs = match node.value {
+            Value::Object(_) | Value::Array(_) => Some(
+                self.children(node)
+                    .iter()
+                    .map(|child| self.one_sided(child, depth + 1, diff_type))
+                    .collect(),
+            ),
+            _ => None,
+        };
+        let side = Some(side_node(node, depth));
+        let (left_node, right_node) = match diff_type {
+            DiffType::Removed => (side, None),
+            _ => (None, side),
+        };
+        DiffNode {
+            path: node.path.clone(),
+            left_node,
+            right_node,
+            diff_type,
+            depth,
+            is_collapsible: node_type(node.value) != NodeType::Primitive,
+            child_diffs,
+            equivalence: None,
+            moved_from: None,
+            moved_to: None,
+        }
+    }
+
+    fn compare(
+        &self,
+        left: Option<Located<'v>>,
+        right: Option<Located<'v>>,
+        depth: usize,
+    ) -> DiffNode {
+        let null_equals_missing = self.options.equivalence.null_equals_missing;
+        let (left, right) = match (left, right) {
+            (None, Some(right)) if null_equals_missing && right.value.is_null() => {
+                return self.null_missing(&right, depth, DiffType::Added);
+            }
+            (Some(left), None) if null_equals_missing && left.value.is_null() => {
+                return self.null_missing(&left, depth, DiffType::Removed);
+            }
+            (None, Some(right)) => {
+                let diff = self.one_sided(&right, depth, DiffType::Added);
+                self.track_unpaired(&self.added, right, depth);
+                return diff;
+            }
+            (Some(left), None) => {
+                let diff = self.one_sided(&left, depth, DiffType::Removed);
+                self.track_unpaired(&self.removed, left, depth);
+                return diff;
+            }
+            (Some(left), Some(right)) => (left, right),
+            (None, None) => {
+                return DiffNode {
+                    path: String::new(),
+                    left_node: None,
+                    right_node: None,
+                    diff_type: DiffType::Unchanged,
+                    depth,
+                    is_collapsible: false,
+                    child_diffs: None,
+                    equivalence: None,
+                    moved_from: None,
+                    moved_to: None,
+                }
+            }
+        };
+
+        let left_type = node_type(left.value);
+        let leaf = |diff_type, equivalence| DiffNode {
+            path: left.path.clone(),
+            left_node: Some(side_node(&left, depth)),
+            right_node: Some(side_node(&right, depth)),
+            diff_type,
+            depth,
+            is_collapsible: false,
+            child_diffs: None,
+            equivalence,
+            moved_from: None,
+            moved_to: None,
+        };
+
+        if left_type == NodeType::Primitive && values_equal(left.value, right.value) {
+            return leaf(DiffType::Unchanged, None);
+        }
+        if left_type == NodeType::Primitive || left_type != node_type(right.value) {
+            return match self.options.equivalence.check(left.value, right.value) {
+                Some(rule) => leaf(DiffType::Equivalent, Some(rule)),
+                None => leaf(DiffType::Changed, None),
+            };
+        }
+
+        let child_diffs = match left_type {
+            NodeType::Array => match self.array_key(&left.segments) {
+                Some(key) => self.match_children_by_key(&left, &right, key, depth),
+                None if self.is_unordered(&left.segments) => {
+                    self.match_children_as_multiset(&left, &right, depth)
+                }
+                None if self.options.array_mode == ArrayMode::Lcs => {
+                    self.align_children(&left, &right, depth)
+                }
+                None => self.compare_children(&left, &right, depth),
+            },
+            _ => self.compare_children(&left, &right, depth),
+        };
+        DiffNode {
+            path: left.path.clone(),
+            left_node: Some(side_node(&left, depth)),
+            right_node: Some(side_node(&right, depth)),
+            diff_type: container_diff_type(&child_diffs),
+            depth,
+            is_collapsible: true,
+            child_diffs: Some(child_diffs),
+            equivalence: None,
+            moved_from: None,
+            moved_to: None,
+        }
+    }
+
+    /// An explicit `null` on one side against nothing on the other.
+    fn null_missing(&self, node: &Located, depth: usize, side: DiffType) -> DiffNode {
+        DiffNode {
+            diff_type: DiffType::Equivalent,
+            equivalence: Some(Equivalence::NullMissing),
+            ..self.one_sided(node, depth, side)
+        }
+    }
+
+    /// Pairs children by key (object field or array index): left keys in
+    /// order, then keys that only exist on the right.
+    fn compare_children(
+        &self,
+        left: &Located<'v>,
+        right: &Located<'v>,
+        depth: usize,
+    ) -> Vec<DiffNode> {
+        let left_children = self.children(left);
+        let right_children = self.children(right);
+        let left_keys: HashSet<Key> = left_children.iter().map(|c| c.key).collect();
+        let mut right_by_key: HashMap<Key, Located> =
+            right_children.iter().map(|c| (c.key, c.clone())).collect();
+
+        let mut diffs = Vec::with_capacity(left_children.len().max(right_children.len()));
+        for l in &left_children {
+            let r = right_by_key.remove(&l.key);
+            diffs.push(self.compare(Some(l.clone()), r, depth + 1));
+        }
+        for r in right_children
+            .into_iter()
+            .filter(|r| !left_keys.contains(&r.key))
+        {
+            diffs.push(self.compare(None, Some(r), depth + 1));
+        }
+        diffs
+    }
+
+    /// Aligns array elements by content so that an insertion reports one
+    /// added element instead of shifting every element after it. Elements
+    /// inside a replaced run are paired up in order and compared, the
+    /// surplus on either side is reported as removed or added.
+    fn align_children(
+        &self,
+        left: &Located<'v>,
+        right: &Located<'v>,
+        depth: usize,
+    ) -> Vec<DiffNode> {
+        let left_children = self.children(left);
+        let right_children = self.children(right);
+        let left_hashes: Vec<u64> = left_children
+            .iter()
+            .map(|c| content_hash(c.value))
+            .collect();
+        let right_hashes: Vec<u64> = right_children
+            .iter()
+            .map(|c| content_hash(c.value))
+            .collect();
+
+        let mut diffs = Vec::with_capacity(left_children.len().max(right_children.len()));
+        for op in similar::capture_diff_slices(Algorithm::Myers, &left_hashes, &right_hashes) {
+            let (tag, old, new) = op.as_tag_tuple();
+            match tag {
+                DiffTag::Equal | DiffTag::Replace => {
+                    let paired = old.len().min(new.len());
+                    for (l, r) in old.clone().zip(new.clone()) {
+                        let (l, r) = (left_children[l].clone(), right_children[r].clone());
+                        diffs.push(self.compare(Some(l), Some(r), depth + 1));
+                    }
+                    for l in old.skip(paired) {
+                        diffs.push(self.compare(Some(left_children[l].clone()), None, depth + 1));
+                    }
+                    for r in new.skip(paired) {
+                        diffs.push(self.compare(None, Some(right_children[r].clone()), depth + 1));
+                    }
+                }
+                DiffTag::Delete => {
+                    for l in old {
+                        diffs.push(self.compare(Some(left_children[l].clone()), None, depth + 1));
+                    }
+                }
+                DiffTag::Insert => {
+                    for r in new {
+                        diffs.push(self.compare(None, Some(right_children[r].clone()), depth + 1));
+                    }
+                }
+            }
+        }
+        diffs
+    }
+
+    /// Identity key configured for the elements of the array at `path`.
+    fn array_key(&self, path: &[Segment]) -> Option<&str> {
+        if self.options.array_keys.is_empty() {
+            return None;
+        }
+        let element = child_segments(path, Segment::Index(0));
+        self.options
+            .array_keys
+            .iter()
+            .find(|rule| rule.path.matches(&element))
+            .map(|rule| rule.key.as_str())
+    }
+
+    /// Whether the array at `path` is compared as a multiset.
+    fn is_unordered(&self, path: &[Segment]) -> bool {
+        if self.options.array_mode == ArrayMode::Multiset {
+            return true;
+        }
+        self.options.unordered_paths.iter().any(|p| p.matches(path))
+    }
+
+    /// Pairs array elements with equal content regardless of position. The
+    /// left elements come first in their order, then what only the right
+    /// side has.
+    fn match_children_as_multiset(
+        &self,
+        left: &Located<'v>,
+        right: &Located<'v>,
+        depth: usize,
+    ) -> Vec<DiffNode> {
+        let left_children = self.children(left);
+        let mut right_children: Vec<Option<Located>> =
+            self.children(right).into_iter().map(Some).collect();
+
+        let mut right_by_hash: HashMap<u64, VecDeque<usize>> = HashMap::new();
+        for (idx, child) in right_children.iter().flatten().enumerate() {
+            right_by_hash
+                .entry(content_hash(child.value))
+                .or_default()
+                .push_back(idx);
+        }
+        let mut diffs = Vec::with_capacity(left_children.len().max(right_children.len()));
+        for l in left_children {
+            let r = right_by_hash
+                .get_mut(&content_hash(l.value))
+                .and_then(VecDeque::pop_front)
+                .and_then(|r| right_children[r].take());
+            diffs.push(self.compare(Some(l), r, depth + 1));
+        }
+        for r in right_children.into_iter().flatten() {
+            diffs.push(self.compare(None, Some(r), depth + 1));
+        }
+        diffs
+    }
+
+    /// Pairs array elements by the value of their `key` field. Elements
+    /// without that field are paired by identical content instead. Pairs
+    /// that are equal but out of order are reported as moved.
+    fn match_children_by_key(
+        &self,
+        left: &Located<'v>,
+        right: &Located<'v>,
+        key: &str,
+        depth: usize,
+    ) -> Vec<DiffNode> {
+        let identity = |value: &Value| match value.get(key) {
+            Some(id) => (true, content_hash(id)),
+            None => (false, content_hash(value)),
+        };
+        let left_children = self.children(left);
+        let mut right_children: Vec<Option<Located>> =
+            self.children(right).into_iter().map(Some).collect();
+
+        let mut right_by_id: HashMap<(bool, u64), VecDeque<usize>> = HashMap::new();
+        for (idx, child) in right_children.iter().flatten().enumerate() {
+            right_by_id
+                .entry(identity(child.value))
+                .or_default()
+                .push_back(idx);
+        }
+        let pairs: Vec<Option<usize>> = left_children
+            .iter()
+            .map(|l| right_by_id.get_mut(&identity(l.value))?.pop_front())
+            .collect();
+        let in_order = longest_increasing(&pairs);
+
+        let mut diffs = Vec::with_capacity(left_children.len().max(right_children.len()));
+        for (l, (pair, in_order)) in left_children.into_iter().zip(pairs.iter().zip(in_order)) {
+            let r = pair.and_then(|r| right_children[r].take());
+            let mut diff = self.compare(Some(l), r, depth + 1);
+            if !in_order && diff.diff_type == DiffType::Unchanged {
+                diff.diff_type = DiffType::Moved;
+            }
+            diffs.push(diff);
+        }
+        for r in right_children.into_iter().flatten() {
+            diffs.push(self.compare(None, Some(r), depth + 1));
+        }
+        diffs
+    }
+
+    fn track_unpaired(&self, list: &RefCell<Vec<Unpaired<'v>>>, node: Located<'v>, depth: usize) {
+        if self.options.detect_moves {
+            list.borrow_mut().push(Unpaired { node, depth });
+        }
+    }
+
+    /// Turns matching removed/added subtrees of `root` into linked moved
+    /// rows. The old location keeps its content and points at the new one;
+    /// the new location compares the old content against the new.
+    fn pair_moves(&self, root: &mut DiffNode) {
+        let removed = self.removed.take();
+        let added = self.added.take();
+        if removed.is_empty() || added.is_empty() {
+            return;
+        }
+        // Comparing the pairs finds removals and additions of their own,
+        // which are part of the moved subtrees rather than moves.
+        let differ = Differ {
+            options: self.options,
+            removed: RefCell::default(),
+            added: RefCell::default(),
+        };
+        let mut sources = HashMap::new();
+        let mut destinations = HashMap::new();
+        for (r, a) in match_moves(&removed, &added) {
+            let (from, to) = (&removed[r], &added[a]);
+            let mut node = differ.compare(Some(from.node.clone()), Some(to.node.clone()), to.depth);
+            rebase_paths(&mut node, &from.node.path, &to.node.path);
+            node.diff_type = DiffType::Moved;
+            node.moved_from = Some(from.node.path.clone());
+            sources.insert(from.node.path.clone(), to.node.path.clone());
+            destinations.insert(to.node.path.clone(), node);
+        }
+        link_moves(root, &mut sources, &mut destinations);
+    }
+}
+
+/// Minimum Dice similarity of their leaves for two subtrees that aren't
+/// identical to still count as one moved subtree. Same threshold git uses
+/// for renames.
+const MOVE_SIMILARITY: f64 = 0.5;
+
+/// Pairs removed with added subtrees, identical content first and then the
+/// most similar containers. Returns `(removed, added)` index pairs.
+///
+/// `null` and booleans never pair up, and other scalars and empty
+/// containers only when their value is removed and added exactly once:
+/// anything else would link unrelated entries that happen to share a value.
+/// Neither do entries removed and added at the same path, as an array
+/// element replaced in place is when array elements are aligned or keyed.
+fn match_moves(removed: &[Unpaired], added: &[Unpaired]) -> Vec<(usize, usize)> {
+    let same_path = |r: usize, a: usize| removed[r].node.path == added[a].node.path;
+    let mut pairs = Vec::new();
+    let mut removed_free = vec![true; removed.len()];
+    let mut added_free = vec![true; added.len()];
+
+    let mut by_hash: HashMap<u64, (Vec<usize>, Vec<usize>)> = HashMap::new();
+    for (idx, entry) in removed.iter().enumerate() {
+        by_hash
+            .entry(content_hash(entry.node.value))
+            .or_default()
+            .0
+            .push(idx);
+    }
+    for (idx, entry) in added.iter().enumerate() {
+        by_hash
+            .entry(content_hash(entry.node.value))
+            .or_default()
+            .1
+            .push(idx);
+    }
+    for (removed_idx, added_idx) in by_hash.values() {
+        let (Some(&first), false) = (removed_idx.first(), added_idx.is_empty()) else {
+            continue;
+        };
+        let unique = removed_idx.len() == 1 && added_idx.len() == 1;
+        let movable = match removed[first].node.value {
+            Value::Null | Value::Bool(_) => false,
+            Value::Object(map) if !map.is_empty() => true,
+            Value::Array(items) if !items.is_empty() => true,
+            _ => unique,
+        };
+        if !movable {
+            continue;
+        }
+        for (&r, &a) in removed_idx.iter().zip(added_idx) {
+            if same_path(r, a) {
+                continue;
+            }
+            pairs.push((r, a));
+            removed_free[r] = false;
+            added_free[a] = false;
+        }
+    }
+
+    let is_container = |value: &Value| match value {
+        Value::Object(map) => !map.is_empty(),
+        Value::Array(items) => !items.is_empty(),
+        _ => false,
+    };
+    let mut leaf_index: HashMap<u64, Vec<(usize, usize)>> = HashMap::new();
+    let mut added_sizes = vec![0; added.len()];
+    for (a, entry) in added.iter().enumerate() {
+        if !added_free[a] || !is_container(entry.node.value) {
+            continue;
+        }
+        for (leaf, count) in leaf_signature(entry.node.value) {
+            added_sizes[a] += count;
+            leaf_index.entry(leaf).or_default().push((a, count));
+        }
+    }
+    let mut candidates = Vec::new();
+    for (r, entry) in removed.iter().enumerate() {
+        if !removed_free[r] || !is_container(entry.node.value) {
+            continue;
+        }
+        let signature = leaf_signature(entry.node.value);
+        let size: usize = signature.values().sum();
+        let mut shared: HashMap<usize, usize> = HashMap::new();
+        for (leaf, count) in &signature {
+            for &(a, other) in leaf_index.get(leaf).into_iter().flatten() {
+                *shared.entry(a).or_default() += (*count).min(other);
+            }
+        }
+        let kind = node_type(entry.node.value);
+        for (a, shared) in shared {
+            let score = 2.0 * shared as f64 / (size + added_sizes[a]) as f64;
+            if score >= MOVE_SIMILARITY
+                && node_type(added[a].node.value) == kind
+                && !same_path(r, a)
+            {
+                candidates.push((score, r, a));
+            }
+        }
+    }
+    candidates.sort_by(|x, y| y.0.total_cmp(&x.0).then((x.1, x.2).cmp(&(y.1, y.2))));
+    for (_, r, a) in candidates {
+        if removed_free[r] && added_free[a] {
+            pairs.push((r, a));
+            removed_free[r] = false;
+            added_free[a] = false;
+        }
+    }
+    pairs
+}
+
+/// Hashes of every leaf (scalar or empty container) of `value` together
+/// with its path relative to `value`, counted.
+fn leaf_signature(value: &Value) -> HashMap<u64, usize> {
+    fn walk(value: &Value, path: &mut String, leaves: &mut HashMap<u64, usize>) {
+        let len = path.len();
+        match value {
+            Value::Object(map) if !map.is_empty() => {
+                for (name, child) in map {
+                    path.push('.');
+                    path.push_str(name);
+                    walk(child, path, leaves);
+                    path.truncate(len);
+                }
+            }
+            Value::Array(items) if !items.is_empty() => {
+                for (idx, child) in items.iter().enumerate() {
+                    path.push_str(&format!("[{idx}]"));
+                    walk(child, path, leaves);
+                    path.truncate(len);
+                }
+            }
+            leaf => {
+                let mut state = DefaultHasher::new();
+                (path.as_str(), content_hash(leaf)).hash(&mut state);
+                *leaves.entry(state.finish()).or_default() += 1;
+            }
+        }
+    }
+    let mut leaves = HashMap::new();
+    walk(value, &mut String::new(), &mut leaves);
+    leaves
+}
+
+/// Moves the paths of rows compared from the left side from under `from` to
+/// under `to`, so the rows of a moved subtree sit at its new location.
+fn rebase_paths(node: &mut DiffNode, from: &str, to: &str) {
+    if node.left_node.is_some() {
+        if let Some(rest) = node.path.strip_prefix(from) {
+            node.path = format!("{to}{rest}");
+        }
+    }
+    for child in node.child_diffs.iter_mut().flatten() {
+        rebase_paths(child, from, to);
+    }
+}
+
+/// Marks the rows of paired subtrees: removed roots in `sources` become
+/// moved and point at their destination, added roots are replaced by the
+/// comparison from `destinations`.
+fn link_moves(
+    node: &mut DiffNode,
+    sources: &mut HashMap<String, String>,
+    destinations: &mut HashMap<String, DiffNode>,
+) {
+    match node.diff_type {
+        DiffType::Removed => {
+            if let Some(to) = sources.remove(&node.path) {
+                mark_moved(node);
+                node.moved_to = Some(to);
+            }
+        }
+        DiffType::Added => {
+            if let Some(destination) = destinations.remove(&node.path) {
+                *node = destination;
+            }
+        }
+        _ => {
+            for child in node.child_diffs.iter_mut().flatten() {
+                if sources.is_empty() && destinations.is_empty() {
+                    return;
+                }
+                link_moves(child, sources, destinations);
+            }
+        }
+    }
+}
+
+fn mark_moved(node: &mut DiffNode) {
+    node.diff_type = DiffType::Moved;
+    for child in node.child_diffs.iter_mut().flatten() {
+        mark_moved(child);
+    }
+}
+
+/// A container is changed if anything below it is, equivalent if its
+/// children only differ under equivalence rules, and unchanged otherwise.
+fn container_diff_type(child_diffs: &[DiffNode]) -> DiffType {
+    let mut diff_type = DiffType::Unchanged;
+    for child in child_diffs {
+        match child.diff_type {
+            DiffType::Unchanged => {}
+            DiffType::Equivalent => diff_type = DiffType::Equivalent,
+            _ => return DiffType::Changed,
+        }
+    }
+    diff_type
+}
+
+/// Marks which paired entries keep their relative order, i.e. belong to a
+/// longest increasing subsequence of right-hand indices. Everything else
+/// counts as moved.
+fn longest_increasing(pairs: &[Option<usize>]) -> Vec<bool> {
+    // Patience-style LIS: `tails[k]` is the position in `pairs` of the
+    // smallest tail of an increasing run of length `k + 1`.
+    let mut tails: Vec<usize> = Vec::new();
+    let mut prev: Vec<Option<usize>> = vec![None; pairs.len()];
+    for (pos, value) in pairs.iter().enumerate() {
+        let Some(value) = value else { continue };
+        let k = tails.partition_point(|&t| pairs[t].is_some_and(|t| t < *value));
+        if k > 0 {
+            prev[pos] = Some(tails[k - 1]);
+        }
+        if k == tails.len() {
+            tails.push(pos);
+        } else {
+            tails[k] = pos;
+        }
+    }
+    let mut keep = vec![false; pairs.len()];
+    let mut cursor = tails.last().copied();
+    while let Some(pos) = cursor {
+        keep[pos] = true;
+        cursor = prev[pos];
+    }
+    keep
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn diff(left: &str, right: &str, options: &DiffOptions) -> DiffNode {
+        let left: Value = serde_json::from_str(left).unwrap();
+        let right: Value = serde_json::from_str(right).unwrap();
+        diff_values(Some(&left), Some(&right), options).unwrap()
+    }
+
+    fn find<'n>(node: &'n DiffNode, path: &str) -> Option<&'n DiffNode> {
+        if node.path == path {
+            return Some(node);
+        }
+        node.child_diffs
+            .iter()
+            .flatten()
+            .find_map(|child| find(child, path))
+    }
+
+    #[test]
+    fn integers_beyond_2_53_compare_exactly() {
+        let node = diff(
+            r#"{"id": 9007199254740993}"#,
+            r#"{"id": 9007199254740992}"#,
+            &DiffOptions::default(),
+        );
+        let id = find(&node, "id").unwrap();
+        assert_eq!(id.diff_type, DiffType::Changed);
+        let left = id.left_node.as_ref().unwrap();
+        assert_eq!(left.raw.as_deref(), Some("9007199254740993"));
+        assert_eq!(id.right_node.as_ref().unwrap().raw, None);
+    }
+
+    #[test]
+    fn numbers_compare_by_value() {
+        let node = diff(
+            r#"{"a": 1.50, "b": 1, "c": 100, "d": 0.0, "e": 123456789012345678901234567890}"#,
+            r#"{"a": 1.5, "b": 1.0, "c": 1e2, "d": -0, "e": 1.2345678901234567890123456789e29}"#,
+            &DiffOptions::default(),
+        );
+        assert_eq!(node.diff_type, DiffType::Unchanged);
+        assert!(values_equal(
+            &json!([1, {"x": 2.0}]),
+            &json!([1.0, {"x": 2}])
+        ));
+        assert!(!values_equal(&json!({"x": 1}), &json!({"x": 1, "y": 1})));
+        assert_eq!(content_hash(&json!([1.50])), content_hash(&json!([1.5])));
+    }
+
+    /// Path and kind of each direct child row.
+    fn rows(node: &DiffNode) -> Vec<(&str, DiffType)> {
+        node.child_diffs
+            .iter()
+            .flatten()
+            .map(|child| (child.path.as_str(), child.diff_type))
+            .collect()
+    }
+
+    fn keyed(key: &str) -> DiffOptions {
+        DiffOptions {
+            array_keys: vec![ArrayKey {
+                path: PathPattern::parse("[*]").unwrap(),
+                key: key.to_string(),
+            }],
+            ..DiffOptions::default()
+        }
+    }
+
+    #[test]
+    fn keyed_elements_pair_by_id() {
+        let node = diff(
+            r#"[{"id": 1, "v": "a"}, {"id": 2, "v": "b"}, {"id": 3, "v": "c"}]"#,
+            r#"[{"id": 3, "v": "c"}, {"id": 1, "v": "a"}, {"id": 2, "v": "B"}]"#,
+            &keyed("id"),
+        );
+        assert_eq!(
+            rows(&node),
+            [
+                ("[0]", DiffType::Unchanged),
+                ("[1]", DiffType::Changed),
+                ("[2]", DiffType::Moved),
+            ]
+        );
+        assert_eq!(find(&node, "[1].v").unwrap().diff_type, DiffType::Changed);
+    }
+
+    #[test]
+    fn elements_without_the_key_pair_by_content() {
+        let node = diff(
+            r#"[{"id": 1}, {"x": 1}, {"x": 2}]"#,
+            r#"[{"id": 1}, {"x": 2}, {"x": 3}]"#,
+            &keyed("id"),
+        );
+        assert_eq!(
+            rows(&node),
+            [
+                ("[0]", DiffType::Unchanged),
+                ("[1]", DiffType::Removed),
+                ("[2]", DiffType::Unchanged),
+                ("[2]", DiffType::Added),
+            ]
+        );
+        // A key present on one side only doesn't pair either.
+        let node = diff(r#"[{"id": 1, "x": 1}]"#, r#"[{"x": 1}]"#, &keyed("id"));
+        assert_eq!(
+            rows(&node),
+            [("[0]", DiffType::Removed), ("[0]", DiffType::Added)]
+        );
+    }
+
+    #[test]
+    fn duplicate_keys_pair_in_order() {
+        let node = diff(
+            r#"[{"id": 1, "v": "a"}, {"id": 1, "v": "b"}]"#,
+            r#"[{"id": 1, "v": "a"}, {"id": 1, "v": "c"}, {"id": 1, "v": "d"}]"#,
+            &keyed("id"),
+        );
+        assert_eq!(
+            rows(&node),
+            [
+                ("[0]", DiffType::Unchanged),
+                ("[1]", DiffType::Changed),
+                ("[2]", DiffType::Added),
+            ]
+        );
+        assert_eq!(find(&node, "[1].v").unwrap().diff_type, DiffType::Changed);
+    }
+
+    #[test]
+    fn multisets_ignore_order_but_count_duplicates() {
+        let options = DiffOptions {
+            array_mode: ArrayMode::Multiset,
+            ..DiffOptions::default()
+        };
+        let node = diff("[1, 2, 2, 3]", "[3, 2, 1, 4]", &options);
+        assert_eq!(
+            rows(&node),
+            [
+                ("[0]", DiffType::Unchanged),
+                ("[1]", DiffType::Unchanged),
+                ("[2]", DiffType::Removed),
+                ("[3]", DiffType::Unchanged),
+                ("[3]", DiffType::Added),
+            ]
+        );
+        let node = diff(
+            r#"[{"a": 1}, {"b": [2]}]"#,
+            r#"[{"b": [2]}, {"a": 1}]"#,
+            &options,
+        );
+        assert_eq!(node.diff_type, DiffType::Unchanged);
+        // Changed elements don't pair: one removed, one added.
+        let node = diff(r#"[{"a": 1}]"#, r#"[{"a": 2}]"#, &options);
+        assert_eq!(
+            rows(&node),
+            [("[0]", DiffType::Removed), ("[0]", DiffType::Added)]
+        );
+    }
+
+    #[test]
+    fn lcs_keeps_elements_after_an_insert_aligned() {
+        let options = DiffOptions {
+            array_mode: ArrayMode::Lcs,
+            ..DiffOptions::default()
+        };
+        let node = diff(r#"[1, 2, {"a": 3}]"#, r#"[0, 1, 2, {"a": 3}]"#, &options);
+        assert_eq!(
+            rows(&node),
+            [
+                ("[0]", DiffType::Added),
+                ("[0]", DiffType::Unchanged),
+                ("[1]", DiffType::Unchanged),
+                ("[2]", DiffType::Unchanged),
+            ]
+        );
+        // By index every element would shift onto its neighbour.
+        let node = diff(
+            r#"[1, 2, {"a": 3}]"#,
+            r#"[0, 1, 2, {"a": 3}]"#,
+            &DiffOptions::default(),
+        );
+        assert!(rows(&node)
+            .iter()
+            .all(|(_, kind)| *kind != DiffType::Unchanged));
+    }
+
+    /// How `{"v": left}` and `{"v": right}` compare at `v`.
+    fn compare_v(
+        left: &str,
+        right: &str,
+        rules: &EquivalenceRules,
+    ) -> (DiffType, Option<Equivalence>) {
+        let options = DiffOptions {
+            equivalence: rules.clone(),
+            ..DiffOptions::default()
+        };
+        let node = diff(
+            &format!(r#"{{"v": {left}}}"#),
+            &format!(r#"{{"v": {right}}}"#),
+            &options,
+        );
+        let v = find(&node, "v").unwrap();
+        (v.diff_type, v.equivalence)
+    }
+
+    #[test]
+    fn numbers_within_tolerance_are_equivalent() {
+        let equivalent = (DiffType::Equivalent, Some(Equivalence::Tolerance));
+        let changed = (DiffType::Changed, None);
+        let absolute = EquivalenceRules {
+            absolute_tolerance: Some(0.01),
+            ..EquivalenceRules::default()
+        };
+        assert_eq!(compare_v("1.0", "1.005", &absolute), equivalent);
+        assert_eq!(compare_v("-2", "-2.01", &absolute), equivalent);
+        assert_eq!(compare_v("1.0", "1.02", &absolute), changed);
+        let relative = EquivalenceRules {
+            relative_tolerance: Some(0.01),
+            ..EquivalenceRules::default()
+        };
+        assert_eq!(compare_v("1000", "1009", &relative), equivalent);
+        assert_eq!(compare_v("1000", "1011", &relative), changed);
+        // Neither rule set: any difference counts.
+        assert_eq!(
+            compare_v("1.0", "1.005", &EquivalenceRules::default()),
+            changed
+        );
+    }
+
+    #[test]
+    fn numeric_strings_coerce_to_numbers() {
+        let rules = EquivalenceRules {
+            coerce_string_numbers: true,
+            ..EquivalenceRules::default()
+        };
+        let equivalent = (DiffType::Equivalent, Some(Equivalence::Coercion));
+        assert_eq!(compare_v(r#""42""#, "42", &rules), equivalent);
+        assert_eq!(compare_v("42", r#"" 42.0 ""#, &rules), equivalent);
+        assert_eq!(
+            compare_v(r#""42""#, "43", &rules),
+            (DiffType::Changed, None)
+        );
+        assert_eq!(
+            compare_v(r#""forty-two""#, "42", &rules),
+            (DiffType::Changed, None)
+        );
+        assert_eq!(
+            compare_v(r#""42""#, "42", &EquivalenceRules::default()),
+            (DiffType::Changed, None)
+        );
+    }
+
+    #[test]
+    fn strings_equal_up_to_case_or_whitespace_are_equivalent() {
+        let case = EquivalenceRules {
+            case_insensitive: true,
+            ..EquivalenceRules::default()
+        };
+        assert_eq!(
+            compare_v(r#""Ready""#, r#""READY""#, &case),
+            (DiffType::Equivalent, Some(Equivalence::CaseInsensitive))
+        );
+        assert_eq!(
+            compare_v(r#""Ready""#, r#""Read""#, &case),
+            (DiffType::Changed, None)
+        );
+        assert_eq!(
+            compare_v(r#""a b""#, r#""a  b""#, &case),
+            (DiffType::Changed, None)
+        );
+
+        let whitespace = EquivalenceRules {
+            collapse_whitespace: true,
+            ..EquivalenceRules::default()
+        };
+        assert_eq!(
+            compare_v(r#""a b""#, r#""\n  a \t b ""#, &whitespace),
+            (DiffType::Equivalent, Some(Equivalence::Whitespace))
+        );
+        assert_eq!(
+            compare_v(r#""a b""#, r#""ab""#, &whitespace),
+            (DiffType::Changed, None)
+        );
+        assert_eq!(
+            compare_v(r#""a b""#, r#""A b""#, &whitespace),
+            (DiffType::Changed, None)
+        );
+    }
+
+    #[test]
+    fn null_and_missing_are_equivalent() {
+        let options = DiffOptions {
+            equivalence: EquivalenceRules {
+                null_equals_missing: true,
+                ..EquivalenceRules::default()
+            },
+            ..DiffOptions::default()
+        };
+        let node = diff(r#"{"a": 1, "b": null}"#, r#"{"a": 1, "c": null}"#, &options);
+        assert_eq!(node.diff_type, DiffType::Equivalent);
+        for path in ["b", "c"] {
+            let row = find(&node, path).unwrap();
+            assert_eq!(row.diff_type, DiffType::Equivalent);
+            assert_eq!(row.equivalence, Some(Equivalence::NullMissing));
+        }
+        // Only null stands in for a missing entry.
+        let node = diff(r#"{"b": 0}"#, "{}", &options);
+        assert_eq!(node.diff_type, DiffType::Changed);
+        assert_eq!(find(&node, "b").unwrap().diff_type, DiffType::Removed);
+        let node = diff(r#"{"b": null}"#, "{}", &DiffOptions::default());
+        assert_eq!(find(&node, "b").unwrap().diff_type, DiffType::Removed);
+    }
+
+    #[test]
+    fn patterns_see_field_names_holding_dots() {
+        let left = r#"{"meta": {"k8s.io/last": "x", "a": {"b": 1}}, "l": {"x.y": [{"id": 1, "v": 1}, {"id": 2, "v": 1}]}}"#;
+        let right = r#"{"meta": {"k8s.io/last": "y", "a": {"b": 1}}, "l": {"x.y": [{"id": 2, "v": 1}, {"id": 1, "v": 1}]}}"#;
+        let ignoring = |pattern: &str| DiffOptions {
+            ignore_paths: vec![PathPattern::parse(pattern).unwrap()],
+            ..DiffOptions::default()
+        };
+        for pattern in [
+            r#"meta["k8s.io/last"]"#,
+            r#"$..["k8s.io/last"]"#,
+            "meta.*/last",
+        ] {
+            let node = diff(left, right, &ignoring(pattern));
+            assert_eq!(
+                find(&node, "meta").unwrap().diff_type,
+                DiffType::Unchanged,
+                "{pattern}"
+            );
+        }
+        // The display path doesn't name the field.
+        let node = diff(left, right, &ignoring("meta.k8s.io/last"));
+        assert_eq!(
+            find(&node, "meta.k8s.io/last").unwrap().diff_type,
+            DiffType::Changed
+        );
+
+        let options = DiffOptions {
+            array_keys: vec![ArrayKey {
+                path: PathPattern::parse(r#"l["x.y"][*]"#).unwrap(),
+                key: "id".to_string(),
+            }],
+            ..DiffOptions::default()
+        };
+        let node = diff(left, right, &options);
+        // Paired by id, the swap is one element moved past the other.
+        let mut kinds: Vec<DiffType> = rows(find(&node, "l.x.y").unwrap())
+            .into_iter()
+            .map(|(_, kind)| kind)
+            .collect();
+        kinds.sort_by_key(|kind| *kind == DiffType::Moved);
+        assert_eq!(kinds, [DiffType::Unchanged, DiffType::Moved]);
+        let options = DiffOptions {
+            unordered_paths: vec![PathPattern::parse(r#"l["x.y"]"#).unwrap()],
+            ..DiffOptions::default()
+        };
+        assert_eq!(
+            find(&diff(left, right, &options), "l").unwrap().diff_type,
+            DiffType::Unchanged
+        );
+    }
+
+    fn moves(options: &DiffOptions) -> DiffOptions {
+        DiffOptions {
+            detect_moves: true,
+            ..options.clone()
+        }
+    }
+
+    /// `(from, to)` of every moved row that says where it came from.
+    fn moved(node: &DiffNode) -> Vec<(String, String)> {
+        let mut found = Vec::new();
+        if let Some(from) = &node.moved_from {
+            found.push((from.clone(), node.path.clone()));
+        }
+        for child in node.child_diffs.iter().flatten() {
+            found.extend(moved(child));
+        }
+        found
+    }
+
+    #[test]
+    fn renamed_keys_are_moves() {
+        let options = moves(&DiffOptions::default());
+        let node = diff(
+            r#"{"old": {"a": 1, "b": [1, 2], "c": "x"}, "keep": 1}"#,
+            r#"{"keep": 1, "new": {"a": 1, "b": [1, 2], "c": "y"}}"#,
+            &options,
+        );
+        assert_eq!(moved(&node), [("old".to_string(), "new".to_string())]);
+        let old = find(&node, "old").unwrap();
+        assert_eq!(
+            (old.diff_type, old.moved_to.as_deref()),
+            (DiffType::Moved, Some("new"))
+        );
+        // The moved subtree compares old against new content.
+        let new = find(&node, "new").unwrap();
+        assert_eq!(new.diff_type, DiffType::Moved);
+        assert_eq!(find(&node, "new.c").unwrap().diff_type, DiffType::Changed);
+        assert_eq!(find(&node, "new.a").unwrap().diff_type, DiffType::Unchanged);
+        // Nothing inside it is paired up on its own.
+        assert_eq!(moved(new).len(), 1);
+    }
+
+    #[test]
+    fn moves_need_half_the_leaves_in_common() {
+        let options = moves(&DiffOptions::default());
+        // Two of four leaves kept: Dice similarity 0.5.
+        let node = diff(
+            r#"{"a": {"w": 1, "x": 2, "y": 3, "z": 4}}"#,
+            r#"{"b": {"w": 1, "x": 2, "y": 0, "z": 0}}"#,
+            &options,
+        );
+        assert_eq!(moved(&node), [("a".to_string(), "b".to_string())]);
+        // One of four: 0.25.
+        let node = diff(
+            r#"{"a": {"w": 1, "x": 2, "y": 3, "z": 4}}"#,
+            r#"{"b": {"w": 1, "x": 0, "y": 0, "z": 0}}"#,
+            &options,
+        );
+        assert!(moved(&node).is_empty());
+        assert_eq!(find(&node, "a").unwrap().diff_type, DiffType::Removed);
+        // Repeated scalars don't pair up, unique ones do.
+        let node = diff(
+            r#"{"a": 5, "b": 5, "c": "s"}"#,
+            r#"{"x": 5, "y": 5, "z": "s"}"#,
+            &options,
+        );
+        assert_eq!(moved(&node), [("c".to_string(), "z".to_string())]);
+    }
+
+    #[test]
+    fn keyed_elements_move_between_arrays() {
+        let options = moves(&DiffOptions {
+            array_keys: vec![ArrayKey {
+                path: PathPattern::parse("$..[*]").unwrap(),
+                key: "id".to_string(),
+            }],
+            ..DiffOptions::default()
+        });
+        let node = diff(
+            r#"{"todo": [{"id": 1, "t": "a"}, {"id": 2, "t": "b"}], "done": []}"#,
+            r#"{"todo": [{"id": 2, "t": "b"}], "done": [{"id": 1, "t": "a"}]}"#,
+            &options,
+        );
+        assert_eq!(
+            moved(&node),
+            [("todo[0]".to_string(), "done[0]".to_string())]
+        );
+        // An element whose key changed in place is not a move.
+        let node = diff(
+            r#"{"l": [{"id": 1, "a": 1, "b": 2, "c": 3}]}"#,
+            r#"{"l": [{"id": 9, "a": 1, "b": 2, "c": 3}]}"#,
+            &options,
+        );
+        assert!(moved(&node).is_empty());
+        assert_eq!(find(&node, "l[0]").unwrap().diff_type, DiffType::Removed);
+    }
+
+    #[test]
+    fn aligned_elements_never_move_onto_their_own_path() {
+        let options = moves(&DiffOptions {
+            array_mode: ArrayMode::Lcs,
+            ..DiffOptions::default()
+        });
+        let left = r#"[{"n": "a", "v": 1}, {"n": "b", "v": 2}, {"n": "c", "v": 3}]"#;
+        let right = r#"[{"n": "c", "v": 3}, {"n": "b", "v": 2}, {"n": "a", "v": 1}]"#;
+        let node = diff(left, right, &options);
+        let pairs = moved(&node);
+        assert!(!pairs.is_empty());
+        assert!(pairs.iter().all(|(from, to)| from != to), "{pairs:?}");
+        // Every element is accounted for: moved or unchanged, nothing lost.
+        let children = node.child_diffs.as_ref().unwrap();
+        assert!(children.iter().all(|c| matches!(
+            c.diff_type,
+            DiffType::Moved | DiffType::Unchanged | DiffType::Added | DiffType::Removed
+        )));
+        let rotated = diff(
+            r#"[{"k": [1, 2]}, 1, 2]"#,
+            r#"[1, 2, {"k": [1, 2]}]"#,
+            &options,
+        );
+        assert_eq!(moved(&rotated), [("[0]".to_string(), "[2]".to_string())]);
+    }
+}
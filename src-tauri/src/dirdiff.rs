@@ -0,0 +1,340 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Serialize;
+
+use crate::diff::{self, DiffNode, DiffOptions, DiffStats, DiffType};
+use crate::document::{self, Format};
+
+/// A file or directory in the summary of a directory diff.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirNode {
+    pub name: String,
+    /// Relative to both roots, `/`-separated; empty for the roots.
+    pub path: String,
+    pub status: DiffType,
+    /// Summed over the files of a directory.
+    pub stats: DiffStats,
+    /// How a file was parsed; absent for directories.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub format: Option<Format>,
+    /// Why a file couldn't be compared.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    /// Subdirectories first, then files, each by name. Absent for files.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub children: Option<Vec<DirNode>>,
+}
+
+/// Walks both directories, pairs their JSON, YAML, TOML and XML files by
+/// relative path and diffs each pair. Files only on one side are diffed
+/// against nothing, so their stats count every leaf. Hidden files and
+/// directories are skipped.
+pub fn diff_dirs(left: &Path, right: &Path, options: &DiffOptions) -> Result<DirNode, String> {
+    let left_files = collect(left).map_err(|e| format!("left: {e}"))?;
+    let right_files = collect(right).map_err(|e| format!("right: {e}"))?;
+    let mut pairs: BTreeMap<&str, (Option<&PathBuf>, Option<&PathBuf>)> = BTreeMap::new();
+    for (rel, path) in &left_files {
+        pairs.entry(rel).or_default().0 = Some(path);
+    }
+    for (rel, path) in &right_files {
+        pairs.entry(rel).or_default().1 = Some(path);
+    }
+    let files = pairs
+        .into_iter()
+        .map(|(rel, (l, r))| (rel.to_string(), diff_file(rel, l, r, options)))
+        .collect();
+    Ok(tree(String::new(), String::new(), files))
+}
+
+/// Files the directory diff picks up, by extension.
+fn format_of(path: &Path) -> Option<Format> {
+    match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+        "json" | "jsonc" | "json5" => Some(Format::Json),
+        "yaml" | "yml" => Some(Format::Yaml),
+        "toml" => Some(Format::Toml),
+        "xml" | "pom" | "xsd" | "wsdl" => Some(Format::Xml),
+        _ => None,
+    }
+}
+
+/// Relative path to full path of every diffable file under `root`.
+/// Symlinked directories aren't followed, so links can't form a cycle.
+fn collect(root: &Path) -> Result<BTreeMap<String, PathBuf>, String> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![(root.to_path_buf(), String::new())];
+    while let Some((dir, rel)) = pending.pop() {
+        let entries = std::fs::read_dir(&dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        for entry in entries {
+            let entry = entry.map_err(|e| e.to_string())?;
+            let name = entry.file_name().to_string_lossy().into_owned();
+            if name.starts_with('.') {
+                continue;
+            }
+            let child_rel = if rel.is_empty() {
+                name
+            } else {
+                format!("{rel}/{name}")
+            };
+            let path = entry.path();
+            if entry.file_type().map_err(|e| e.to_string())?.is_dir() {
+                pending.push((path, child_rel));
+            } else if format_of(&path).is_some() {
+                files.insert(child_rel, path);
+            }
+        }
+    }
+    Ok(files)
+}
+
+fn diff_file(
+    rel: &str,
+    left: Option<&PathBuf>,
+    right: Option<&PathBuf>,
+    options: &DiffOptions,
+) -> DirNode {
+    let status = match (left, right) {
+        (None, _) => DiffType::Added,
+        (_, None) => DiffType::Removed,
+        _ => DiffType::Unchanged,
+    };
+    let format = left.or(right).and_then(|p| format_of(p));
+    let mut node = DirNode {
+        name: rel.rsplit('/').next().unwrap_or(rel).to_string(),
+        path: rel.to_string(),
+        status,
+        stats: DiffStats::default(),
+        format,
+        error: None,
+        children: None,
+    };
+    let Some(format) = format else {
+        return node;
+    };
+    match compare(left, right, format, options) {
+        Ok(Some(root)) => {
+            node.stats = root.stats();
+            if status == DiffType::Unchanged {
+                node.status = root.diff_type;
+            }
+        }
+        Ok(None) => {}
+        Err(e) => {
+            node.error = Some(e);
+            if status == DiffType::Unchanged {
+                node.status = DiffType::Changed;
+            }
+        }
+    }
+    node
+}
+
+fn compare(
+    left: Option<&PathBuf>,
+    right: Option<&PathBuf>,
+    format: Format,
+    options: &DiffOptions,
+) -> Result<Option<DiffNode>, String> {
+    let read = |path: Option<&PathBuf>| -> Result<Option<String>, String> {
+        path.map(std::fs::read_to_string)
+            .transpose()
+            .map_err(|e| e.to_string())
+    };
+    let (left_text, right_text) = (read(left)?, read(right)?);
+    // Most files in two copies of a config tree are identical; skip parsing those.
+    if left_text.is_some() && left_text == right_text {
+        return Ok(None);
+    }
+    let parse = |text: Option<String>| -> Result<_, String> {
+        match text {
+            Some(text) => document::parse(&text, format).map_err(|e| e.to_string()),
+            None => Ok(None),
+        }
+    };
+    let left = parse(left_text).map_err(|e| format!("left: {e}"))?;
+    let right = parse(right_text).map_err(|e| format!("right: {e}"))?;
+    Ok(diff::diff_documents(left.as_ref(), right.as_ref(), options))
+}
+
+/// Nests files, keyed by their path below `path`, into directory nodes.
+fn tree(name: String, path: String, files: Vec<(String, DirNode)>) -> DirNode {
+    let mut dirs: BTreeMap<String, Vec<(String, DirNode)>> = BTreeMap::new();
+    let mut leaves = Vec::new();
+    for (rest, file) in files {
+        match rest.split_once('/') {
+            Some((dir, rest)) => dirs
+                .entry(dir.to_string())
+                .or_default()
+                .push((rest.to_string(), file)),
+            None => leaves.push(file),
+        }
+    }
+    let mut children: Vec<DirNode> = dirs
+        .into_iter()
+        .map(|(dir, files)| {
+            let dir_path = if path.is_empty() {
+                dir.clone()
+            } else {
+                format!("{path}/{dir}")
+            };
+            tree(dir, dir_path, files)
+        })
+        .collect();
+    children.extend(leaves);
+
+    let mut stats = DiffStats::default();
+    children.iter().for_each(|c| stats.add(&c.stats));
+    DirNode {
+        name,
+        path,
+        status: folder_status(&children),
+        stats,
+        format: None,
+        error: None,
+        children: Some(children),
+    }
+}
+
+/// Added or removed when everything inside is, unchanged when nothing
+/// inside changed, and changed otherwise.
+fn folder_status(children: &[DirNode]) -> DiffType {
+    let Some(first) = children.first().map(|c| c.status) else {
+        return DiffType::Unchanged;
+    };
+    if children.iter().all(|c| c.status == first) {
+        return first;
+    }
+    let unchanged = |status| matches!(status, DiffType::Unchanged | DiffType::Equivalent);
+    if children.iter().all(|c| unchanged(c.status)) {
+        DiffType::Equivalent
+    } else {
+        DiffType::Changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    /// A fresh directory under the system temp dir holding `files`.
+    fn temp_tree(files: &[(&str, &str)]) -> PathBuf {
+        static NEXT: AtomicU64 = AtomicU64::new(0);
+        let root = std::env::temp_dir().join(format!(
+            "dirdiff-test-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        for (rel, text) in files {
+            let path = root.join(rel);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, text).unwrap();
+        }
+        root
+    }
+
+    fn child<'n>(node: &'n DirNode, name: &str) -> &'n DirNode {
+        node.children
+            .iter()
+            .flatten()
+            .find(|c| c.name == name)
+            .unwrap_or_else(|| panic!("no {name} in {}", node.path))
+    }
+
+    fn names(node: &DirNode) -> Vec<&str> {
+        node.children
+            .iter()
+            .flatten()
+            .map(|c| c.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn pairs_files_by_relative_path() {
+        let left = temp_tree(&[
+            // Identical text isn't parsed, so this broken file still matches.
+            ("same.json", "{oops"),
+            ("conf/app.yaml", "a: 1\nb: 2\n"),
+            ("conf/bad.json", "{oops"),
+            ("gone.toml", "x = 1\ny = 2\n"),
+            ("notes.txt", "not diffed"),
+            (".hidden.json", "{}"),
+            (".git/HEAD.json", "{}"),
+        ]);
+        let right = temp_tree(&[
+            ("same.json", "{oops"),
+            ("conf/app.yaml", "a: 1\nb: 3\n"),
+            ("conf/bad.json", "{}"),
+            ("new/one.json", r#"{"k": [1, 2]}"#),
+        ]);
+        let root = diff_dirs(&left, &right, &DiffOptions::default()).unwrap();
+        std::fs::remove_dir_all(&left).unwrap();
+        std::fs::remove_dir_all(&right).unwrap();
+
+        assert_eq!(names(&root), ["conf", "new", "gone.toml", "same.json"]);
+        assert_eq!(root.status, DiffType::Changed);
+
+        let same = child(&root, "same.json");
+        assert_eq!(same.status, DiffType::Unchanged);
+        assert_eq!(same.error, None);
+
+        let gone = child(&root, "gone.toml");
+        assert_eq!(
+            (gone.status, gone.format),
+            (DiffType::Removed, Some(Format::Toml))
+        );
+        assert_eq!(gone.stats.removed, 2);
+
+        let new = child(&root, "new");
+        assert_eq!(new.status, DiffType::Added);
+        assert_eq!(new.path, "new");
+        let one = child(new, "one.json");
+        assert_eq!(
+            (one.status, one.path.as_str()),
+            (DiffType::Added, "new/one.json")
+        );
+        assert_eq!(new.stats.added, one.stats.added);
+        assert!(one.stats.added > 0);
+
+        let conf = child(&root, "conf");
+        assert_eq!(conf.status, DiffType::Changed);
+        let app = child(conf, "app.yaml");
+        assert_eq!(app.status, DiffType::Changed);
+        assert_eq!(app.stats.changed, 1);
+        let bad = child(conf, "bad.json");
+        assert_eq!(bad.status, DiffType::Changed);
+        assert!(bad.error.as_deref().unwrap().starts_with("left: "));
+
+        assert_eq!(root.stats.removed, 2);
+        assert_eq!(root.stats.changed, 1);
+        assert_eq!(root.stats.added, one.stats.added);
+    }
+
+    fn with_status(status: DiffType) -> DirNode {
+        DirNode {
+            name: String::new(),
+            path: String::new(),
+            status,
+            stats: DiffStats::default(),
+            format: None,
+            error: None,
+            children: None,
+        }
+    }
+
+    #[test]
+    fn folders_roll_up_their_children() {
+        use DiffType::*;
+        let status = |statuses: &[DiffType]| {
+            let children: Vec<DirNode> = statuses.iter().map(|s| with_status(*s)).collect();
+            folder_status(&children)
+        };
+        assert_eq!(status(&[]), Unchanged);
+        assert_eq!(status(&[Added, Added]), Added);
+        assert_eq!(status(&[Removed]), Removed);
+        assert_eq!(status(&[Unchanged, Equivalent]), Equivalent);
+        assert_eq!(status(&[Unchanged, Added]), Changed);
+        assert_eq!(status(&[Added, Removed]), Changed);
+    }
+}
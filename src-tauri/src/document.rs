@@ -0,0 +1,449 @@
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::fmt;
+
+use serde::de::{self, DeserializeSeed, Deserializer, MapAccess, SeqAccess, Visitor};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::source::{self, LineRange};
+use crate::{json5doc, tomldoc, xmldoc};
+
+/// Text formats the backend knows how to parse into a diffable value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Json,
+    Yaml,
+    Toml,
+    Xml,
+}
+
+/// Where and why parsing failed. Positions are absent when the parser
+/// couldn't tell.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseError {
+    pub message: String,
+    /// 1-based.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub line: Option<usize>,
+    /// 1-based.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub column: Option<usize>,
+    /// Byte offset into the text.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub offset: Option<usize>,
+    /// What the parser wanted to see instead, e.g. `` `,` or `}` ``.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub expected: Option<String>,
+}
+
+impl ParseError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            line: None,
+            column: None,
+            offset: None,
+            expected: None,
+        }
+    }
+
+    /// Locates the error at byte `offset` of `text`.
+    pub fn at(error: &dyn fmt::Display, text: &str, offset: usize) -> Self {
+        let offset = offset.min(text.len());
+        let line = source::LineIndex::new(text).line_of(offset);
+        let line_start = source::line_start(text, line);
+        let column = text
+            .get(line_start..offset)
+            .map_or(1, |prefix| prefix.chars().count() + 1);
+        Self::located(error, line, column, offset)
+    }
+
+    pub fn located(error: &dyn fmt::Display, line: usize, column: usize, offset: usize) -> Self {
+        let full = error.to_string();
+        // serde_json and serde_yaml append the position to the message; it's reported
+        // separately here.
+        let message = match full.rfind(" at line ") {
+            Some(idx) => &full[..idx],
+            None => &full,
+        };
+        Self {
+            expected: expected_token(message),
+            message: message.to_string(),
+            line: Some(line),
+            column: Some(column),
+            offset: Some(offset),
+        }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let (Some(line), Some(column)) = (self.line, self.column) {
+            write!(f, " at line {line} column {column}")?;
+        }
+        Ok(())
+    }
+}
+
+impl From<ParseError> for String {
+    fn from(error: ParseError) -> Self {
+        error.to_string()
+    }
+}
+
+/// Pulls the expected token out of a parser message ("expected `:`", "did
+/// not find expected key"), or names the closer an "EOF while parsing ..."
+/// is missing.
+fn expected_token(message: &str) -> Option<String> {
+    if let Some(what) = message.strip_prefix("EOF while parsing ") {
+        let closer = match what {
+            "a list" => "`]`",
+            "an object" => "`}`",
+            "a string" => "`\"`",
+            _ => "value",
+        };
+        return Some(closer.to_string());
+    }
+    let start = message
+        .strip_prefix("expected ")
+        .map(|_| 0)
+        .or_else(|| message.find(" expected ").map(|idx| idx + 1))?;
+    let expected = &message[start + "expected ".len()..];
+    // libyaml adds where the enclosing construct started, quick-xml what it
+    // found instead.
+    let expected = expected.split(" at line ").next().unwrap_or(expected);
+    let expected = expected.split(", but ").next().unwrap_or(expected);
+    Some(expected.to_string())
+}
+
+/// Something the parser resolved silently that the user should know about,
+/// since the diff shows the resolved value.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Warning {
+    pub kind: WarningKind,
+    pub path: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum WarningKind {
+    /// A key repeated within one object; only its last value is kept.
+    DuplicateKey,
+    /// A number with more precision or range than an `f64`/`i64`/`u64` holds,
+    /// or none at all: JSON5's `Infinity` and `NaN`.
+    PrecisionLoss,
+}
+
+/// Parses `text` in the given format. Blank input yields `None` so an empty
+/// panel reads as "nothing there" rather than an error.
+pub fn parse(text: &str, format: Format) -> Result<Option<Value>, ParseError> {
+    parse_with_warnings(text, format).map(|(value, _)| value)
+}
+
+/// Like [`parse`], but also reports duplicate keys in JSON (JSON5 and JSONC
+/// included) and YAML, and JSON numbers JavaScript would round. Numbers are
+/// kept and compared exactly as written either way. TOML needs no such
+/// check: its parser rejects duplicate keys, and its integers are `i64` by
+/// definition. XML holds only text.
+pub fn parse_with_warnings(
+    text: &str,
+    format: Format,
+) -> Result<(Option<Value>, Vec<Warning>), ParseError> {
+    if text.trim().is_empty() {
+        return Ok((None, Vec::new()));
+    }
+    match format {
+        Format::Json => match parse_json(text)? {
+            Json::Strict(value, source) => Ok((Some(value), source::json_warnings(&source))),
+            Json::Json5(parsed) => Ok((Some(parsed.value), parsed.warnings)),
+        },
+        Format::Yaml => {
+            let mut warnings = Vec::new();
+            let value = parse_yaml_with_warnings(text, &mut warnings)?;
+            Ok((Some(value), warnings))
+        }
+        Format::Toml => tomldoc::parse(text).map(|value| (Some(value), Vec::new())),
+        Format::Xml => xmldoc::parse(text).map(|value| (Some(value), Vec::new())),
+    }
+}
+
+/// Checks that `text` parses and maps each display path to the source lines
+/// it spans.
+pub fn line_map(text: &str, format: Format) -> Result<HashMap<String, LineRange>, ParseError> {
+    if text.trim().is_empty() {
+        return Ok(HashMap::new());
+    }
+    match format {
+        Format::Json => match parse_json(text)? {
+            Json::Strict(_, source) => Ok(source::json_line_map(&source)),
+            Json::Json5(parsed) => Ok(parsed.lines),
+        },
+        Format::Yaml => {
+            parse_yaml(text)?;
+            Ok(source::yaml_line_map(text))
+        }
+        Format::Toml => tomldoc::line_map(text),
+        Format::Xml => xmldoc::line_map(text),
+    }
+}
+
+/// Serializes `value` back to text the way the webview's Format button does:
+/// two-space indented JSON, though with numbers as written, or block-style
+/// YAML. TOML is written with
+/// `[table]` headers, see [`tomldoc::to_string`]; XML as described in
+/// [`xmldoc::parse`].
+pub fn to_string(value: &Value, format: Format) -> Result<String, String> {
+    match format {
+        Format::Json => serde_json::to_string_pretty(value).map_err(|e| e.to_string()),
+        Format::Yaml => serde_yaml::to_string(value).map_err(|e| e.to_string()),
+        Format::Toml => tomldoc::to_string(value),
+        Format::Xml => xmldoc::to_string(value),
+    }
+}
+
+/// Reformats `text`. Strict JSON and YAML go through [`to_string`]; JSON5,
+/// TOML and XML are tidied in place so comments and layout survive.
+pub fn format(text: &str, format: Format) -> Result<String, ParseError> {
+    if text.trim().is_empty() {
+        return Ok(String::new());
+    }
+    match format {
+        Format::Json => match parse_json(text)? {
+            Json::Strict(value, _) => to_string(&value, format).map_err(ParseError::new),
+            Json::Json5(_) => json5doc::format(text),
+        },
+        Format::Toml => tomldoc::format(text),
+        Format::Xml => xmldoc::format(text),
+        _ => {
+            let value = parse(text, format)?.unwrap_or_default();
+            to_string(&value, format).map_err(ParseError::new)
+        }
+    }
+}
+
+/// JSON as parsed: strictly, along with the text it was actually parsed
+/// from, or as JSON5.
+enum Json<'t> {
+    Strict(Value, Cow<'t, str>),
+    Json5(json5doc::Parsed),
+}
+
+/// Tries strict JSON first, then JSON5, which also covers JSONC. Text that
+/// uses JSON5 syntax before going wrong gets the JSON5 parser's error; other
+/// text was meant as plain JSON and gets serde_json's.
+fn parse_json(text: &str) -> Result<Json<'_>, ParseError> {
+    let strict = match serde_json::from_str(text) {
+        Ok(value) => return Ok(Json::Strict(value, Cow::Borrowed(text))),
+        Err(e) => {
+            let (line, column) = (e.line(), e.column());
+            let offset = source::line_start(text, line) + column.saturating_sub(1);
+            ParseError::located(&e, line, column, offset.min(text.len()))
+        }
+    };
+    let error = match json5doc::parse(text, strict) {
+        Ok(parsed) => return Ok(Json::Json5(parsed)),
+        Err(error) => error,
+    };
+    // Same fallback as the webview: accept JSON pasted as an escaped string.
+    let unescaped = text.replace("\\\"", "\"");
+    match serde_json::from_str(&unescaped) {
+        Ok(value) => Ok(Json::Strict(value, Cow::Owned(unescaped))),
+        Err(_) => Err(error),
+    }
+}
+
+fn parse_yaml(text: &str) -> Result<Value, ParseError> {
+    parse_yaml_with_warnings(text, &mut Vec::new())
+}
+
+fn parse_yaml_with_warnings(text: &str, warnings: &mut Vec<Warning>) -> Result<Value, ParseError> {
+    let seed = YamlValue {
+        path: String::new(),
+        warnings,
+    };
+    seed.deserialize(serde_yaml::Deserializer::from_str(text))
+        .map_err(|e| match e.location() {
+            Some(at) => ParseError::located(&e, at.line(), at.column(), at.index()),
+            None => ParseError::new(e.to_string()),
+        })
+}
+
+/// Deserializes YAML the way `Value::deserialize` does, noting repeated
+/// keys along the way: serde_yaml keeps the last one without complaint.
+struct YamlValue<'w> {
+    path: String,
+    warnings: &'w mut Vec<Warning>,
+}
+
+impl<'de> DeserializeSeed<'de> for YamlValue<'_> {
+    type Value = Value;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+}
+
+impl<'de> Visitor<'de> for YamlValue<'_> {
+    type Value = Value;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("any YAML value")
+    }
+
+    fn visit_bool<E>(self, value: bool) -> Result<Value, E> {
+        Ok(Value::Bool(value))
+    }
+
+    fn visit_i64<E>(self, value: i64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_u64<E>(self, value: u64) -> Result<Value, E> {
+        Ok(Value::Number(value.into()))
+    }
+
+    fn visit_i128<E: de::Error>(self, value: i128) -> Result<Value, E> {
+        serde_json::from_str(&value.to_string()).map_err(E::custom)
+    }
+
+    fn visit_u128<E: de::Error>(self, value: u128) -> Result<Value, E> {
+        serde_json::from_str(&value.to_string()).map_err(E::custom)
+    }
+
+    fn visit_f64<E>(self, value: f64) -> Result<Value, E> {
+        Ok(serde_json::Number::from_f64(value).map_or(Value::Null, Value::Number))
+    }
+
+    fn visit_str<E>(self, value: &str) -> Result<Value, E> {
+        Ok(Value::String(value.to_string()))
+    }
+
+    fn visit_string<E>(self, value: String) -> Result<Value, E> {
+        Ok(Value::String(value))
+    }
+
+    fn visit_unit<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_none<E>(self) -> Result<Value, E> {
+        Ok(Value::Null)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+        deserializer.deserialize_any(self)
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element_seed(YamlValue {
+            path: format!("{}[{}]", self.path, items.len()),
+            warnings: &mut *self.warnings,
+        })? {
+            items.push(item);
+        }
+        Ok(Value::Array(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+        let mut object = serde_json::Map::new();
+        while let Some(key) = map.next_key::<String>()? {
+            let path = if self.path.is_empty() {
+                key.clone()
+            } else {
+                format!("{}.{key}", self.path)
+            };
+            if object.contains_key(&key) {
+                self.warnings.push(Warning {
+                    kind: WarningKind::DuplicateKey,
+                    path: path.clone(),
+                    message: format!("duplicate key \"{key}\"; only the last value is kept"),
+                });
+            }
+            let value = map.next_value_seed(YamlValue {
+                path,
+                warnings: &mut *self.warnings,
+            })?;
+            object.insert(key, value);
+        }
+        Ok(Value::Object(object))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_numbers_are_kept_as_written() {
+        let text = r#"{"id": 9007199254740993, "big": 123456789012345678901234, "f": 1.50}"#;
+        let value = parse(text, Format::Json).unwrap().unwrap();
+        assert_eq!(value["id"].to_string(), "9007199254740993");
+        assert_eq!(value["big"].to_string(), "123456789012345678901234");
+        assert_eq!(value["f"].to_string(), "1.50");
+    }
+
+    #[test]
+    fn json_warnings() {
+        let text = r#"{"id": 9007199254740993, "ok": 9007199254740992, "f": 0.1, "a": 1, "a": 2}"#;
+        let (_, warnings) = parse_with_warnings(text, Format::Json).unwrap();
+        let found: Vec<_> = warnings.iter().map(|w| (w.kind, w.path.as_str())).collect();
+        assert_eq!(
+            found,
+            [
+                (WarningKind::PrecisionLoss, "id"),
+                (WarningKind::DuplicateKey, "a")
+            ]
+        );
+        assert!(warnings[0].message.contains("reads it as 9007199254740992"));
+    }
+
+    #[test]
+    fn json_errors_come_from_the_parser_the_text_was_meant_for() {
+        let error = parse("{\"a\": 1,\n \"b\": tru}", Format::Json).unwrap_err();
+        assert_eq!(
+            (error.message.as_str(), error.line, error.column),
+            ("expected ident", Some(2), Some(10))
+        );
+        let error = parse("{\"a\": 1, // c\n \"b\": tru}", Format::Json).unwrap_err();
+        assert_eq!(
+            (error.message.as_str(), error.line, error.column),
+            ("expected value", Some(2), Some(7))
+        );
+        // JSON pasted as an escaped string still parses.
+        let value = parse("{\\\"a\\\": 1}", Format::Json).unwrap().unwrap();
+        assert_eq!(value, serde_json::json!({"a": 1}));
+    }
+
+    #[test]
+    fn yaml_duplicate_keys_are_reported() {
+        let text = "a: 1\nb:\n  - x: 1\n    x: 2\na: 3\n";
+        let (value, warnings) = parse_with_warnings(text, Format::Yaml).unwrap();
+        assert_eq!(value.unwrap(), serde_json::json!({"a": 3, "b": [{"x": 2}]}));
+        let paths: Vec<_> = warnings.iter().map(|w| w.path.as_str()).collect();
+        assert_eq!(paths, ["b[0].x", "a"]);
+        assert!(warnings.iter().all(|w| w.kind == WarningKind::DuplicateKey));
+    }
+
+    #[test]
+    fn yaml_values_match_serde_yaml() {
+        let text =
+            "s: text\nn: ~\nb: true\ni: -3\nf: 1.5\nbig: 123456789012345678901234\nl: [1, two]\n";
+        let expected: Value = serde_yaml::from_str(text).unwrap();
+        assert_eq!(parse(text, Format::Yaml).unwrap().unwrap(), expected);
+        let error = parse("a: [1\n", Format::Yaml).unwrap_err();
+        assert!(error.line.is_some());
+    }
+
+    #[test]
+    fn toml_rejects_duplicate_keys() {
+        let error = parse("a = 1\na = 2\n", Format::Toml).unwrap_err();
+        assert!(error.message.contains("duplicate key"), "{error}");
+    }
+}
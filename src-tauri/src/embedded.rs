@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use base64::alphabet::{STANDARD, URL_SAFE};
+use base64::engine::{DecodePaddingMode, GeneralPurpose, GeneralPurposeConfig};
+use base64::Engine;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::document::{self, Format};
+
+/// How a string value was decoded into structure.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Encoding {
+    Json,
+    Yaml,
+    Base64Json,
+}
+
+/// A document with its embedded documents expanded, and the display paths
+/// of the strings that were replaced.
+pub struct Decoded {
+    pub value: Value,
+    pub paths: HashMap<String, Encoding>,
+}
+
+/// Payloads wrapped more than this many times are left as strings.
+const MAX_NESTING: usize = 8;
+
+/// Replaces string values that hold a serialized object or array (JSON,
+/// multi-line YAML, or base64 of JSON) with the parsed structure, so log
+/// records and message envelopes diff field by field. Decoded values are
+/// searched again for further embedded documents.
+pub fn decode(value: &Value) -> Decoded {
+    let mut paths = HashMap::new();
+    let value = expand(value, "", 0, &mut paths);
+    Decoded { value, paths }
+}
+
+fn expand(
+    value: &Value,
+    path: &str,
+    nesting: usize,
+    paths: &mut HashMap<String, Encoding>,
+) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(name, child)| {
+                    let child_path = if path.is_empty() {
+                        name.clone()
+                    } else {
+                        format!("{path}.{name}")
+                    };
+                    (name.clone(), expand(child, &child_path, nesting, paths))
+                })
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .enumerate()
+                .map(|(idx, child)| expand(child, &format!("{path}[{idx}]"), nesting, paths))
+                .collect(),
+        ),
+        Value::String(text) if nesting < MAX_NESTING => match decode_string(text) {
+            Some((decoded, encoding)) => {
+                // The outermost encoding is the one the user sees in the text.
+                paths.entry(path.to_string()).or_insert(encoding);
+                expand(&decoded, path, nesting + 1, paths)
+            }
+            None => value.clone(),
+        },
+        _ => value.clone(),
+    }
+}
+
+/// Parses `text` as an embedded object or array. Scalars are never
+/// decoded: `"42"` or `"true"` are meant as strings far more often than not.
+fn decode_string(text: &str) -> Option<(Value, Encoding)> {
+    let trimmed = text.trim();
+    if trimmed.starts_with(['{', '[']) {
+        return structured(serde_json::from_str(trimmed).ok()?).map(|v| (v, Encoding::Json));
+    }
+    // A single `key: value` line is far more likely prose than YAML.
+    if trimmed.contains('\n') {
+        if let Ok(Some(value)) = document::parse(trimmed, Format::Yaml) {
+            return structured(value).map(|v| (v, Encoding::Yaml));
+        }
+    }
+    let bytes = decode_base64(trimmed)?;
+    let json = std::str::from_utf8(&bytes).ok()?.trim();
+    if !json.starts_with(['{', '[']) {
+        return None;
+    }
+    structured(serde_json::from_str(json).ok()?).map(|v| (v, Encoding::Base64Json))
+}
+
+fn structured(value: Value) -> Option<Value> {
+    matches!(value, Value::Object(_) | Value::Array(_)).then_some(value)
+}
+
+/// Standard or URL-safe base64, padded or not. Short strings are skipped:
+/// plenty of ordinary words happen to be valid base64.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    const LENIENT: GeneralPurposeConfig =
+        GeneralPurposeConfig::new().with_decode_padding_mode(DecodePaddingMode::Indifferent);
+    if text.len() < 16 {
+        return None;
+    }
+    let url_safe = text.contains(['-', '_']);
+    let engine = GeneralPurpose::new(if url_safe { &URL_SAFE } else { &STANDARD }, LENIENT);
+    engine.decode(text).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn stringified_json_is_parsed() {
+        let decoded = decode(&json!({"body": " {\"a\": [1, 2]} ", "list": ["[true]"]}));
+        assert_eq!(
+            decoded.value,
+            json!({"body": {"a": [1, 2]}, "list": [[true]]})
+        );
+        assert_eq!(decoded.paths.get("body"), Some(&Encoding::Json));
+        assert_eq!(decoded.paths.get("list[0]"), Some(&Encoding::Json));
+        // Broken JSON stays text.
+        let decoded = decode(&json!({"body": "{not json"}));
+        assert_eq!(decoded.value, json!({"body": "{not json"}));
+        assert!(decoded.paths.is_empty());
+    }
+
+    #[test]
+    fn only_multi_line_yaml_is_parsed() {
+        let decoded = decode(&json!({"config": "name: web\nports:\n  - 80\n"}));
+        assert_eq!(
+            decoded.value,
+            json!({"config": {"name": "web", "ports": [80]}})
+        );
+        assert_eq!(decoded.paths.get("config"), Some(&Encoding::Yaml));
+        let decoded = decode(&json!({"note": "status: done"}));
+        assert_eq!(decoded.value, json!({"note": "status: done"}));
+        // Multi-line prose parses as a YAML scalar, which isn't structure.
+        let decoded = decode(&json!({"note": "first line\nsecond line"}));
+        assert!(decoded.paths.is_empty());
+    }
+
+    #[test]
+    fn base64_json_from_sixteen_characters() {
+        // `{"a":"1234"}` is 12 bytes, 16 characters of base64.
+        let decoded = decode(&json!({"token": "eyJhIjoiMTIzNCJ9"}));
+        assert_eq!(decoded.value, json!({"token": {"a": "1234"}}));
+        assert_eq!(decoded.paths.get("token"), Some(&Encoding::Base64Json));
+        // `{"a":"123"}` without padding is 15.
+        let decoded = decode(&json!({"token": "eyJhIjoiMTIzIn0"}));
+        assert_eq!(decoded.value, json!({"token": "eyJhIjoiMTIzIn0"}));
+        // Padded it reaches 16 again.
+        let decoded = decode(&json!({"token": "eyJhIjoiMTIzIn0="}));
+        assert_eq!(decoded.value, json!({"token": {"a": "123"}}));
+    }
+
+    #[test]
+    fn plain_strings_are_left_alone() {
+        let value = json!({
+            "word": "hello",
+            "number": "42",
+            "flag": "true",
+            // Valid base64, but not of JSON.
+            "base64": "aGVsbG8gd29ybGQgYWdhaW4=",
+        });
+        let decoded = decode(&value);
+        assert_eq!(decoded.value, value);
+        assert!(decoded.paths.is_empty());
+    }
+
+    /// `{"p": "{\"p\": ...}"}` with `depth` levels of stringified JSON.
+    fn wrapped(depth: usize) -> Value {
+        (0..depth).fold(
+            json!({"end": true}),
+            |inner, _| json!({"p": inner.to_string()}),
+        )
+    }
+
+    #[test]
+    fn nesting_stops_after_max_nesting_levels() {
+        let decoded = decode(&wrapped(MAX_NESTING));
+        assert_eq!(decoded.paths.len(), MAX_NESTING);
+        let innermost = format!("p{}", ".p".repeat(MAX_NESTING - 1));
+        assert_eq!(decoded.paths.get(&innermost), Some(&Encoding::Json));
+        let mut value = &decoded.value;
+        for _ in 0..MAX_NESTING {
+            value = &value["p"];
+        }
+        assert_eq!(value, &json!({"end": true}));
+
+        let decoded = decode(&wrapped(MAX_NESTING + 1));
+        assert_eq!(decoded.paths.len(), MAX_NESTING);
+        let mut value = &decoded.value;
+        for _ in 0..MAX_NESTING {
+            value = &value["p"];
+        }
+        assert_eq!(value["p"], Value::String(json!({"end": true}).to_string()));
+    }
+}
@@ -0,0 +1,245 @@
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use serde::Serialize;
+
+/// Commits listed when no limit is given.
+pub const DEFAULT_LOG_LIMIT: usize = 200;
+
+/// A commit that touched a file.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Commit {
+    pub hash: String,
+    pub short_hash: String,
+    pub author: String,
+    pub email: String,
+    /// Author date, seconds since the epoch.
+    pub timestamp: i64,
+    pub subject: String,
+    /// The file's path in the repository as of this commit, which differs
+    /// from today's before a rename.
+    pub path: String,
+}
+
+/// A file inside a work tree: the repository root and the file's
+/// `/`-separated path relative to it.
+pub struct Tracked {
+    pub root: PathBuf,
+    pub path: String,
+}
+
+impl Tracked {
+    pub fn locate(file: &Path) -> Result<Self, String> {
+        let file = file
+            .canonicalize()
+            .map_err(|e| format!("{}: {e}", file.display()))?;
+        let dir = file.parent().ok_or("file has no parent directory")?;
+        let root = git(dir, &["rev-parse", "--show-toplevel"])?;
+        let root = PathBuf::from(root.trim())
+            .canonicalize()
+            .map_err(|e| e.to_string())?;
+        let path = file
+            .strip_prefix(&root)
+            .map_err(|_| "file is outside the repository".to_string())?
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        Ok(Self { root, path })
+    }
+
+    /// Commits that touched the file, newest first, following renames.
+    pub fn log(&self, limit: Option<usize>) -> Result<Vec<Commit>, String> {
+        let limit = format!("-n{}", limit.unwrap_or(DEFAULT_LOG_LIMIT));
+        let output = git(
+            &self.root,
+            &[
+                "-c",
+                "core.quotepath=off",
+                "log",
+                "--follow",
+                "--name-only",
+                "--format=%x1e%H%x1f%h%x1f%an%x1f%ae%x1f%at%x1f%s",
+                &limit,
+                "--",
+                &self.path,
+            ],
+        )?;
+        let mut commits = Vec::new();
+        // Merges list no file names; they keep the path of the commit after.
+        let mut path = self.path.clone();
+        for record in output.split('\x1e').filter(|r| !r.trim().is_empty()) {
+            let mut lines = record.lines();
+            let header = lines.next().unwrap_or_default();
+            if let Some(name) = lines.map(str::trim).rfind(|l| !l.is_empty()) {
+                path = name.to_string();
+            }
+            let fields: Vec<&str> = header.splitn(6, '\x1f').collect();
+            let [hash, short_hash, author, email, timestamp, subject] = fields[..] else {
+                return Err(format!("unexpected git log output: {header}"));
+            };
+            commits.push(Commit {
+                hash: hash.to_string(),
+                short_hash: short_hash.to_string(),
+                author: author.to_string(),
+                email: email.to_string(),
+                timestamp: timestamp.parse().unwrap_or_default(),
+                subject: subject.to_string(),
+                path: path.clone(),
+            });
+        }
+        Ok(commits)
+    }
+
+    /// The file's contents at `rev`: anything `git rev-parse` accepts, such
+    /// as `HEAD~2`, `main`, a commit hash or `stash@{0}`. `path` overrides
+    /// the file's current path, for revisions before a rename.
+    pub fn show(&self, rev: &str, path: Option<&str>) -> Result<String, String> {
+        if rev.is_empty() || rev.starts_with('-') {
+            return Err(format!("invalid revision '{rev}'"));
+        }
+        let path = path.unwrap_or(&self.path);
+        let object = format!("{rev}:{path}");
+        git(&self.root, &["cat-file", "blob", &object])
+            .map_err(|_| format!("{path} does not exist at {rev}"))
+    }
+
+    /// The file's contents at each of `commits`, at the path it had there,
+    /// read through a single `git cat-file --batch`. `None` where the file
+    /// doesn't exist.
+    pub fn contents(&self, commits: &[Commit]) -> Result<Vec<Option<Vec<u8>>>, String> {
+        let mut child = command(&self.root)
+            .args(["cat-file", "--batch"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("could not run git: {e}"))?;
+        let (Some(mut stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err("could not talk to git".to_string());
+        };
+        let requests: String = commits
+            .iter()
+            .map(|c| format!("{}:{}\n", c.hash, c.path))
+            .collect();
+        // Write from another thread so a full stdout pipe can't stall git.
+        let writer = std::thread::spawn(move || stdin.write_all(requests.as_bytes()));
+
+        let contents = read_batch(BufReader::new(stdout), commits.len());
+        // Stopping early leaves git blocked on a pipe nobody reads; end it
+        // so the writer and the wait below can finish.
+        if contents.is_err() {
+            let _ = child.kill();
+        }
+        let _ = writer.join();
+        let _ = child.wait();
+        contents
+    }
+
+    /// Branches, remote branches, tags and stashes, for picking a revision.
+    pub fn refs(&self) -> Result<Vec<String>, String> {
+        let mut refs = vec!["HEAD".to_string()];
+        let names = git(
+            &self.root,
+            &[
+                "for-each-ref",
+                "--format=%(refname:short)",
+                "refs/heads",
+                "refs/remotes",
+                "refs/tags",
+            ],
+        )?;
+        let stashes = git(&self.root, &["stash", "list", "--format=%gd"])?;
+        refs.extend(
+            names
+                .lines()
+                .chain(stashes.lines())
+                .filter(|name| !name.is_empty() && !name.ends_with("/HEAD"))
+                .map(str::to_string),
+        );
+        Ok(refs)
+    }
+}
+
+/// Reads `count` responses of `git cat-file --batch`: `<object> missing`,
+/// or `<oid> <type> <size>`, the content and a newline.
+fn read_batch(mut stdout: impl BufRead, count: usize) -> Result<Vec<Option<Vec<u8>>>, String> {
+    let mut contents = Vec::with_capacity(count);
+    let mut header = String::new();
+    for _ in 0..count {
+        header.clear();
+        stdout.read_line(&mut header).map_err(|e| e.to_string())?;
+        if header.trim_end().ends_with(" missing") {
+            contents.push(None);
+            continue;
+        }
+        let size: usize = header
+            .split_whitespace()
+            .last()
+            .and_then(|size| size.parse().ok())
+            .ok_or_else(|| format!("unexpected git cat-file output: {header}"))?;
+        let mut content = vec![0; size + 1];
+        stdout.read_exact(&mut content).map_err(|e| e.to_string())?;
+        content.pop();
+        contents.push(Some(content));
+    }
+    Ok(contents)
+}
+
+/// Runs git in `dir`. Fails with git's own message, without the `fatal: `.
+fn git(dir: &Path, args: &[&str]) -> Result<String, String> {
+    let output = command(dir)
+        .args(args)
+        .output()
+        .map_err(|e| format!("could not run git: {e}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let message = stderr.trim();
+        return Err(message
+            .strip_prefix("fatal: ")
+            .unwrap_or(message)
+            .to_string());
+    }
+    String::from_utf8(output.stdout).map_err(|_| "git output is not UTF-8 text".to_string())
+}
+
+fn command(dir: &Path) -> Command {
+    let mut command = Command::new("git");
+    command.arg("-C").arg(dir);
+    // Release builds have no console; don't flash one up for each call.
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        command.creation_flags(CREATE_NO_WINDOW);
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn batch_output_is_framed_by_size() {
+        // Content that looks like another header must not end the frame.
+        let output = b"aaaa blob 21\n{\"a\": 1}\nbbbb blob 3\n\n\
+            HEAD:gone.json missing\n\
+            cccc blob 0\n\n";
+        let contents = read_batch(&output[..], 3).unwrap();
+        assert_eq!(
+            contents,
+            [
+                Some(b"{\"a\": 1}\nbbbb blob 3\n".to_vec()),
+                None,
+                Some(Vec::new())
+            ]
+        );
+        assert!(read_batch(&output[..], 4).is_err());
+        assert!(read_batch(&b"aaaa blob 10\nshort\n"[..], 1).is_err());
+    }
+}
@@ -0,0 +1,215 @@
+use std::path::Path;
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::diff::{self, DiffOptions, DiffStats, DiffType};
+use crate::document::{self, Format};
+use crate::git::{self, Commit, Tracked};
+
+/// Changed paths listed per commit; the stats still count all of them.
+const MAX_CHANGES: usize = 50;
+
+/// A commit in a file's history and what it changed in the document.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Revision {
+    #[serde(flatten)]
+    pub commit: Commit,
+    /// The commit this one is compared with; absent for the file's first.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_hash: Option<String>,
+    /// The file's path in `previous_hash`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub previous_path: Option<String>,
+    pub stats: DiffStats,
+    /// The first `MAX_CHANGES` changed rows, by display path.
+    pub changes: Vec<Change>,
+    /// Why this version couldn't be compared with the one before.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    pub path: String,
+    pub diff_type: DiffType,
+}
+
+/// The commits that touched the file, newest first, each with the
+/// structural diff against the file's previous version.
+pub fn file_history(
+    file: &Path,
+    format: Format,
+    options: &DiffOptions,
+    limit: Option<usize>,
+) -> Result<Vec<Revision>, String> {
+    let limit = limit.unwrap_or(git::DEFAULT_LOG_LIMIT);
+    let tracked = Tracked::locate(file)?;
+    // One commit past the limit, so the oldest one listed has a version to
+    // compare against rather than showing up as entirely added.
+    let commits = tracked.log(Some(limit + 1))?;
+    let versions: Vec<Result<Option<Value>, String>> = tracked
+        .contents(&commits)?
+        .into_iter()
+        .map(|content| parse(content, format))
+        .collect();
+
+    let revisions = commits
+        .iter()
+        .take(limit)
+        .enumerate()
+        .map(|(idx, commit)| {
+            let previous = commits.get(idx + 1);
+            let before = versions.get(idx + 1).cloned().unwrap_or(Ok(None));
+            let mut revision = Revision {
+                commit: commit.clone(),
+                previous_hash: previous.map(|c| c.hash.clone()),
+                previous_path: previous.map(|c| c.path.clone()),
+                stats: DiffStats::default(),
+                changes: Vec::new(),
+                error: None,
+            };
+            let (before, after) = match (before, &versions[idx]) {
+                (Ok(before), Ok(after)) => (before, after),
+                (Err(e), _) => {
+                    revision.error = Some(format!("previous version: {e}"));
+                    return revision;
+                }
+                (_, Err(e)) => {
+                    revision.error = Some(e.clone());
+                    return revision;
+                }
+            };
+            if let Some(root) = diff::diff_documents(before.as_ref(), after.as_ref(), options) {
+                revision.stats = root.stats();
+                root.for_each_change(&mut |node| {
+                    if revision.changes.len() < MAX_CHANGES {
+                        revision.changes.push(Change {
+                            path: node.path.clone(),
+                            diff_type: node.diff_type,
+                        });
+                    }
+                });
+            }
+            revision
+        })
+        .collect();
+    Ok(revisions)
+}
+
+fn parse(content: Option<Vec<u8>>, format: Format) -> Result<Option<Value>, String> {
+    let Some(bytes) = content else {
+        return Ok(None);
+    };
+    let text = String::from_utf8(bytes).map_err(|_| "not UTF-8 text".to_string())?;
+    document::parse(&text, format).map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::process::Command;
+
+    fn run_git(root: &Path, args: &[&str], date: &str) {
+        let status = Command::new("git")
+            .arg("-C")
+            .arg(root)
+            .args(["-c", "user.name=Ada", "-c", "user.email=ada@example.com"])
+            .args(["-c", "commit.gpgsign=false"])
+            .args(args)
+            .env("GIT_AUTHOR_DATE", date)
+            .env("GIT_COMMITTER_DATE", date)
+            .status()
+            .unwrap();
+        assert!(status.success(), "git {args:?}");
+    }
+
+    /// Enough keys that a one-key edit still reads as a rename.
+    fn settings(a: u32, extra: &str) -> String {
+        let keys: String = (0..12)
+            .map(|i| format!("  \"key{i}\": \"value {i}\",\n"))
+            .collect();
+        format!("{{\n{keys}  \"a\": {a}{extra}\n}}\n")
+    }
+
+    #[test]
+    fn history_follows_a_rename() {
+        let root = std::env::temp_dir().join(format!("history-test-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(&root).unwrap();
+        let write = |name: &str, text: &str| std::fs::write(root.join(name), text).unwrap();
+
+        run_git(&root, &["init", "-q"], "1700000000 +0000");
+        write("config.json", &settings(1, ""));
+        run_git(&root, &["add", "."], "1700000000 +0000");
+        run_git(&root, &["commit", "-qm", "add config"], "1700000000 +0000");
+        write("config.json", &settings(2, ""));
+        run_git(&root, &["commit", "-qam", "bump a"], "1700000100 +0000");
+        run_git(
+            &root,
+            &["mv", "config.json", "settings.json"],
+            "1700000200 +0000",
+        );
+        write("settings.json", &settings(2, ",\n  \"b\": true"));
+        run_git(&root, &["add", "."], "1700000200 +0000");
+        // Separators in the subject mustn't split the record.
+        let subject = "rename: config → settings | add b";
+        run_git(&root, &["commit", "-qm", subject], "1700000200 +0000");
+        write("other.json", "{}");
+        run_git(&root, &["add", "."], "1700000300 +0000");
+        run_git(&root, &["commit", "-qm", "unrelated"], "1700000300 +0000");
+
+        let file: PathBuf = root.join("settings.json");
+        let tracked = Tracked::locate(&file).unwrap();
+        assert_eq!(tracked.path, "settings.json");
+        let commits = tracked.log(None).unwrap();
+        let listed: Vec<(&str, &str, i64)> = commits
+            .iter()
+            .map(|c| (c.subject.as_str(), c.path.as_str(), c.timestamp))
+            .collect();
+        assert_eq!(
+            listed,
+            [
+                (subject, "settings.json", 1700000200),
+                ("bump a", "config.json", 1700000100),
+                ("add config", "config.json", 1700000000),
+            ]
+        );
+        assert_eq!(commits[0].author, "Ada");
+        assert_eq!(commits[0].email, "ada@example.com");
+        assert!(commits[0].hash.starts_with(&commits[0].short_hash));
+
+        let contents = tracked.contents(&commits).unwrap();
+        let texts: Vec<Option<String>> = contents
+            .into_iter()
+            .map(|c| c.map(|bytes| String::from_utf8(bytes).unwrap()))
+            .collect();
+        assert_eq!(
+            texts,
+            [
+                Some(settings(2, ",\n  \"b\": true")),
+                Some(settings(2, "")),
+                Some(settings(1, "")),
+            ]
+        );
+
+        let revisions = file_history(&file, Format::Json, &DiffOptions::default(), None).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(revisions.len(), 3);
+        let renamed = &revisions[0];
+        assert_eq!(renamed.previous_path.as_deref(), Some("config.json"));
+        assert_eq!(renamed.previous_hash.as_ref(), Some(&commits[1].hash));
+        assert_eq!((renamed.stats.added, renamed.stats.changed), (1, 0));
+        assert_eq!(renamed.changes[0].path, "b");
+        let bumped = &revisions[1];
+        assert_eq!((bumped.stats.added, bumped.stats.changed), (0, 1));
+        assert_eq!(bumped.changes[0].path, "a");
+        let first = &revisions[2];
+        assert_eq!(first.previous_hash, None);
+        assert_eq!(first.stats.added, 13);
+        assert!(revisions.iter().all(|r| r.error.is_none()));
+    }
+}
@@ -0,0 +1,1035 @@
+use std::collections::{HashMap, HashSet};
+
+use serde_json::{Map, Number, Value};
+
+use crate::document::{ParseError, Warning, WarningKind};
+use crate::source::{self, LineIndex, LineRange};
+
+/// Nesting depth past which parsing gives up, as serde_json does.
+const MAX_DEPTH: usize = 128;
+
+/// A JSON5 text as parsed, with what the strict JSON path reports for it.
+pub struct Parsed {
+    pub value: Value,
+    pub warnings: Vec<Warning>,
+    pub lines: HashMap<String, LineRange>,
+}
+
+/// Parses JSON5, which covers JSONC: `//` and `/* */` comments, trailing
+/// commas, single-quoted strings, unquoted keys, hex numbers, leading or
+/// trailing decimal points and a leading `+`. `Infinity`, `-Infinity` and
+/// `NaN` have no JSON number: they're kept as written (see
+/// [`source::non_finite`]), with a warning. Duplicate keys and lossy numbers
+/// are reported the same way as for strict JSON.
+///
+/// `strict` is the error strict JSON gave for `text`. If the JSON5 parser
+/// fails too before meeting any JSON5-only syntax, the text was meant as
+/// plain JSON, and `strict` is the error returned.
+pub fn parse(text: &str, strict: ParseError) -> Result<Parsed, ParseError> {
+    let mut parser = Parser::new(text);
+    match parser.document() {
+        Ok(document) => Ok(walk(text, &document)),
+        Err(_) if !parser.json5 => Err(strict),
+        Err(error) => Err(error),
+    }
+}
+
+fn walk(text: &str, document: &Document) -> Parsed {
+    let mut walk = Walk::default();
+    let value = walk.value(&document.root, "", document.root.start);
+    let lines = LineIndex::new(text);
+    let lines = walk
+        .spans
+        .into_iter()
+        .map(|(path, start, end)| {
+            let range = LineRange {
+                start: lines.line_of(start),
+                end: lines.line_of(end.saturating_sub(1).max(start)),
+            };
+            (path, range)
+        })
+        .collect();
+    Parsed {
+        value,
+        warnings: walk.warnings,
+        lines,
+    }
+}
+
+/// Re-indents by two spaces per level with one entry per line, keeping every
+/// comment with the entry it was written next to: comments above an entry
+/// stay above it, comments after it on the same line stay there. Keys,
+/// strings and numbers keep their literal form, a container keeps its
+/// trailing comma, and blank lines between entries are kept, collapsed to
+/// one.
+pub fn format(text: &str) -> Result<String, ParseError> {
+    let document = Parser::new(text).document()?;
+    let mut out = String::new();
+    let printed = write_comments(&mut out, &document.leading, 0, false);
+    if document.blank_before_root && printed {
+        out.push('\n');
+    }
+    write_node(&mut out, &document.root, 0);
+    write_trailing(&mut out, &document.root_trailing);
+    out.push('\n');
+    write_comments(&mut out, &document.trailing, 0, true);
+    Ok(out.trim_end_matches('\n').to_string())
+}
+
+struct Document<'t> {
+    leading: Vec<Comment<'t>>,
+    blank_before_root: bool,
+    root: Node<'t>,
+    /// Comments on the root's last line.
+    root_trailing: Vec<Comment<'t>>,
+    trailing: Vec<Comment<'t>>,
+}
+
+struct Comment<'t> {
+    /// As written, delimiters included.
+    text: &'t str,
+    /// Nothing but whitespace precedes it on its line.
+    own_line: bool,
+    /// A blank line separates it from what came before.
+    blank_before: bool,
+}
+
+struct Node<'t> {
+    start: usize,
+    end: usize,
+    raw: &'t str,
+    kind: Kind<'t>,
+}
+
+enum Kind<'t> {
+    Scalar(Value),
+    /// `Infinity`, `-Infinity` or `NaN`.
+    NonFinite(&'static str),
+    Object(Container<'t>),
+    Array(Container<'t>),
+}
+
+#[derive(Default)]
+struct Container<'t> {
+    /// Comments on the line of the opening bracket.
+    open: Vec<Comment<'t>>,
+    entries: Vec<Entry<'t>>,
+    trailing_comma: bool,
+    /// Comments after the last entry.
+    closing: Vec<Comment<'t>>,
+}
+
+impl Container<'_> {
+    fn is_empty(&self) -> bool {
+        self.open.is_empty() && self.entries.is_empty() && self.closing.is_empty()
+    }
+}
+
+/// An object member or array item.
+struct Entry<'t> {
+    /// Comments above the entry, and any written between its key and value.
+    leading: Vec<Comment<'t>>,
+    blank_before: bool,
+    key: Option<Key<'t>>,
+    value: Node<'t>,
+    /// Comments after the entry on the same line.
+    trailing: Vec<Comment<'t>>,
+}
+
+struct Key<'t> {
+    raw: &'t str,
+    name: String,
+    start: usize,
+}
+
+/// Whitespace and comments between two tokens.
+struct Trivia<'t> {
+    comments: Vec<Comment<'t>>,
+    /// A blank line separates the next token from the last comment, or from
+    /// the previous token if there are none.
+    blank_before_token: bool,
+}
+
+struct Parser<'t> {
+    text: &'t str,
+    pos: usize,
+    /// Syntax strict JSON lacks has been seen.
+    json5: bool,
+}
+
+impl<'t> Parser<'t> {
+    fn new(text: &'t str) -> Self {
+        Parser {
+            text,
+            pos: 0,
+            json5: false,
+        }
+    }
+
+    fn document(&mut self) -> Result<Document<'t>, ParseError> {
+        let before = self.trivia()?;
+        let root = self.value(0)?;
+        let after = self.trivia()?;
+        if self.pos < self.text.len() {
+            return Err(self.error("trailing characters"));
+        }
+        let (root_trailing, trailing) = after.comments.into_iter().partition(|c| !c.own_line);
+        Ok(Document {
+            leading: before.comments,
+            blank_before_root: before.blank_before_token,
+            root,
+            root_trailing,
+            trailing,
+        })
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        self.error_at(message, self.pos)
+    }
+
+    fn error_at(&self, message: &str, offset: usize) -> ParseError {
+        ParseError::at(&message, self.text, offset)
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn trivia(&mut self) -> Result<Trivia<'t>, ParseError> {
+        let mut comments = Vec::new();
+        let mut own_line = false;
+        // Line breaks since the last comment.
+        let mut breaks = 0;
+        while let Some(c) = self.peek_char() {
+            let rest = &self.text[self.pos..];
+            if is_line_break(c) {
+                self.json5 |= !matches!(c, '\n' | '\r');
+                if !rest.starts_with("\r\n") {
+                    breaks += 1;
+                }
+                own_line = true;
+                self.pos += c.len_utf8();
+            } else if c.is_whitespace() || c == '\u{FEFF}' {
+                self.json5 |= !matches!(c, ' ' | '\t');
+                self.pos += c.len_utf8();
+            } else if rest.starts_with("//") {
+                self.json5 = true;
+                let len = rest.find(is_line_break).unwrap_or(rest.len());
+                comments.push(Comment {
+                    text: rest[..len].trim_end(),
+                    own_line,
+                    blank_before: breaks > 1,
+                });
+                self.pos += len;
+                breaks = 0;
+            } else if let Some(body) = rest.strip_prefix("/*") {
+                self.json5 = true;
+                let len = body
+                    .find("*/")
+                    .ok_or_else(|| self.error("EOF while parsing a comment"))?
+                    + 4;
+                comments.push(Comment {
+                    text: &rest[..len],
+                    own_line,
+                    blank_before: breaks > 1,
+                });
+                self.pos += len;
+                breaks = 0;
+            } else {
+                break;
+            }
+        }
+        Ok(Trivia {
+            comments,
+            blank_before_token: breaks > 1,
+        })
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Node<'t>, ParseError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("recursion limit exceeded"));
+        }
+        let start = self.pos;
+        let kind = match self.peek() {
+            Some(b'{') => Kind::Object(self.container(b'}', depth)?),
+            Some(b'[') => Kind::Array(self.container(b']', depth)?),
+            Some(b'"' | b'\'') => Kind::Scalar(Value::String(self.string()?)),
+            Some(b'-' | b'+' | b'.' | b'0'..=b'9') => self.number()?,
+            Some(_) => self.literal()?,
+            None => return Err(self.error("EOF while parsing a value")),
+        };
+        Ok(Node {
+            start,
+            end: self.pos,
+            raw: &self.text[start..self.pos],
+            kind,
+        })
+    }
+
+    /// Parses an object (`close` is `}`) or array, attaching comments to
+    /// the entries around them.
+    fn container(&mut self, close: u8, depth: usize) -> Result<Container<'t>, ParseError> {
+        let (eof, expected) = if close == b'}' {
+            ("EOF while parsing an object", "expected `,` or `}`")
+        } else {
+            ("EOF while parsing a list", "expected `,` or `]`")
+        };
+        self.pos += 1;
+        let mut container = Container::default();
+        // Comments on lines of their own, waiting for the entry below them.
+        let mut pending = Vec::new();
+        loop {
+            let trivia = self.trivia()?;
+            for comment in trivia.comments {
+                match container.entries.last_mut() {
+                    _ if comment.own_line || !pending.is_empty() => pending.push(comment),
+                    Some(entry) => entry.trailing.push(comment),
+                    None => container.open.push(comment),
+                }
+            }
+            match self.peek() {
+                Some(b) if b == close => {
+                    self.json5 |= container.trailing_comma;
+                    self.pos += 1;
+                    container.closing = pending;
+                    return Ok(container);
+                }
+                None => return Err(self.error(eof)),
+                _ => {}
+            }
+
+            let mut leading = std::mem::take(&mut pending);
+            let key = if close == b'}' {
+                let key = self.key()?;
+                let mut between = self.trivia()?.comments;
+                if self.peek() != Some(b':') {
+                    return Err(self.error(if self.peek().is_some() {
+                        "expected `:`"
+                    } else {
+                        eof
+                    }));
+                }
+                self.pos += 1;
+                between.extend(self.trivia()?.comments);
+                leading.extend(between.into_iter().map(|comment| Comment {
+                    own_line: true,
+                    blank_before: false,
+                    ..comment
+                }));
+                Some(key)
+            } else {
+                None
+            };
+            let mut entry = Entry {
+                leading,
+                blank_before: trivia.blank_before_token,
+                key,
+                value: self.value(depth + 1)?,
+                trailing: Vec::new(),
+            };
+            for comment in self.trivia()?.comments {
+                if comment.own_line || !pending.is_empty() {
+                    pending.push(comment);
+                } else {
+                    entry.trailing.push(comment);
+                }
+            }
+            container.entries.push(entry);
+            container.trailing_comma = false;
+            match self.peek() {
+                Some(b',') => {
+                    self.pos += 1;
+                    container.trailing_comma = true;
+                }
+                Some(b) if b == close => {}
+                None => return Err(self.error(eof)),
+                _ => return Err(self.error(expected)),
+            }
+        }
+    }
+
+    fn key(&mut self) -> Result<Key<'t>, ParseError> {
+        let start = self.pos;
+        let name = match self.peek_char() {
+            Some('"' | '\'') => self.string()?,
+            Some(c) if is_identifier_start(c) => {
+                self.json5 = true;
+                self.identifier();
+                self.text[start..self.pos].to_string()
+            }
+            _ => return Err(self.error("expected key")),
+        };
+        Ok(Key {
+            raw: &self.text[start..self.pos],
+            name,
+            start,
+        })
+    }
+
+    fn identifier(&mut self) -> &'t str {
+        let start = self.pos;
+        while let Some(c) = self.peek_char().filter(|&c| is_identifier_part(c)) {
+            self.pos += c.len_utf8();
+        }
+        &self.text[start..self.pos]
+    }
+
+    /// `true`, `false`, `null`, `Infinity` or `NaN`.
+    fn literal(&mut self) -> Result<Kind<'t>, ParseError> {
+        let start = self.pos;
+        let value = match self.identifier() {
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            "null" => Value::Null,
+            "Infinity" => return Ok(self.non_finite("Infinity")),
+            "NaN" => return Ok(self.non_finite("NaN")),
+            _ => return Err(self.error_at("expected value", start)),
+        };
+        Ok(Kind::Scalar(value))
+    }
+
+    fn non_finite(&mut self, name: &'static str) -> Kind<'t> {
+        self.json5 = true;
+        Kind::NonFinite(name)
+    }
+
+    fn string(&mut self) -> Result<String, ParseError> {
+        let quote = char::from(self.text.as_bytes()[self.pos]);
+        self.json5 |= quote == '\'';
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let c = self
+                .peek_char()
+                .ok_or_else(|| self.error("EOF while parsing a string"))?;
+            match c {
+                '\\' => {
+                    self.pos += 1;
+                    self.escape(&mut out)?;
+                }
+                '\n' | '\r' => return Err(self.error("unescaped line break in string")),
+                c if c == quote => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                c => {
+                    out.push(c);
+                    self.pos += c.len_utf8();
+                }
+            }
+        }
+    }
+
+    /// Decodes the escape sequence after a backslash. Any character not
+    /// listed escapes to itself; a backslash before a line break continues
+    /// the string on the next line.
+    fn escape(&mut self, out: &mut String) -> Result<(), ParseError> {
+        let start = self.pos - 1;
+        let c = self
+            .peek_char()
+            .ok_or_else(|| self.error("EOF while parsing a string"))?;
+        self.pos += c.len_utf8();
+        self.json5 |= !matches!(c, '"' | '\\' | '/' | 'b' | 'f' | 'n' | 'r' | 't' | 'u');
+        match c {
+            'b' => out.push('\u{8}'),
+            'f' => out.push('\u{c}'),
+            'n' => out.push('\n'),
+            'r' => out.push('\r'),
+            't' => out.push('\t'),
+            'v' => out.push('\u{b}'),
+            '0' if !self.peek().is_some_and(|b| b.is_ascii_digit()) => out.push('\0'),
+            '0'..='9' => return Err(self.error_at("invalid escape", start)),
+            'x' => {
+                let code = self.hex(2, start)?;
+                out.extend(char::from_u32(code));
+            }
+            'u' => {
+                let mut code = self.hex(4, start)?;
+                if (0xD800..0xDC00).contains(&code) && self.text[self.pos..].starts_with("\\u") {
+                    let high = self.pos;
+                    self.pos += 2;
+                    match self.hex(4, high)? {
+                        low @ 0xDC00..0xE000 => {
+                            code = 0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00)
+                        }
+                        _ => self.pos = high,
+                    }
+                }
+                // Lone surrogates have no `char`.
+                out.push(char::from_u32(code).unwrap_or(char::REPLACEMENT_CHARACTER));
+            }
+            '\r' => {
+                if self.peek() == Some(b'\n') {
+                    self.pos += 1;
+                }
+            }
+            c if is_line_break(c) => {}
+            c => out.push(c),
+        }
+        Ok(())
+    }
+
+    fn hex(&mut self, len: usize, escape_start: usize) -> Result<u32, ParseError> {
+        let code = self
+            .text
+            .get(self.pos..self.pos + len)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| self.error_at("invalid escape", escape_start))?;
+        self.pos += len;
+        Ok(code)
+    }
+
+    fn number(&mut self) -> Result<Kind<'t>, ParseError> {
+        let start = self.pos;
+        let negative = self.peek() == Some(b'-');
+        self.json5 |= self.peek() == Some(b'+');
+        if matches!(self.peek(), Some(b'-' | b'+')) {
+            self.pos += 1;
+        }
+        let rest = &self.text[self.pos..];
+        if rest.starts_with(|c: char| c.is_ascii_alphabetic()) && !rest.starts_with(['x', 'X']) {
+            let name = match self.identifier() {
+                "Infinity" if negative => "-Infinity",
+                "Infinity" => "Infinity",
+                // NaN has no sign.
+                "NaN" => "NaN",
+                _ => return Err(self.error_at("invalid number", start)),
+            };
+            return Ok(self.non_finite(name));
+        }
+        if rest.starts_with("0x") || rest.starts_with("0X") {
+            self.json5 = true;
+            self.pos += 2;
+            let digits = self.digits(|b| b.is_ascii_hexdigit());
+            if digits.is_empty() {
+                return Err(self.error_at("invalid number", start));
+            }
+            self.end_of_number(start)?;
+            return Ok(self.hex_number(digits, negative));
+        }
+
+        let int = self.digits(|b| b.is_ascii_digit());
+        if int.len() > 1 && int.starts_with('0') {
+            return Err(self.error_at("invalid number", start));
+        }
+        let mut frac = "";
+        if self.peek() == Some(b'.') {
+            self.pos += 1;
+            frac = self.digits(|b| b.is_ascii_digit());
+        }
+        if int.is_empty() && frac.is_empty() {
+            return Err(self.error_at("invalid number", start));
+        }
+        self.json5 |= int.is_empty() || frac.is_empty() && self.text[..self.pos].ends_with('.');
+        let mut exponent = "";
+        if matches!(self.peek(), Some(b'e' | b'E')) {
+            let exponent_start = self.pos;
+            self.pos += 1;
+            if matches!(self.peek(), Some(b'-' | b'+')) {
+                self.pos += 1;
+            }
+            if self.digits(|b| b.is_ascii_digit()).is_empty() {
+                return Err(self.error_at("invalid number", start));
+            }
+            exponent = &self.text[exponent_start..self.pos];
+        }
+        self.end_of_number(start)?;
+
+        // The same number as strict JSON writes it.
+        let mut literal = String::from(if negative { "-" } else { "" });
+        literal.push_str(if int.is_empty() { "0" } else { int });
+        if !frac.is_empty() {
+            literal.push('.');
+            literal.push_str(frac);
+        }
+        literal.push_str(exponent);
+        let number: Number = serde_json::from_str(&literal)
+            .map_err(|_| self.error_at("number out of range", start))?;
+        Ok(Kind::Scalar(Value::Number(number)))
+    }
+
+    /// Hex numbers are converted exactly, however long.
+    fn hex_number(&self, digits: &str, negative: bool) -> Kind<'t> {
+        // Little-endian base 10^9 limbs.
+        let mut limbs: Vec<u64> = vec![0];
+        for digit in digits.chars().filter_map(|c| c.to_digit(16)) {
+            let mut carry = u64::from(digit);
+            for limb in &mut limbs {
+                let next = *limb * 16 + carry;
+                *limb = next % 1_000_000_000;
+                carry = next / 1_000_000_000;
+            }
+            if carry > 0 {
+                limbs.push(carry);
+            }
+        }
+        let mut decimal = String::from(if negative { "-" } else { "" });
+        let mut limbs = limbs.iter().rev();
+        decimal.push_str(&limbs.next().unwrap_or(&0).to_string());
+        for limb in limbs {
+            decimal.push_str(&format!("{limb:09}"));
+        }
+        let number = serde_json::from_str(&decimal).expect("decimal digits are a valid number");
+        Kind::Scalar(Value::Number(number))
+    }
+
+    fn digits(&mut self, accept: impl Fn(u8) -> bool) -> &'t str {
+        let start = self.pos;
+        while self.peek().is_some_and(&accept) {
+            self.pos += 1;
+        }
+        &self.text[start..self.pos]
+    }
+
+    /// Rejects numbers run together with a name or another number, like
+    /// `12px` or `1.2.3`.
+    fn end_of_number(&self, start: usize) -> Result<(), ParseError> {
+        match self.peek_char() {
+            Some(c) if c == '.' || is_identifier_part(c) => {
+                Err(self.error_at("invalid number", start))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+fn is_line_break(c: char) -> bool {
+    matches!(c, '\n' | '\r' | '\u{2028}' | '\u{2029}')
+}
+
+fn is_identifier_start(c: char) -> bool {
+    c == '$' || c == '_' || c.is_alphabetic()
+}
+
+fn is_identifier_part(c: char) -> bool {
+    is_identifier_start(c) || c.is_alphanumeric() || matches!(c, '\u{200C}' | '\u{200D}')
+}
+
+/// Turns the parsed tree into a value, collecting warnings and the byte
+/// span of every display path on the way.
+#[derive(Default)]
+struct Walk {
+    warnings: Vec<Warning>,
+    spans: Vec<(String, usize, usize)>,
+}
+
+impl Walk {
+    /// Object members span from their key.
+    fn value(&mut self, node: &Node, path: &str, start: usize) -> Value {
+        self.spans.push((path.to_string(), start, node.end));
+        match &node.kind {
+            Kind::Scalar(value) => {
+                if let Value::Number(number) = value {
+                    let warning = source::precision_warning(&number.to_string(), path);
+                    self.warnings.extend(warning);
+                }
+                value.clone()
+            }
+            Kind::NonFinite(name) => {
+                self.warnings.push(Warning {
+                    kind: WarningKind::PrecisionLoss,
+                    path: path.to_string(),
+                    message: format!(
+                        "{name} has no JSON number; it's compared as written and only \
+                         equals another {name}"
+                    ),
+                });
+                source::non_finite(name)
+            }
+            Kind::Object(container) => {
+                let mut map = Map::new();
+                let mut seen = HashSet::new();
+                for entry in &container.entries {
+                    let Some(key) = &entry.key else { continue };
+                    let child = if path.is_empty() {
+                        key.name.clone()
+                    } else {
+                        format!("{path}.{}", key.name)
+                    };
+                    if !seen.insert(key.name.as_str()) {
+                        self.warnings.push(Warning {
+                            kind: WarningKind::DuplicateKey,
+                            path: child.clone(),
+                            message: format!(
+                                "duplicate key \"{}\"; only the last value is kept",
+                                key.name
+                            ),
+                        });
+                    }
+                    let value = self.value(&entry.value, &child, key.start);
+                    map.insert(key.name.clone(), value);
+                }
+                Value::Object(map)
+            }
+            Kind::Array(container) => Value::Array(
+                container
+                    .entries
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, entry)| {
+                        self.value(&entry.value, &format!("{path}[{idx}]"), entry.value.start)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+fn indent(out: &mut String, depth: usize) {
+    out.push_str(&"  ".repeat(depth));
+}
+
+/// Writes comments on lines of their own. A blank line is kept before one
+/// that had it, unless nothing was `printed` yet in the enclosing block.
+fn write_comments(out: &mut String, comments: &[Comment], depth: usize, mut printed: bool) -> bool {
+    for comment in comments {
+        if comment.blank_before && printed {
+            out.push('\n');
+        }
+        indent(out, depth);
+        out.push_str(comment.text);
+        out.push('\n');
+        printed = true;
+    }
+    printed
+}
+
+fn write_trailing(out: &mut String, comments: &[Comment]) {
+    for comment in comments {
+        out.push(' ');
+        out.push_str(comment.text);
+    }
+}
+
+fn write_node(out: &mut String, node: &Node, depth: usize) {
+    match &node.kind {
+        Kind::Scalar(_) | Kind::NonFinite(_) => out.push_str(node.raw),
+        Kind::Object(container) => write_container(out, container, ('{', '}'), depth),
+        Kind::Array(container) => write_container(out, container, ('[', ']'), depth),
+    }
+}
+
+fn write_container(
+    out: &mut String,
+    container: &Container,
+    (open, close): (char, char),
+    depth: usize,
+) {
+    out.push(open);
+    if container.is_empty() {
+        out.push(close);
+        return;
+    }
+    write_trailing(out, &container.open);
+    out.push('\n');
+    let mut printed = false;
+    for (idx, entry) in container.entries.iter().enumerate() {
+        printed = write_comments(out, &entry.leading, depth + 1, printed);
+        if entry.blank_before && printed {
+            out.push('\n');
+        }
+        indent(out, depth + 1);
+        if let Some(key) = &entry.key {
+            out.push_str(key.raw);
+            out.push_str(": ");
+        }
+        write_node(out, &entry.value, depth + 1);
+        if idx + 1 < container.entries.len() || container.trailing_comma {
+            out.push(',');
+        }
+        write_trailing(out, &entry.trailing);
+        out.push('\n');
+        printed = true;
+    }
+    write_comments(out, &container.closing, depth + 1, printed);
+    indent(out, depth);
+    out.push(close);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::canonical;
+    use crate::diff::{self, DiffOptions, DiffType};
+
+    fn parse(text: &str) -> Result<Parsed, ParseError> {
+        super::parse(text, ParseError::new("strict"))
+    }
+
+    fn value(text: &str) -> Value {
+        parse(text).unwrap().value
+    }
+
+    fn error(text: &str) -> ParseError {
+        parse(text).err().expect("an error")
+    }
+
+    const SETTINGS: &str = r#"// VS Code settings
+{
+    // Editor
+    "editor.fontSize": 14, // px
+    "editor.rulers": [80, 120,],
+
+    /* Files */
+    "files.exclude": {
+        "**/.git": true,
+        "**/node_modules": true, /* deps */
+    },
+    "terminal.integrated.env.linux": {}, // empty
+}
+// end
+"#;
+
+    #[test]
+    fn jsonc() {
+        let parsed = parse(SETTINGS).unwrap();
+        assert_eq!(
+            parsed.value,
+            json!({
+                "editor.fontSize": 14,
+                "editor.rulers": [80, 120],
+                "files.exclude": {"**/.git": true, "**/node_modules": true},
+                "terminal.integrated.env.linux": {}
+            })
+        );
+        assert_eq!(parsed.lines[""].start, 2);
+        assert_eq!(parsed.lines["editor.rulers"].start, 5);
+        let exclude = &parsed.lines["files.exclude"];
+        assert_eq!((exclude.start, exclude.end), (8, 11));
+    }
+
+    #[test]
+    fn format_keeps_comments() {
+        let formatted = format(SETTINGS).unwrap();
+        let expected = r#"// VS Code settings
+{
+  // Editor
+  "editor.fontSize": 14, // px
+  "editor.rulers": [
+    80,
+    120,
+  ],
+
+  /* Files */
+  "files.exclude": {
+    "**/.git": true,
+    "**/node_modules": true, /* deps */
+  },
+  "terminal.integrated.env.linux": {}, // empty
+}
+// end"#;
+        assert_eq!(formatted, expected);
+        assert_eq!(format(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn comments_in_every_position() {
+        let text = "/* head */ { // open\n  a /* k */ : /* v */ 1 // one\n  // before comma\n  , b: [ /* x */ ], c: { // only\n }\n\n\n  // closing\n} // root\n// tail";
+        let formatted = format(text).unwrap();
+        assert_eq!(
+            formatted,
+            "/* head */\n{ // open\n  /* k */\n  /* v */\n  a: 1, // one\n  // before comma\n  b: [ /* x */\n  ],\n  c: { // only\n  }\n\n  // closing\n} // root\n// tail"
+        );
+        assert_eq!(format(&formatted).unwrap(), formatted);
+        assert_eq!(value(&formatted), value(text));
+        assert_eq!(value(text), json!({"a": 1, "b": [], "c": {}}));
+    }
+
+    #[test]
+    fn trailing_commas() {
+        assert_eq!(value("[1, 2,]"), json!([1, 2]));
+        assert_eq!(value("{a: {b: 1,},}"), json!({"a": {"b": 1}}));
+        assert_eq!(format("[1,2,]").unwrap(), "[\n  1,\n  2,\n]");
+        assert_eq!(format("[1,2]").unwrap(), "[\n  1,\n  2\n]");
+        assert_eq!(error("[/**/,]").message, "expected value");
+        assert_eq!(error("[0x1,,]").message, "expected value");
+    }
+
+    #[test]
+    fn keys_and_strings() {
+        let v = value(r#"{unquoted: 'single "quoted"', 'it\'s': 1, $id_1: "\x41é😀\v\0", ünï: 2}"#);
+        assert_eq!(
+            v,
+            json!({"unquoted": "single \"quoted\"", "it's": 1, "$id_1": "Aé😀\u{b}\0", "ünï": 2})
+        );
+        // A backslash before a line break continues the string.
+        assert_eq!(value("'a\\\nb\\\r\nc'"), json!("abc"));
+        assert_eq!(value(r#""😀 \q""#), json!("😀 q"));
+        let formatted = format(r#"{a: 'x', "b": "y"}"#).unwrap();
+        assert_eq!(formatted, "{\n  a: 'x',\n  \"b\": \"y\"\n}");
+    }
+
+    #[test]
+    fn numbers() {
+        let v = value(
+            "{hex: 0xFF, neg: -0x10, huge: 0x10000000000000000, lead: .5, trail: 5., \
+             plus: +1, exp: 1e3}",
+        );
+        for (key, expected) in [
+            ("hex", "255"),
+            ("neg", "-16"),
+            ("huge", "18446744073709551616"),
+            ("lead", "0.5"),
+            ("trail", "5"),
+            ("plus", "1"),
+            ("exp", "1e+3"),
+        ] {
+            assert_eq!(v[key].to_string(), expected, "{key}");
+        }
+        let literals = "[0xFF, -0x10, .5, 5., +1]";
+        assert_eq!(
+            format(literals).unwrap(),
+            "[\n  0xFF,\n  -0x10,\n  .5,\n  5.,\n  +1\n]"
+        );
+        // After a JSON5 number, so the JSON5 parser's error is the one reported.
+        for bad in ["12px", "01", "1.2.3", "0x", ".", "+", "1e"] {
+            let e = error(&format!("[.5, {bad}]"));
+            assert_eq!(
+                (e.message.as_str(), e.column),
+                ("invalid number", Some(6)),
+                "{bad}"
+            );
+        }
+    }
+
+    #[test]
+    fn non_finite_numbers_are_kept_as_written() {
+        let parsed = parse("{a: Infinity, b: +Infinity, c: -Infinity, d: NaN, e: -NaN}").unwrap();
+        let texts: Vec<String> = parsed
+            .value
+            .as_object()
+            .unwrap()
+            .values()
+            .map(Value::to_string)
+            .collect();
+        assert_eq!(texts, ["Infinity", "Infinity", "-Infinity", "NaN", "NaN"]);
+        let warnings: Vec<_> = parsed
+            .warnings
+            .iter()
+            .map(|w| (w.kind, w.path.as_str()))
+            .collect();
+        assert_eq!(
+            warnings,
+            ["a", "b", "c", "d", "e"].map(|path| (WarningKind::PrecisionLoss, path))
+        );
+        assert_eq!(
+            parsed.warnings[2].message,
+            "-Infinity has no JSON number; it's compared as written and only equals another \
+             -Infinity"
+        );
+        assert_eq!(
+            format("[+Infinity, -NaN]").unwrap(),
+            "[\n  +Infinity,\n  -NaN\n]"
+        );
+    }
+
+    #[test]
+    fn non_finite_numbers_never_equal_strings() {
+        let numbers = value("[Infinity, -Infinity, NaN]");
+        let strings = json!(["Infinity", "-Infinity", "NaN"]);
+        for (number, string) in numbers
+            .as_array()
+            .unwrap()
+            .iter()
+            .zip(strings.as_array().unwrap())
+        {
+            assert!(!diff::values_equal(number, string), "{number}");
+            assert_ne!(diff::content_hash(number), diff::content_hash(string));
+            assert!(diff::values_equal(number, number));
+        }
+        assert!(!diff::values_equal(&numbers[0], &numbers[1]));
+        assert!(!diff::values_equal(&numbers[0], &json!(1e308)));
+
+        let node =
+            diff::diff_documents(Some(&numbers), Some(&strings), &DiffOptions::default()).unwrap();
+        let rows: Vec<_> = node.child_diffs.iter().flatten().collect();
+        assert!(rows.iter().all(|row| row.diff_type == DiffType::Changed));
+        // The webview gets the name, flagged as a raw number.
+        let left = rows[0].left_node.as_ref().unwrap();
+        assert_eq!(
+            (left.value.clone(), left.raw.as_deref()),
+            (json!("Infinity"), Some("Infinity"))
+        );
+        assert_eq!(rows[0].right_node.as_ref().unwrap().raw, None);
+
+        let error = canonical::canonicalize(&numbers).unwrap_err();
+        assert_eq!(error, "Infinity is out of range for canonical JSON");
+    }
+
+    #[test]
+    fn duplicate_and_lossy() {
+        let parsed = parse("{a: 1, // x\n a: 9007199254740993}").unwrap();
+        assert_eq!(parsed.value["a"].to_string(), "9007199254740993");
+        let warnings: Vec<_> = parsed
+            .warnings
+            .iter()
+            .map(|w| (w.kind, w.path.as_str()))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                (WarningKind::DuplicateKey, "a"),
+                (WarningKind::PrecisionLoss, "a")
+            ]
+        );
+    }
+
+    #[test]
+    fn crlf_and_bom() {
+        let text = "\u{FEFF}{\r\n  // c\r\n  a: 1,\r\n\r\n  b: 2\r\n}\r\n";
+        assert_eq!(format(text).unwrap(), "{\n  // c\n  a: 1,\n\n  b: 2\n}");
+        assert_eq!(parse(text).unwrap().lines["b"].start, 5);
+    }
+
+    #[test]
+    fn errors() {
+        let e = error("{\n  // c\n  \"a\": 1\n  \"b\": 2\n}");
+        assert_eq!(
+            (e.message.as_str(), e.line, e.column),
+            ("expected `,` or `}`", Some(4), Some(3))
+        );
+        assert_eq!(e.expected.as_deref(), Some("`,` or `}`"));
+        for (text, message) in [
+            ("{a: 1 /* open", "EOF while parsing a comment"),
+            ("[1, 2,] x", "trailing characters"),
+            ("{a: 1, 1: 2}", "expected key"),
+            ("{a 1}", "expected `:`"),
+            ("[1, // c\n", "EOF while parsing a list"),
+            ("{a: 'x\ny'}", "unescaped line break in string"),
+            ("'\\1'", "invalid escape"),
+            ("'\\u12'", "invalid escape"),
+            ("{a: tru}", "expected value"),
+            ("// only a comment", "EOF while parsing a value"),
+        ] {
+            assert_eq!(error(text).message, message, "{text}");
+        }
+        let deep = format!("// c\n{}", "[".repeat(200));
+        assert_eq!(error(&deep).message, "recursion limit exceeded");
+    }
+
+    #[test]
+    fn plain_json_mistakes_keep_the_strict_error() {
+        // Nothing JSON5-only comes before the mistake.
+        for text in ["{\"a\": tru}", "[1 2]", "{\"a\": 01}", "{\"a\" 1}"] {
+            assert_eq!(error(text).message, "strict", "{text}");
+        }
+        for text in [
+            "// c\n{\"a\": tru}",
+            "{'a': tru}",
+            "{a: tru}",
+            "[1, 2,] x",
+            "[0x1 2]",
+            "[.5 2]",
+            "[Infinity 2]",
+        ] {
+            assert_ne!(error(text).message, "strict", "{text}");
+        }
+    }
+}
@@ -1,17 +1,533 @@
 // Prevents additional console window on Windows in release, DO NOT REMOVE!!
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
+mod canonical;
+mod diff;
+mod dirdiff;
+mod document;
+mod embedded;
+mod git;
+mod history;
+mod json5doc;
+mod merge;
+mod patch;
+mod path;
+mod source;
+mod stream;
+mod table;
+mod textdiff;
+mod tomldoc;
+mod unified;
+mod xmldoc;
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+
+use canonical::Canonical;
+use diff::{ArrayKey, DiffNode, DiffOptions};
+use dirdiff::DirNode;
+use document::{Format, ParseError, Warning};
+use git::{Commit, Tracked};
+use history::Revision;
+use merge::{Conflict, Side};
+use patch::PatchKind;
+use serde::Serialize;
+use source::LineRange;
+use stream::{Progress, StreamDiff, StreamOptions, StreamRow, Summary};
+use table::{TableDiff, TableDiffOptions};
+use tauri::ipc::Channel;
+use tauri::webview::PageLoadEvent;
+use tauri::{Manager, RunEvent, State, Webview, WindowEvent};
+use textdiff::{TextDiff, TextDiffOptions};
+use unified::FilePatch;
+
 #[tauri::command]
 fn read_file_content(path: String) -> Result<String, String> {
     std::fs::read_to_string(&path).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+fn write_file_content(path: String, content: String) -> Result<(), String> {
+    std::fs::write(&path, content).map_err(|e| e.to_string())
+}
+
+/// Checks that `text` parses and maps each display path to the source lines
+/// it spans, so diff rows can be traced back to the text. Failures carry the
+/// line, column and offset of the problem.
+#[tauri::command]
+async fn parse_document(
+    text: String,
+    format: Format,
+) -> Result<HashMap<String, LineRange>, ParseError> {
+    tauri::async_runtime::spawn_blocking(move || document::line_map(&text, format))
+        .await
+        .map_err(|e| ParseError::new(e.to_string()))?
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiffOutput {
+    root: Option<DiffNode>,
+    left_warnings: Vec<Warning>,
+    right_warnings: Vec<Warning>,
+}
+
+#[tauri::command]
+async fn diff_documents(
+    left: String,
+    right: String,
+    format: Format,
+    options: Option<DiffOptions>,
+) -> Result<DiffOutput, String> {
+    let options = options.unwrap_or_default();
+    // Parsing and diffing multi-megabyte documents is CPU bound; keep it off
+    // the async runtime so other IPC calls stay responsive.
+    tauri::async_runtime::spawn_blocking(move || -> Result<DiffOutput, String> {
+        let (left, left_warnings) =
+            document::parse_with_warnings(&left, format).map_err(|e| format!("left: {e}"))?;
+        let (right, right_warnings) =
+            document::parse_with_warnings(&right, format).map_err(|e| format!("right: {e}"))?;
+        Ok(DiffOutput {
+            root: diff::diff_documents(left.as_ref(), right.as_ref(), &options),
+            left_warnings,
+            right_warnings,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Diffs every JSON, YAML, TOML and XML file under two directories, paired
+/// by relative path, and summarizes the result as a tree.
+#[tauri::command]
+async fn diff_directories(
+    left: PathBuf,
+    right: PathBuf,
+    options: Option<DiffOptions>,
+) -> Result<DirNode, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || dirdiff::diff_dirs(&left, &right, &options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Row diff of two CSV/TSV files. Encoding, delimiter and quoting are
+/// sniffed per file; rows are paired by the key columns, or by position.
+#[tauri::command]
+async fn diff_tables(
+    left: PathBuf,
+    right: PathBuf,
+    options: Option<TableDiffOptions>,
+) -> Result<TableDiff, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || -> Result<TableDiff, String> {
+        let left = table::read(&left)?;
+        let right = table::read(&right)?;
+        table::diff_tables(&left, &right, &options)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Returns a pretty-printed patch that turns `left` into `right`. An empty
+/// side counts as `null`.
+#[tauri::command]
+async fn create_patch(
+    left: String,
+    right: String,
+    format: Format,
+    kind: PatchKind,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<String, String> {
+        let left = document::parse(&left, format).map_err(|e| format!("left: {e}"))?;
+        let right = document::parse(&right, format).map_err(|e| format!("right: {e}"))?;
+        let patch = patch::create(&left.unwrap_or_default(), &right.unwrap_or_default(), kind);
+        serde_json::to_string_pretty(&patch).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Applies a JSON Patch or Merge Patch to `document` and returns the result
+/// in the document's format. The patch kind is detected when not given.
+#[tauri::command]
+async fn apply_patch(
+    document: String,
+    format: Format,
+    patch: String,
+    kind: Option<PatchKind>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<String, String> {
+        let document = document::parse(&document, format)?.unwrap_or_default();
+        let patch = patch::parse(&patch).map_err(|e| format!("patch: {e}"))?;
+        let kind = kind.unwrap_or_else(|| PatchKind::detect(&patch));
+        let patched = patch::apply(document, &patch, kind)?;
+        document::to_string(&patched, format)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Reformats `text` for the Format button of views the webview can't parse
+/// itself. TOML and XML keep their comments and layout.
+#[tauri::command]
+async fn format_document(text: String, format: Format) -> Result<String, ParseError> {
+    tauri::async_runtime::spawn_blocking(move || document::format(&text, format))
+        .await
+        .map_err(|e| ParseError::new(e.to_string()))?
+}
+
+/// RFC 8785 canonical form and SHA-256 of `text`, or of the subtree at the
+/// display path `path` when given.
+#[tauri::command]
+async fn canonicalize_document(
+    text: String,
+    format: Format,
+    path: Option<String>,
+) -> Result<Canonical, String> {
+    tauri::async_runtime::spawn_blocking(move || -> Result<Canonical, String> {
+        let value = document::parse(&text, format)?.ok_or("document is empty")?;
+        canonical::canonical_at(&value, path.as_deref().unwrap_or_default())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// Line diff of two arbitrary texts, for files that aren't JSON or YAML.
+#[tauri::command]
+async fn diff_text(
+    left: String,
+    right: String,
+    options: Option<TextDiffOptions>,
+) -> Result<TextDiff, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || textdiff::diff_lines(&left, &right, &options))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// Parses a `.diff`/`.patch` file into side-by-side hunks per file.
+#[tauri::command]
+async fn parse_unified_diff(text: String) -> Result<Vec<FilePatch>, String> {
+    tauri::async_runtime::spawn_blocking(move || unified::parse(&text))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Writes the line diff of two texts as a unified diff.
+#[tauri::command]
+async fn write_unified_diff(
+    left: String,
+    right: String,
+    left_name: String,
+    right_name: String,
+    options: Option<TextDiffOptions>,
+) -> Result<String, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        unified::write(&left, &right, &left_name, &right_name, &options)
+    })
+    .await
+    .map_err(|e| e.to_string())
+}
+
+/// Commits that touched the file at `path`, newest first.
+#[tauri::command]
+async fn git_file_log(path: PathBuf, limit: Option<usize>) -> Result<Vec<Commit>, String> {
+    tauri::async_runtime::spawn_blocking(move || Tracked::locate(&path)?.log(limit))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Revisions of the repository holding `path` to offer in a picker.
+#[tauri::command]
+async fn git_refs(path: PathBuf) -> Result<Vec<String>, String> {
+    tauri::async_runtime::spawn_blocking(move || Tracked::locate(&path)?.refs())
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Contents of the file at `path` as of `rev`. `at_path` is the file's path
+/// in the repository at that revision, when it has since been renamed.
+#[tauri::command]
+async fn git_show_file(
+    path: PathBuf,
+    rev: String,
+    at_path: Option<String>,
+) -> Result<String, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        Tracked::locate(&path)?.show(&rev, at_path.as_deref())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// The commits that touched a JSON, YAML, TOML or XML file, each with the
+/// structural diff stats against the file's previous version.
+#[tauri::command]
+async fn git_file_history(
+    path: PathBuf,
+    format: Format,
+    options: Option<DiffOptions>,
+    limit: Option<usize>,
+) -> Result<Vec<Revision>, String> {
+    let options = options.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || {
+        history::file_history(&path, format, &options, limit)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Serialize)]
+struct MergeOutput {
+    merged: String,
+    conflicts: Vec<Conflict>,
+}
+
+/// Three-way merges `ours` and `theirs` against `base`, pairing elements of
+/// arrays that match `array_keys` by identity field. Conflicts listed in
+/// `resolutions` (JSON Pointer to side) are settled accordingly, the rest
+/// take ours; all of them are reported back so they can be reviewed.
+#[tauri::command]
+async fn merge_documents(
+    base: String,
+    ours: String,
+    theirs: String,
+    format: Format,
+    array_keys: Option<Vec<ArrayKey>>,
+    resolutions: Option<HashMap<String, Side>>,
+) -> Result<MergeOutput, String> {
+    let array_keys = array_keys.unwrap_or_default();
+    let resolutions = resolutions.unwrap_or_default();
+    tauri::async_runtime::spawn_blocking(move || -> Result<MergeOutput, String> {
+        let base = document::parse(&base, format).map_err(|e| format!("base: {e}"))?;
+        let ours = document::parse(&ours, format).map_err(|e| format!("ours: {e}"))?;
+        let theirs = document::parse(&theirs, format).map_err(|e| format!("theirs: {e}"))?;
+        let result = merge::merge(
+            base.as_ref(),
+            ours.as_ref(),
+            theirs.as_ref(),
+            &array_keys,
+            &resolutions,
+        );
+        let merged = match result.merged {
+            Some(value) => document::to_string(&value, format)?,
+            None => String::new(),
+        };
+        Ok(MergeOutput {
+            merged,
+            conflicts: result.conflicts,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[derive(Clone, Serialize)]
+#[serde(tag = "event", rename_all = "camelCase")]
+enum StreamEvent {
+    Progress(Progress),
+    #[serde(rename_all = "camelCase")]
+    Rows {
+        start: u64,
+        changes_only: bool,
+        rows: Vec<StreamRow>,
+    },
+}
+
+/// An open streaming diff, the channel its rows are sent on and the webview
+/// that opened it.
+struct StreamSession {
+    diff: Mutex<StreamDiff>,
+    events: Channel<StreamEvent>,
+    webview: String,
+}
+
+/// Open streaming diffs. A diff's work directory goes when the last
+/// reference to it does: on `close_stream_diff`, when its channel is gone,
+/// when its webview reloads or closes, or when the app exits.
+#[derive(Default)]
+struct StreamSessions {
+    next_id: AtomicU32,
+    open: Mutex<HashMap<u32, Arc<StreamSession>>>,
+}
+
+impl StreamSessions {
+    fn get(&self, session: u32) -> Result<Arc<StreamSession>, String> {
+        let open = self.open.lock().map_err(|e| e.to_string())?;
+        open.get(&session)
+            .cloned()
+            .ok_or_else(|| format!("no open diff {session}"))
+    }
+
+    fn close(&self, session: u32) {
+        self.close_where(|id, _| *id == session);
+    }
+
+    /// Closes the diffs a webview opened; nothing on its new page knows them.
+    fn close_webview(&self, label: &str) {
+        self.close_where(|_, open| open.webview == label);
+    }
+
+    /// Deleting the files can take a while, so it's left to a blocking
+    /// thread.
+    fn close_where(&self, closes: impl Fn(&u32, &StreamSession) -> bool) {
+        let Ok(mut open) = self.open.lock() else {
+            return;
+        };
+        let closed: Vec<_> = open
+            .extract_if(|id, session| closes(id, &**session))
+            .collect();
+        if !closed.is_empty() {
+            tauri::async_runtime::spawn_blocking(move || drop(closed));
+        }
+    }
+
+    fn close_all(&self) {
+        if let Ok(mut open) = self.open.lock() {
+            open.clear();
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamDiffStarted {
+    session: u32,
+    summary: Summary,
+}
+
+/// Diffs two JSON or JSON Lines files too large to load into the webview.
+/// Progress is reported on `events`; the rows stay on disk until requested
+/// with `stream_diff_rows`.
+#[tauri::command]
+async fn stream_diff(
+    left_path: PathBuf,
+    right_path: PathBuf,
+    options: Option<StreamOptions>,
+    events: Channel<StreamEvent>,
+    webview: Webview,
+    sessions: State<'_, StreamSessions>,
+) -> Result<StreamDiffStarted, String> {
+    let progress = events.clone();
+    let options = options.unwrap_or_default();
+    let diff = tauri::async_runtime::spawn_blocking(move || {
+        StreamDiff::build(&left_path, &right_path, &options, &mut |p| {
+            let _ = progress.send(StreamEvent::Progress(p));
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    let session = sessions.next_id.fetch_add(1, Ordering::Relaxed);
+    let summary = diff.summary.clone();
+    let open = StreamSession {
+        diff: Mutex::new(diff),
+        events,
+        webview: webview.label().to_string(),
+    };
+    sessions
+        .open
+        .lock()
+        .map_err(|e| e.to_string())?
+        .insert(session, Arc::new(open));
+    Ok(StreamDiffStarted { session, summary })
+}
+
+/// Sends one window of rows of an open streaming diff over its channel. The
+/// diff is closed if the channel is gone.
+#[tauri::command]
+async fn stream_diff_rows(
+    session: u32,
+    start: u64,
+    count: usize,
+    changes_only: bool,
+    sessions: State<'_, StreamSessions>,
+) -> Result<(), String> {
+    let open = sessions.get(session)?;
+    let sent = tauri::async_runtime::spawn_blocking(move || {
+        let rows = open
+            .diff
+            .lock()
+            .map_err(|e| e.to_string())?
+            .rows(start, count, changes_only)?;
+        Ok::<_, String>(open.events.send(StreamEvent::Rows {
+            start,
+            changes_only,
+            rows,
+        }))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    sent.map_err(|e| {
+        sessions.close(session);
+        e.to_string()
+    })
+}
+
+/// Closes a streaming diff and deletes its files.
+#[tauri::command]
+async fn close_stream_diff(
+    session: u32,
+    sessions: State<'_, StreamSessions>,
+) -> Result<(), String> {
+    sessions.close(session);
+    Ok(())
+}
+
 fn main() {
     tauri::Builder::default()
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
-        .invoke_handler(tauri::generate_handler![read_file_content])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .manage(StreamSessions::default())
+        .on_page_load(|webview, payload| {
+            if payload.event() == PageLoadEvent::Started {
+                webview
+                    .state::<StreamSessions>()
+                    .close_webview(webview.label());
+            }
+        })
+        .on_window_event(|window, event| {
+            // Webview windows share their label with their webview.
+            if let WindowEvent::Destroyed = event {
+                window
+                    .state::<StreamSessions>()
+                    .close_webview(window.label());
+            }
+        })
+        .invoke_handler(tauri::generate_handler![
+            read_file_content,
+            write_file_content,
+            parse_document,
+            diff_documents,
+            diff_directories,
+            diff_tables,
+            create_patch,
+            apply_patch,
+            canonicalize_document,
+            format_document,
+            diff_text,
+            parse_unified_diff,
+            write_unified_diff,
+            git_file_log,
+            git_refs,
+            git_show_file,
+            git_file_history,
+            merge_documents,
+            stream_diff,
+            stream_diff_rows,
+            close_stream_diff
+        ])
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app, event| {
+            // The process exits without dropping managed state.
+            if let RunEvent::Exit = event {
+                app.state::<StreamSessions>().close_all();
+            }
+        });
 }
@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use similar::{Algorithm, DiffTag};
+
+use crate::diff::{content_hash, values_equal, ArrayKey};
+use crate::patch::escape_pointer_token;
+use crate::path::Segment;
+use crate::source;
+
+/// Which input a conflict is resolved with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    Base,
+    #[default]
+    Ours,
+    Theirs,
+}
+
+/// A path changed differently on both sides. Missing values mean the side
+/// deleted (or never had) the entry. Where the sides edited the same run of
+/// array elements differently, the values are the runs each side has there,
+/// and `path` is the run's first index in ours.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Conflict {
+    pub path: String,
+    /// JSON Pointer of the conflicting entry; used as its id when resolving.
+    pub pointer: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub base: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ours: Option<Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theirs: Option<Value>,
+    pub resolution: Side,
+}
+
+#[derive(Debug)]
+pub struct MergeResult {
+    pub merged: Option<Value>,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Three-way merges `ours` and `theirs` against their common `base`.
+///
+/// Changes on only one side are taken as is. Objects are merged key by key.
+/// Arrays matching one of `array_keys` are merged element by element, paired
+/// by identity field, in ours' order followed by what only theirs has.
+/// Other arrays are aligned with base by content, as diff3 aligns lines:
+/// runs of elements only one side touched take that side's run, and runs
+/// both sides edited are merged element by element if all three are the
+/// same length. Anything else that changed on both sides is a conflict,
+/// settled by `resolutions` (keyed by JSON Pointer) or by taking ours.
+pub fn merge(
+    base: Option<&Value>,
+    ours: Option<&Value>,
+    theirs: Option<&Value>,
+    array_keys: &[ArrayKey],
+    resolutions: &HashMap<String, Side>,
+) -> MergeResult {
+    let mut merger = Merger {
+        array_keys,
+        resolutions,
+        segments: Vec::new(),
+        conflicts: Vec::new(),
+    };
+    let merged = merger.merge(base, ours, theirs, "", "");
+    MergeResult {
+        merged,
+        conflicts: merger.conflicts,
+    }
+}
+
+struct Merger<'r> {
+    array_keys: &'r [ArrayKey],
+    resolutions: &'r HashMap<String, Side>,
+    /// Path of the value being merged, as matched by `array_keys`.
+    segments: Vec<Segment>,
+    conflicts: Vec<Conflict>,
+}
+
+impl<'r> Merger<'r> {
+    fn merge(
+        &mut self,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+        path: &str,
+        pointer: &str,
+    ) -> Option<Value> {
+        if same(ours, theirs) || same(theirs, base) {
+            return ours.cloned();
+        }
+        if same(ours, base) {
+            return theirs.cloned();
+        }
+
+        match (base, ours, theirs) {
+            (base, Some(Value::Object(o)), Some(Value::Object(t)))
+                if base.is_none_or(Value::is_object) =>
+            {
+                let empty = Map::new();
+                let b = base.and_then(Value::as_object).unwrap_or(&empty);
+                let keys = o
+                    .keys()
+                    .chain(t.keys().filter(|k| !o.contains_key(*k)))
+                    .chain(
+                        b.keys()
+                            .filter(|k| !o.contains_key(*k) && !t.contains_key(*k)),
+                    );
+                let mut merged = Map::new();
+                for key in keys {
+                    let path = if path.is_empty() {
+                        key.clone()
+                    } else {
+                        format!("{path}.{key}")
+                    };
+                    let pointer = format!("{pointer}/{}", escape_pointer_token(key));
+                    let (b, o, t) = (b.get(key), o.get(key), t.get(key));
+                    let segment = Segment::Field(key.clone());
+                    if let Some(value) = self.merge_child(segment, b, o, t, &path, &pointer) {
+                        merged.insert(key.clone(), value);
+                    }
+                }
+                Some(Value::Object(merged))
+            }
+            (Some(Value::Array(b)), Some(Value::Array(o)), Some(Value::Array(t))) => {
+                let keyed = self
+                    .array_key()
+                    .and_then(|key| self.merge_by_key(b, o, t, key, path, pointer));
+                let merged = keyed.unwrap_or_else(|| self.merge_aligned(b, o, t, path, pointer));
+                Some(Value::Array(merged))
+            }
+            _ => self.conflict(base, ours, theirs, path, pointer),
+        }
+    }
+
+    /// Merges the child of the current value under `segment`.
+    fn merge_child(
+        &mut self,
+        segment: Segment,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+        path: &str,
+        pointer: &str,
+    ) -> Option<Value> {
+        self.segments.push(segment);
+        let merged = self.merge(base, ours, theirs, path, pointer);
+        self.segments.pop();
+        merged
+    }
+
+    /// Identity field configured for the elements of the array being
+    /// merged.
+    fn array_key(&self) -> Option<&'r str> {
+        let element = [&self.segments[..], &[Segment::Index(0)]].concat();
+        self.array_keys
+            .iter()
+            .find(|rule| rule.path.matches(&element))
+            .map(|rule| rule.key.as_str())
+    }
+
+    /// Merges elements paired by their `key` field, or returns `None` when
+    /// some element lacks the field or shares its value with another on the
+    /// same side.
+    fn merge_by_key(
+        &mut self,
+        base: &[Value],
+        ours: &[Value],
+        theirs: &[Value],
+        key: &str,
+        path: &str,
+        pointer: &str,
+    ) -> Option<Vec<Value>> {
+        let index = |items: &[Value]| -> Option<HashMap<u64, usize>> {
+            let mut ids = HashMap::new();
+            for (idx, item) in items.iter().enumerate() {
+                if ids.insert(content_hash(item.get(key)?), idx).is_some() {
+                    return None;
+                }
+            }
+            Some(ids)
+        };
+        let (b, o, t) = (index(base)?, index(ours)?, index(theirs)?);
+        let id = |item: &Value| item.get(key).map(content_hash).unwrap_or_default();
+        let mut order: Vec<u64> = ours.iter().map(id).collect();
+        order.extend(theirs.iter().map(id).filter(|id| !o.contains_key(id)));
+
+        let mut merged = Vec::with_capacity(order.len());
+        for (idx, id) in order.iter().enumerate() {
+            let path = format!("{path}[{idx}]");
+            let pointer = format!("{pointer}/{idx}");
+            let [b, o, t] = [(&b, base), (&o, ours), (&t, theirs)]
+                .map(|(ids, items)| ids.get(id).map(|&idx| &items[idx]));
+            merged.extend(self.merge_child(Segment::Index(idx), b, o, t, &path, &pointer));
+        }
+        Some(merged)
+    }
+
+    /// Merges arrays aligned with base by content. Elements unchanged on
+    /// all three sides split the arrays into runs, each merged on its own.
+    fn merge_aligned(
+        &mut self,
+        base: &[Value],
+        ours: &[Value],
+        theirs: &[Value],
+        path: &str,
+        pointer: &str,
+    ) -> Vec<Value> {
+        let in_ours = align(base, ours);
+        let in_theirs = align(base, theirs);
+        let mut merged = Vec::with_capacity(ours.len().max(theirs.len()));
+        let (mut b, mut o, mut t) = (0, 0, 0);
+        loop {
+            let stable =
+                (b..base.len()).find_map(|idx| Some((idx, in_ours[idx]?, in_theirs[idx]?)));
+            let (b_end, o_end, t_end) = stable.unwrap_or((base.len(), ours.len(), theirs.len()));
+            if (b, o, t) != (b_end, o_end, t_end) {
+                let runs = (&base[b..b_end], &ours[o..o_end], &theirs[t..t_end]);
+                self.merge_run(runs, o, path, pointer, &mut merged);
+            }
+            let Some((b_at, o_at, t_at)) = stable else {
+                return merged;
+            };
+            merged.push(ours[o_at].clone());
+            (b, o, t) = (b_at + 1, o_at + 1, t_at + 1);
+        }
+    }
+
+    /// Merges one run of elements between two stable ones; `start` is
+    /// where ours' run begins.
+    fn merge_run(
+        &mut self,
+        (base, ours, theirs): (&[Value], &[Value], &[Value]),
+        start: usize,
+        path: &str,
+        pointer: &str,
+        merged: &mut Vec<Value>,
+    ) {
+        if same_run(ours, base) {
+            merged.extend_from_slice(theirs);
+        } else if same_run(theirs, base) || same_run(ours, theirs) {
+            merged.extend_from_slice(ours);
+        } else if ours.len() == base.len() && theirs.len() == base.len() {
+            for (idx, ((b, o), t)) in base.iter().zip(ours).zip(theirs).enumerate() {
+                let path = format!("{path}[{}]", start + idx);
+                let pointer = format!("{pointer}/{}", start + idx);
+                let segment = Segment::Index(start + idx);
+                merged.extend(self.merge_child(
+                    segment,
+                    Some(b),
+                    Some(o),
+                    Some(t),
+                    &path,
+                    &pointer,
+                ));
+            }
+        } else {
+            let path = format!("{path}[{start}]");
+            let pointer = format!("{pointer}/{start}");
+            let [b, o, t] = [base, ours, theirs].map(|run| Value::Array(run.to_vec()));
+            if let Some(Value::Array(run)) =
+                self.conflict(Some(&b), Some(&o), Some(&t), &path, &pointer)
+            {
+                merged.extend(run);
+            }
+        }
+    }
+
+    fn conflict(
+        &mut self,
+        base: Option<&Value>,
+        ours: Option<&Value>,
+        theirs: Option<&Value>,
+        path: &str,
+        pointer: &str,
+    ) -> Option<Value> {
+        let resolution = self.resolutions.get(pointer).copied().unwrap_or_default();
+        self.conflicts.push(Conflict {
+            path: path.to_string(),
+            pointer: pointer.to_string(),
+            base: base.map(source::webview_value),
+            ours: ours.map(source::webview_value),
+            theirs: theirs.map(source::webview_value),
+            resolution,
+        });
+        match resolution {
+            Side::Base => base.cloned(),
+            Side::Ours => ours.cloned(),
+            Side::Theirs => theirs.cloned(),
+        }
+    }
+}
+
+/// For each element of `base`, the index of the element of `side` the
+/// longest common subsequence pairs it with.
+fn align(base: &[Value], side: &[Value]) -> Vec<Option<usize>> {
+    let base_hashes: Vec<u64> = base.iter().map(content_hash).collect();
+    let side_hashes: Vec<u64> = side.iter().map(content_hash).collect();
+    let mut aligned = vec![None; base.len()];
+    for op in similar::capture_diff_slices(Algorithm::Myers, &base_hashes, &side_hashes) {
+        if let (DiffTag::Equal, old, new) = op.as_tag_tuple() {
+            for (b, s) in old.zip(new) {
+                aligned[b] = Some(s);
+            }
+        }
+    }
+    aligned
+}
+
+fn same_run(left: &[Value], right: &[Value]) -> bool {
+    left.len() == right.len() && left.iter().zip(right).all(|(l, r)| values_equal(l, r))
+}
+
+/// Both sides hold equal values, or both hold none.
+fn same(left: Option<&Value>, right: Option<&Value>) -> bool {
+    match (left, right) {
+        (Some(left), Some(right)) => values_equal(left, right),
+        _ => left.is_none() && right.is_none(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::path::PathPattern;
+
+    fn merge3(base: Value, ours: Value, theirs: Value) -> MergeResult {
+        merge(
+            Some(&base),
+            Some(&ours),
+            Some(&theirs),
+            &[],
+            &HashMap::new(),
+        )
+    }
+
+    fn keyed(path: &str, key: &str) -> Vec<ArrayKey> {
+        vec![ArrayKey {
+            path: PathPattern::parse(path).unwrap(),
+            key: key.to_string(),
+        }]
+    }
+
+    fn pointers(result: &MergeResult) -> Vec<&str> {
+        result
+            .conflicts
+            .iter()
+            .map(|c| c.pointer.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn clean_merges() {
+        let result = merge3(
+            json!({"a": 1, "b": {"c": 1, "d": 1}, "e": 1}),
+            json!({"a": 2, "b": {"c": 1, "d": 2}, "e": 1}),
+            json!({"a": 1, "b": {"c": 3, "d": 1}, "f": 1}),
+        );
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            result.merged,
+            Some(json!({"a": 2, "b": {"c": 3, "d": 2}, "f": 1}))
+        );
+        // Both sides adding the same key from nothing.
+        let result = merge(
+            None,
+            Some(&json!({"x": 1})),
+            Some(&json!({"y": 2})),
+            &[],
+            &HashMap::new(),
+        );
+        assert_eq!(result.merged, Some(json!({"x": 1, "y": 2})));
+    }
+
+    #[test]
+    fn identical_changes_on_both_sides() {
+        let result = merge3(
+            json!({"a": 1, "list": [1, 2], "gone": true}),
+            json!({"a": 2.0, "list": [1, 2, 3]}),
+            json!({"a": 2, "list": [1, 2, 3]}),
+        );
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.merged, Some(json!({"a": 2.0, "list": [1, 2, 3]})));
+    }
+
+    #[test]
+    fn conflicts_take_ours_unless_resolved() {
+        let (base, ours, theirs) = (
+            json!({"a": 1, "del": {"x": 1}, "mod": 1}),
+            json!({"a": 2, "mod": 2}),
+            json!({"a": 3, "del": {"x": 2}}),
+        );
+        let result = merge3(base.clone(), ours.clone(), theirs.clone());
+        assert_eq!(pointers(&result), ["/a", "/mod", "/del"]);
+        // Modified on one side, deleted on the other.
+        let del = &result.conflicts[2];
+        assert_eq!((del.path.as_str(), del.ours.as_ref()), ("del", None));
+        assert_eq!(del.theirs, Some(json!({"x": 2})));
+        assert_eq!(result.merged, Some(json!({"a": 2, "mod": 2})));
+
+        let resolutions = HashMap::from([
+            ("/a".to_string(), Side::Theirs),
+            ("/mod".to_string(), Side::Theirs),
+            ("/del".to_string(), Side::Base),
+        ]);
+        let result = merge(Some(&base), Some(&ours), Some(&theirs), &[], &resolutions);
+        assert_eq!(result.merged, Some(json!({"a": 3, "del": {"x": 1}})));
+        assert!(result.conflicts.iter().all(|c| c.resolution != Side::Ours));
+    }
+
+    #[test]
+    fn arrays_of_different_lengths_merge_by_alignment() {
+        // Ours inserts at the front, theirs appends and edits the middle.
+        let result = merge3(
+            json!([1, 2, {"v": 3}, 4]),
+            json!([0, 1, 2, {"v": 3}, 4]),
+            json!([1, 2, {"v": 30}, 4, 5]),
+        );
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.merged, Some(json!([0, 1, 2, {"v": 30}, 4, 5])));
+
+        // Deletions on both sides in different places.
+        let result = merge3(
+            json!([1, 2, 3, 4, 5]),
+            json!([2, 3, 4, 5]),
+            json!([1, 2, 3, 5]),
+        );
+        assert_eq!(result.merged, Some(json!([2, 3, 5])));
+
+        // The same element edited in different fields.
+        let result = merge3(
+            json!(["x", {"a": 1, "b": 1}, "y"]),
+            json!(["x", {"a": 2, "b": 1}, "y", "z"]),
+            json!(["w", "x", {"a": 1, "b": 2}, "y"]),
+        );
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            result.merged,
+            Some(json!(["w", "x", {"a": 2, "b": 2}, "y", "z"]))
+        );
+    }
+
+    #[test]
+    fn overlapping_array_edits_conflict_on_the_run() {
+        let base = json!({"l": [1, 2, 3, 4]});
+        let ours = json!({"l": [1, 9, 9, 4, 5]});
+        let theirs = json!({"l": [0, 1, 8, 4]});
+        let result = merge3(base.clone(), ours.clone(), theirs.clone());
+        assert_eq!(pointers(&result), ["/l/1"]);
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.path, "l[1]");
+        assert_eq!(conflict.base, Some(json!([2, 3])));
+        assert_eq!(conflict.ours, Some(json!([9, 9])));
+        assert_eq!(conflict.theirs, Some(json!([8])));
+        // The runs around it still merge.
+        assert_eq!(result.merged, Some(json!({"l": [0, 1, 9, 9, 4, 5]})));
+
+        let resolutions = HashMap::from([("/l/1".to_string(), Side::Theirs)]);
+        let result = merge(Some(&base), Some(&ours), Some(&theirs), &[], &resolutions);
+        assert_eq!(result.merged, Some(json!({"l": [0, 1, 8, 4, 5]})));
+    }
+
+    #[test]
+    fn keyed_arrays_merge_by_identity() {
+        let base =
+            json!({"items": [{"id": "a", "v": 1}, {"id": "b", "v": 1}, {"id": "c", "v": 1}]});
+        // Ours reorders and edits `a`; theirs edits `c`, drops `b` and adds `d`.
+        let ours =
+            json!({"items": [{"id": "c", "v": 1}, {"id": "a", "v": 2}, {"id": "b", "v": 1}]});
+        let theirs =
+            json!({"items": [{"id": "a", "v": 1}, {"id": "c", "v": 3}, {"id": "d", "v": 1}]});
+        let keys = keyed("items[*]", "id");
+        let result = merge(
+            Some(&base),
+            Some(&ours),
+            Some(&theirs),
+            &keys,
+            &HashMap::new(),
+        );
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            result.merged,
+            Some(json!({"items": [{"id": "c", "v": 3}, {"id": "a", "v": 2}, {"id": "d", "v": 1}]}))
+        );
+
+        // Deleted by ours, modified by theirs.
+        let theirs =
+            json!({"items": [{"id": "a", "v": 1}, {"id": "b", "v": 5}, {"id": "c", "v": 1}]});
+        let ours = json!({"items": [{"id": "a", "v": 1}, {"id": "c", "v": 1}]});
+        let result = merge(
+            Some(&base),
+            Some(&ours),
+            Some(&theirs),
+            &keys,
+            &HashMap::new(),
+        );
+        assert_eq!(pointers(&result), ["/items/2"]);
+        assert_eq!(result.conflicts[0].ours, None);
+
+        // Without a usable key, elements are aligned by content instead.
+        let dup = json!({"items": [{"id": "a"}, {"id": "a"}]});
+        let result = merge(
+            Some(&dup),
+            Some(&json!({"items": [{"id": "a"}]})),
+            Some(&dup),
+            &keys,
+            &HashMap::new(),
+        );
+        assert_eq!(result.merged, Some(json!({"items": [{"id": "a"}]})));
+    }
+
+    #[test]
+    fn key_rules_match_field_names_holding_dots() {
+        let base = json!({"a.b": [{"id": 1, "v": 1}, {"id": 2, "v": 1}], "a": {"b": []}});
+        let ours = json!({"a.b": [{"id": 2, "v": 1}, {"id": 1, "v": 1}], "a": {"b": []}});
+        let theirs = json!({"a.b": [{"id": 1, "v": 2}, {"id": 2, "v": 1}], "a": {"b": []}});
+        let keys = keyed(r#"["a.b"][*]"#, "id");
+        let result = merge(
+            Some(&base),
+            Some(&ours),
+            Some(&theirs),
+            &keys,
+            &HashMap::new(),
+        );
+        assert!(result.conflicts.is_empty());
+        assert_eq!(
+            result.merged,
+            Some(json!({"a.b": [{"id": 2, "v": 1}, {"id": 1, "v": 2}], "a": {"b": []}}))
+        );
+        // Spelled with a dot, the rule is for `a` → `b`, so `a.b` is merged
+        // by position.
+        let keyed_merge = result.merged;
+        let result = merge(
+            Some(&base),
+            Some(&ours),
+            Some(&theirs),
+            &keyed("a.b[*]", "id"),
+            &HashMap::new(),
+        );
+        assert_ne!(result.merged, keyed_merge);
+    }
+}
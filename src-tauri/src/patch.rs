@@ -0,0 +1,612 @@
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+use similar::{Algorithm, DiffTag};
+
+use crate::diff::{content_hash, values_equal};
+use crate::document::{self, Format};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum PatchKind {
+    /// RFC 6902 JSON Patch: a list of operations addressed by JSON Pointer.
+    JsonPatch,
+    /// RFC 7386 Merge Patch: a partial document merged over the original.
+    MergePatch,
+}
+
+/// Builds a patch that turns `left` into `right`.
+pub fn create(left: &Value, right: &Value, kind: PatchKind) -> Value {
+    match kind {
+        PatchKind::JsonPatch => {
+            let mut ops = Vec::new();
+            json_patch(left, right, "", &mut ops);
+            Value::Array(ops)
+        }
+        PatchKind::MergePatch => merge_patch(left, right),
+    }
+}
+
+/// Escapes a key for use as a JSON Pointer reference token (RFC 6901).
+pub fn escape_pointer_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn json_patch(left: &Value, right: &Value, pointer: &str, ops: &mut Vec<Value>) {
+    match (left, right) {
+        (Value::Object(l), Value::Object(r)) => {
+            for (key, value) in l {
+                let path = format!("{pointer}/{}", escape_pointer_token(key));
+                match r.get(key) {
+                    Some(other) => json_patch(value, other, &path, ops),
+                    None => ops.push(json!({ "op": "remove", "path": path })),
+                }
+            }
+            for (key, value) in r.iter().filter(|(key, _)| !l.contains_key(*key)) {
+                let path = format!("{pointer}/{}", escape_pointer_token(key));
+                ops.push(json!({ "op": "add", "path": path, "value": value }));
+            }
+        }
+        (Value::Array(l), Value::Array(r)) => array_patch(l, r, pointer, ops),
+        _ if values_equal(left, right) => {}
+        _ => ops.push(json!({ "op": "replace", "path": pointer, "value": right })),
+    }
+}
+
+/// Emits array operations in order. Ops are applied sequentially, so when
+/// a diff op starts at `new_index` the array being patched already equals
+/// `right[..new_index]` followed by the untouched tail of `left`.
+fn array_patch(left: &[Value], right: &[Value], pointer: &str, ops: &mut Vec<Value>) {
+    let left_hashes: Vec<u64> = left.iter().map(content_hash).collect();
+    let right_hashes: Vec<u64> = right.iter().map(content_hash).collect();
+    let index_path = |idx: usize| format!("{pointer}/{idx}");
+
+    for op in similar::capture_diff_slices(Algorithm::Myers, &left_hashes, &right_hashes) {
+        let (tag, old, new) = op.as_tag_tuple();
+        match tag {
+            DiffTag::Equal => {}
+            DiffTag::Delete => {
+                for _ in old {
+                    ops.push(json!({ "op": "remove", "path": index_path(new.start) }));
+                }
+            }
+            DiffTag::Insert => {
+                for idx in new {
+                    ops.push(json!({ "op": "add", "path": index_path(idx), "value": right[idx] }));
+                }
+            }
+            DiffTag::Replace => {
+                let paired = old.len().min(new.len());
+                for (l, r) in old.clone().zip(new.clone()) {
+                    json_patch(&left[l], &right[r], &index_path(r), ops);
+                }
+                for _ in old.skip(paired) {
+                    let at = new.start + paired;
+                    ops.push(json!({ "op": "remove", "path": index_path(at) }));
+                }
+                for idx in new.skip(paired) {
+                    ops.push(json!({ "op": "add", "path": index_path(idx), "value": right[idx] }));
+                }
+            }
+        }
+    }
+}
+
+/// Merge patches can't express "set this field to null" since null means
+/// delete; such fields come out as removals.
+fn merge_patch(left: &Value, right: &Value) -> Value {
+    let (Value::Object(l), Value::Object(r)) = (left, right) else {
+        return right.clone();
+    };
+    let mut patch = Map::new();
+    for key in l.keys().filter(|key| !r.contains_key(*key)) {
+        patch.insert(key.clone(), Value::Null);
+    }
+    for (key, value) in r {
+        match l.get(key) {
+            Some(old) if values_equal(old, value) => {}
+            Some(old) => {
+                patch.insert(key.clone(), merge_patch(old, value));
+            }
+            None => {
+                patch.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    Value::Object(patch)
+}
+
+impl PatchKind {
+    /// Guesses the kind of a patch file: an array of operation objects is a
+    /// JSON Patch, anything else a Merge Patch.
+    pub fn detect(patch: &Value) -> Self {
+        match patch {
+            Value::Array(ops) if ops.iter().all(|op| op.get("op").is_some()) => {
+                PatchKind::JsonPatch
+            }
+            _ => PatchKind::MergePatch,
+        }
+    }
+}
+
+/// Reads a patch file written as JSON or YAML. Text that starts with `{`,
+/// `[` or a comment is JSON: YAML would read a broken JSON patch as some
+/// other value.
+pub fn parse(text: &str) -> Result<Value, String> {
+    let start = text.trim_start();
+    let format =
+        if start.starts_with(['{', '[']) || start.starts_with("//") || start.starts_with("/*") {
+            Format::Json
+        } else {
+            Format::Yaml
+        };
+    document::parse(text, format)?.ok_or_else(|| "file is empty".to_string())
+}
+
+/// Applies `patch` to `document`. JSON Patch errors name the failing
+/// operation and its path.
+pub fn apply(document: Value, patch: &Value, kind: PatchKind) -> Result<Value, String> {
+    match kind {
+        PatchKind::JsonPatch => {
+            let Value::Array(ops) = patch else {
+                return Err("a JSON Patch must be an array of operations".to_string());
+            };
+            ops.iter().enumerate().try_fold(document, |doc, (idx, op)| {
+                apply_operation(doc, op).map_err(|e| format!("operation {idx}: {e}"))
+            })
+        }
+        PatchKind::MergePatch => {
+            let mut document = document;
+            apply_merge_patch(&mut document, patch);
+            Ok(document)
+        }
+    }
+}
+
+fn apply_merge_patch(target: &mut Value, patch: &Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch.clone();
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(map) = target else {
+        unreachable!()
+    };
+    for (key, value) in patch {
+        if value.is_null() {
+            map.shift_remove(key);
+        } else {
+            apply_merge_patch(map.entry(key.clone()).or_insert(Value::Null), value);
+        }
+    }
+}
+
+fn apply_operation(mut doc: Value, op: &Value) -> Result<Value, String> {
+    let field = |name: &str| {
+        op.get(name)
+            .ok_or_else(|| format!("missing \"{name}\" member"))
+    };
+    let name = field("op")?.as_str().ok_or("\"op\" must be a string")?;
+    let path = field("path")?.as_str().ok_or("\"path\" must be a string")?;
+    let tokens = parse_pointer(path)?;
+    let at = |message: String| format!("{name} at \"{path}\": {message}");
+
+    match name {
+        "add" => add(&mut doc, &tokens, field("value")?.clone()).map_err(at)?,
+        "remove" => {
+            remove(&mut doc, &tokens).map_err(at)?;
+        }
+        "replace" => {
+            let value = field("value")?.clone();
+            *resolve_mut(&mut doc, &tokens).map_err(at)? = value;
+        }
+        "move" | "copy" => {
+            let from = field("from")?.as_str().ok_or("\"from\" must be a string")?;
+            let from_tokens = parse_pointer(from)?;
+            let value = if name == "move" {
+                if tokens.starts_with(&from_tokens) && tokens != from_tokens {
+                    return Err(at(format!("cannot move \"{from}\" into its own child")));
+                }
+                remove(&mut doc, &from_tokens)
+            } else {
+                resolve(&doc, &from_tokens).cloned()
+            }
+            .map_err(|e| at(format!("from \"{from}\": {e}")))?;
+            add(&mut doc, &tokens, value).map_err(at)?;
+        }
+        // Numbers are equal by value (RFC 6902 section 4.6): `1` tests equal to `1.0`.
+        "test" => {
+            let expected = field("value")?;
+            let actual = resolve(&doc, &tokens).map_err(at)?;
+            if !values_equal(actual, expected) {
+                let message = if type_name(actual) == type_name(expected) {
+                    format!("expected {expected}, found {actual}")
+                } else {
+                    format!(
+                        "expected {} {expected}, found {} {actual}",
+                        type_name(expected),
+                        type_name(actual)
+                    )
+                };
+                return Err(at(message));
+            }
+        }
+        other => return Err(format!("unknown op \"{other}\"")),
+    }
+    Ok(doc)
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+/// Splits a JSON Pointer (RFC 6901) into unescaped reference tokens.
+fn parse_pointer(pointer: &str) -> Result<Vec<String>, String> {
+    if pointer.is_empty() {
+        return Ok(Vec::new());
+    }
+    let Some(rest) = pointer.strip_prefix('/') else {
+        return Err(format!(
+            "invalid JSON Pointer \"{pointer}\": must start with '/'"
+        ));
+    };
+    Ok(rest
+        .split('/')
+        .map(|token| token.replace("~1", "/").replace("~0", "~"))
+        .collect())
+}
+
+fn array_index(token: &str, len: usize) -> Result<usize, String> {
+    let valid = !token.is_empty()
+        && token.bytes().all(|b| b.is_ascii_digit())
+        && (token == "0" || !token.starts_with('0'));
+    match token.parse::<usize>() {
+        Ok(idx) if valid && idx < len => Ok(idx),
+        Ok(idx) if valid => Err(format!("index {idx} is out of bounds (length {len})")),
+        _ => Err(format!("\"{token}\" is not a valid array index")),
+    }
+}
+
+fn resolve<'a>(doc: &'a Value, tokens: &[String]) -> Result<&'a Value, String> {
+    tokens.iter().try_fold(doc, |node, token| match node {
+        Value::Object(map) => map
+            .get(token)
+            .ok_or_else(|| format!("path not found: no member \"{token}\"")),
+        Value::Array(items) => Ok(&items[array_index(token, items.len())?]),
+        _ => Err(format!(
+            "path not found: cannot index a scalar with \"{token}\""
+        )),
+    })
+}
+
+fn resolve_mut<'a>(doc: &'a mut Value, tokens: &[String]) -> Result<&'a mut Value, String> {
+    tokens.iter().try_fold(doc, |node, token| match node {
+        Value::Object(map) => map
+            .get_mut(token)
+            .ok_or_else(|| format!("path not found: no member \"{token}\"")),
+        Value::Array(items) => {
+            let idx = array_index(token, items.len())?;
+            Ok(&mut items[idx])
+        }
+        _ => Err(format!(
+            "path not found: cannot index a scalar with \"{token}\""
+        )),
+    })
+}
+
+fn add(doc: &mut Value, tokens: &[String], value: Value) -> Result<(), String> {
+    let Some((last, parent)) = tokens.split_last() else {
+        *doc = value;
+        return Ok(());
+    };
+    match resolve_mut(doc, parent)? {
+        Value::Object(map) => {
+            map.insert(last.clone(), value);
+        }
+        Value::Array(items) if last == "-" => items.push(value),
+        Value::Array(items) => {
+            let idx = array_index(last, items.len() + 1)?;
+            items.insert(idx, value);
+        }
+        _ => return Err("parent is not an object or array".to_string()),
+    }
+    Ok(())
+}
+
+fn remove(doc: &mut Value, tokens: &[String]) -> Result<Value, String> {
+    let Some((last, parent)) = tokens.split_last() else {
+        return Ok(std::mem::take(doc));
+    };
+    match resolve_mut(doc, parent)? {
+        Value::Object(map) => map
+            .shift_remove(last)
+            .ok_or_else(|| format!("path not found: no member \"{last}\"")),
+        Value::Array(items) => {
+            let idx = array_index(last, items.len())?;
+            Ok(items.remove(idx))
+        }
+        _ => Err("path not found: parent is not an object or array".to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn apply_ops(document: Value, ops: Value) -> Result<Value, String> {
+        apply(document, &ops, PatchKind::JsonPatch)
+    }
+
+    /// Pairs a created patch must turn one into the other.
+    fn pairs() -> Vec<(Value, Value)> {
+        vec![
+            (json!({"a": 1, "b": 2}), json!({"a": 1, "b": 3, "c": 4})),
+            (json!({"a": {"b": [1]}}), json!({"a": "flat"})),
+            (json!([1, 2, 3]), json!([0, 1, 2, 3, 4])),
+            (json!([1, 2, 3, 4, 5]), json!([2, 4])),
+            (json!([1, 2, 3]), json!([3, 2, 1])),
+            (json!(["a", "b", "c", "d"]), json!(["c", "d", "a", "b"])),
+            (
+                json!([{"id": 1, "v": "x"}, {"id": 2}]),
+                json!([{"id": 2}, {"id": 1, "v": "y"}]),
+            ),
+            (json!([[1, 2], [3]]), json!([[1], [3, 4], []])),
+            (json!([1, 2]), json!([])),
+            (json!([]), json!([{"a": 1}])),
+            (
+                json!({"a~b": 1, "c/d": {"~1": 2}}),
+                json!({"a~b": 2, "c/d": {"~1": 3, "/": 4}}),
+            ),
+            (json!({"~0/": [1]}), json!({"x": [1]})),
+            (json!({"a": null}), json!({"a": {"b": null}})),
+            (json!(1), json!("scalar root")),
+        ]
+    }
+
+    #[test]
+    fn created_json_patches_apply() {
+        for (left, right) in pairs() {
+            let patch = create(&left, &right, PatchKind::JsonPatch);
+            let patched = apply(left.clone(), &patch, PatchKind::JsonPatch);
+            assert_eq!(patched.as_ref(), Ok(&right), "{left} -> {right}: {patch}");
+            let empty = create(&right, &right, PatchKind::JsonPatch);
+            assert_eq!(empty, json!([]), "{right}");
+        }
+    }
+
+    #[test]
+    fn created_merge_patches_apply() {
+        // Merge patches can't set a value to null, and replace arrays whole.
+        for (left, right) in pairs()
+            .into_iter()
+            .filter(|(_, right)| !right.to_string().contains("null"))
+        {
+            let patch = create(&left, &right, PatchKind::MergePatch);
+            let patched = apply(left.clone(), &patch, PatchKind::MergePatch);
+            assert_eq!(patched.as_ref(), Ok(&right), "{left} -> {right}: {patch}");
+        }
+    }
+
+    #[test]
+    fn pointer_tokens_are_escaped() {
+        let patch = create(
+            &json!({"a/b": 1, "c~d": 1}),
+            &json!({"a/b": 2}),
+            PatchKind::JsonPatch,
+        );
+        assert_eq!(
+            patch,
+            json!([
+                {"op": "replace", "path": "/a~1b", "value": 2},
+                {"op": "remove", "path": "/c~0d"},
+            ])
+        );
+        // `~01` is `~1` unescaped once, not `/`.
+        let patched = apply_ops(json!({"~1": 1}), json!([{"op": "remove", "path": "/~01"}]));
+        assert_eq!(patched.unwrap(), json!({}));
+    }
+
+    #[test]
+    fn equal_numbers_produce_no_ops() {
+        let patch = create(
+            &json!({"a": 1, "b": [1.50]}),
+            &json!({"a": 1.0, "b": [1.5]}),
+            PatchKind::JsonPatch,
+        );
+        assert_eq!(patch, json!([]));
+    }
+
+    #[test]
+    fn applies_every_operation() {
+        let document = json!({"a": {"b": [1, 2, 3]}, "c": "x", "d~/e": true});
+        let patched = apply_ops(
+            document,
+            json!([
+                {"op": "add", "path": "/a/b/1", "value": 9},
+                {"op": "add", "path": "/a/b/-", "value": 4},
+                {"op": "remove", "path": "/a/b/0"},
+                {"op": "replace", "path": "/c", "value": {"y": null}},
+                {"op": "copy", "from": "/c", "path": "/f"},
+                {"op": "move", "from": "/d~0~1e", "path": "/a/g"},
+                {"op": "test", "path": "/a/b", "value": [9, 2, 3, 4]},
+            ]),
+        )
+        .unwrap();
+        assert_eq!(
+            patched,
+            json!({"a": {"b": [9, 2, 3, 4], "g": true}, "c": {"y": null}, "f": {"y": null}})
+        );
+        assert_eq!(
+            apply_ops(
+                json!({"a": 1}),
+                json!([{"op": "replace", "path": "", "value": [1]}])
+            )
+            .unwrap(),
+            json!([1])
+        );
+    }
+
+    #[test]
+    fn test_compares_numbers_by_value() {
+        let document = json!({"n": 1, "list": [1.5, {"x": 100}]});
+        let ops = json!([
+            {"op": "test", "path": "/n", "value": 1.0},
+            {"op": "test", "path": "/list", "value": [1.50, {"x": 1e2}]},
+        ]);
+        assert!(apply_ops(document.clone(), ops).is_ok());
+
+        let error = apply_ops(
+            document.clone(),
+            json!([{"op": "test", "path": "/n", "value": 2}]),
+        );
+        assert_eq!(
+            error.unwrap_err(),
+            "operation 0: test at \"/n\": expected 2, found 1"
+        );
+        let ops = json!([
+            {"op": "add", "path": "/s", "value": "1"},
+            {"op": "test", "path": "/s", "value": 1},
+        ]);
+        assert_eq!(
+            apply_ops(document, ops).unwrap_err(),
+            "operation 1: test at \"/s\": expected number 1, found string \"1\""
+        );
+    }
+
+    #[test]
+    fn missing_paths_are_errors() {
+        let document = json!({"a": {"b": [1]}, "s": 1});
+        for (op, error) in [
+            (
+                json!({"op": "remove", "path": "/a/x"}),
+                "remove at \"/a/x\": path not found: no member \"x\"",
+            ),
+            (
+                json!({"op": "replace", "path": "/a/b/1", "value": 0}),
+                "replace at \"/a/b/1\": index 1 is out of bounds (length 1)",
+            ),
+            (
+                json!({"op": "add", "path": "/a/b/01", "value": 0}),
+                "add at \"/a/b/01\": \"01\" is not a valid array index",
+            ),
+            (
+                json!({"op": "add", "path": "/x/y", "value": 0}),
+                "add at \"/x/y\": path not found: no member \"x\"",
+            ),
+            (
+                json!({"op": "test", "path": "/s/t", "value": 0}),
+                "test at \"/s/t\": path not found: cannot index a scalar with \"t\"",
+            ),
+            (
+                json!({"op": "copy", "from": "/nope", "path": "/c"}),
+                "copy at \"/c\": from \"/nope\": path not found: no member \"nope\"",
+            ),
+            (
+                json!({"op": "add", "path": "a", "value": 0}),
+                "invalid JSON Pointer \"a\": must start with '/'",
+            ),
+            (json!({"op": "jump", "path": "/a"}), "unknown op \"jump\""),
+            (
+                json!({"op": "add", "path": "/a"}),
+                "missing \"value\" member",
+            ),
+        ] {
+            let result = apply_ops(document.clone(), json!([op]));
+            assert_eq!(result.unwrap_err(), format!("operation 0: {error}"));
+        }
+    }
+
+    #[test]
+    fn dash_appends_to_arrays() {
+        let patched = apply_ops(
+            json!({"a": [], "o": {}}),
+            json!([
+                {"op": "add", "path": "/a/-", "value": 1},
+                {"op": "add", "path": "/a/-", "value": 2},
+                {"op": "add", "path": "/o/-", "value": 3},
+            ]),
+        );
+        // On an object, `-` is an ordinary member name.
+        assert_eq!(patched.unwrap(), json!({"a": [1, 2], "o": {"-": 3}}));
+        let error = apply_ops(json!([1]), json!([{"op": "remove", "path": "/-"}]));
+        assert_eq!(
+            error.unwrap_err(),
+            "operation 0: remove at \"/-\": \"-\" is not a valid array index"
+        );
+    }
+
+    #[test]
+    fn move_into_own_child_is_rejected() {
+        let document = json!({"a": {"b": {}}, "ab": 1});
+        let error = apply_ops(
+            document.clone(),
+            json!([{"op": "move", "from": "/a", "path": "/a/b/c"}]),
+        );
+        assert_eq!(
+            error.unwrap_err(),
+            "operation 0: move at \"/a/b/c\": cannot move \"/a\" into its own child"
+        );
+        // A sibling sharing a name prefix is not a child, and a move onto
+        // itself changes nothing.
+        let patched = apply_ops(
+            document.clone(),
+            json!([
+                {"op": "move", "from": "/a", "path": "/ab"},
+                {"op": "move", "from": "/ab", "path": "/ab"},
+            ]),
+        );
+        assert_eq!(patched.unwrap(), json!({"ab": {"b": {}}}));
+    }
+
+    #[test]
+    fn merge_patches() {
+        let patched = apply(
+            json!({"a": 1, "b": {"c": 2, "d": 3}, "e": [1]}),
+            &json!({"a": null, "b": {"c": 4}, "e": {"f": 1}, "g": 5}),
+            PatchKind::MergePatch,
+        );
+        assert_eq!(
+            patched.unwrap(),
+            json!({"b": {"c": 4, "d": 3}, "e": {"f": 1}, "g": 5})
+        );
+        assert_eq!(
+            PatchKind::detect(&json!([{"op": "add"}])),
+            PatchKind::JsonPatch
+        );
+        assert_eq!(PatchKind::detect(&json!([1])), PatchKind::MergePatch);
+    }
+
+    #[test]
+    fn patch_files_may_be_yaml() {
+        let yaml =
+            "# bump\n- op: replace\n  path: /a\n  value: 2\n- {op: add, path: /b, value: [x]}\n";
+        let patch = parse(yaml).unwrap();
+        assert_eq!(PatchKind::detect(&patch), PatchKind::JsonPatch);
+        assert_eq!(
+            apply(json!({"a": 1}), &patch, PatchKind::JsonPatch).unwrap(),
+            json!({"a": 2, "b": ["x"]})
+        );
+        assert_eq!(
+            parse("[{\"op\": \"add\"}]").unwrap(),
+            json!([{"op": "add"}])
+        );
+        assert_eq!(parse(" \n").unwrap_err(), "file is empty");
+        // JSON5 is JSON too.
+        assert_eq!(parse("// c\n{a: 1,}").unwrap(), json!({"a": 1}));
+        // Broken JSON gets the JSON error, broken YAML the YAML one.
+        assert!(parse("[{\"op\": }]")
+            .unwrap_err()
+            .starts_with("expected value"));
+        assert!(parse("- op: add\n bad: [").unwrap_err().contains("line"));
+    }
+}
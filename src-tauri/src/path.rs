@@ -0,0 +1,277 @@
+use serde::Deserialize;
+
+/// One step of a concrete path into a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Segment {
+    Field(String),
+    Index(usize),
+}
+
+/// A path pattern such as `spec.containers[*]`, `items.*.id` or
+/// `$..timestamp`.
+///
+/// `*` matches any single field or index and `[*]` any array index. Inside a
+/// field name `*` and `?` glob (`*At` matches `createdAt`). `..` (JSONPath
+/// recursive descent) and `**` match any number of segments. A leading `$`
+/// is accepted and ignored.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(try_from = "String")]
+pub struct PathPattern {
+    segments: Vec<PatternSegment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum PatternSegment {
+    Field(String),
+    Glob(String),
+    Index(usize),
+    Any,
+    AnyIndex,
+    Descendants,
+}
+
+impl PatternSegment {
+    fn matches(&self, segment: &Segment) -> bool {
+        match (self, segment) {
+            (PatternSegment::Any, _) => true,
+            (PatternSegment::AnyIndex, Segment::Index(_)) => true,
+            (PatternSegment::Field(a), Segment::Field(b)) => a == b,
+            (PatternSegment::Glob(glob), Segment::Field(name)) => glob_matches(glob, name),
+            (PatternSegment::Index(a), Segment::Index(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl PathPattern {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        let segments = tokenize(source)?
+            .into_iter()
+            .map(|token| match token {
+                Token::Field(name) if name.contains(['*', '?']) => PatternSegment::Glob(name),
+                Token::Field(name) => PatternSegment::Field(name),
+                Token::Index(idx) => PatternSegment::Index(idx),
+                Token::Wildcard => PatternSegment::Any,
+                Token::AnyIndex => PatternSegment::AnyIndex,
+                Token::Descendants => PatternSegment::Descendants,
+            })
+            .collect();
+        Ok(Self { segments })
+    }
+
+    pub fn matches(&self, path: &[Segment]) -> bool {
+        fn match_from(pattern: &[PatternSegment], path: &[Segment]) -> bool {
+            match pattern.split_first() {
+                None => path.is_empty(),
+                Some((PatternSegment::Descendants, rest)) => {
+                    (0..=path.len()).any(|skip| match_from(rest, &path[skip..]))
+                }
+                Some((segment, rest)) => path
+                    .split_first()
+                    .is_some_and(|(first, tail)| segment.matches(first) && match_from(rest, tail)),
+            }
+        }
+        match_from(&self.segments, path)
+    }
+}
+
+/// Matches `name` against a glob where `*` is any run of characters and `?`
+/// any single character.
+fn glob_matches(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    // Classic greedy matcher that backtracks to the last `*`.
+    let (mut g, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match glob.get(g) {
+            Some('*') => {
+                star = Some((g, n));
+                g += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                g += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_g, star_n)) => {
+                    g = star_g + 1;
+                    n = star_n + 1;
+                    star = Some((star_g, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+impl TryFrom<String> for PathPattern {
+    type Error = String;
+
+    fn try_from(source: String) -> Result<Self, Self::Error> {
+        Self::parse(&source)
+    }
+}
+
+enum Token {
+    Field(String),
+    Index(usize),
+    Wildcard,
+    AnyIndex,
+    Descendants,
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let source = source.trim();
+    let source = source.strip_prefix('$').unwrap_or(source);
+    let mut chars = source.chars().peekable();
+    let mut tokens = Vec::new();
+    let mut field = String::new();
+
+    let flush = |field: &mut String, tokens: &mut Vec<Token>| {
+        if field == "*" {
+            tokens.push(Token::Wildcard);
+        } else if field == "**" {
+            tokens.push(Token::Descendants);
+        } else if !field.is_empty() {
+            tokens.push(Token::Field(field.clone()));
+        }
+        field.clear();
+    };
+
+    while let Some(c) = chars.next() {
+        match c {
+            '.' => {
+                flush(&mut field, &mut tokens);
+                if chars.next_if_eq(&'.').is_some() {
+                    tokens.push(Token::Descendants);
+                }
+            }
+            '[' => {
+                flush(&mut field, &mut tokens);
+                let mut inner = String::new();
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some(c) => inner.push(c),
+                        None => return Err(format!("unclosed '[' in path '{source}'")),
+                    }
+                }
+                let inner = inner.trim();
+                let token = if inner == "*" {
+                    Token::AnyIndex
+                } else if let Ok(idx) = inner.parse() {
+                    Token::Index(idx)
+                } else if let Some(name) = unquote(inner) {
+                    Token::Field(name.to_string())
+                } else {
+                    return Err(format!("invalid index '[{inner}]' in path '{source}'"));
+                };
+                tokens.push(token);
+            }
+            c => field.push(c),
+        }
+    }
+    flush(&mut field, &mut tokens);
+    Ok(tokens)
+}
+
+fn unquote(s: &str) -> Option<&str> {
+    s.strip_prefix('"')
+        .and_then(|s| s.strip_suffix('"'))
+        .or_else(|| s.strip_prefix('\'').and_then(|s| s.strip_suffix('\'')))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Segments of a path written in pattern syntax, without wildcards.
+    fn segments(path: &str) -> Vec<Segment> {
+        tokenize(path)
+            .unwrap()
+            .into_iter()
+            .map(|token| match token {
+                Token::Field(name) => Segment::Field(name),
+                Token::Index(idx) => Segment::Index(idx),
+                _ => panic!("wildcard in '{path}'"),
+            })
+            .collect()
+    }
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        PathPattern::parse(pattern)
+            .unwrap()
+            .matches(&segments(path))
+    }
+
+    #[test]
+    fn quoted_fields_are_one_segment() {
+        assert_eq!(
+            segments(r#"spec.containers[0]["a.b"].name"#),
+            [
+                Segment::Field("spec".to_string()),
+                Segment::Field("containers".to_string()),
+                Segment::Index(0),
+                Segment::Field("a.b".to_string()),
+                Segment::Field("name".to_string()),
+            ]
+        );
+        assert!(segments("").is_empty());
+    }
+
+    #[test]
+    fn descendants_match_any_number_of_segments() {
+        for pattern in ["**.id", "$..id", "..id"] {
+            assert!(matches(pattern, "id"), "{pattern}");
+            assert!(matches(pattern, "a.id"), "{pattern}");
+            assert!(matches(pattern, "a[2].b.id"), "{pattern}");
+            assert!(!matches(pattern, "id.a"), "{pattern}");
+            assert!(!matches(pattern, "a.ids"), "{pattern}");
+        }
+        assert!(matches("a..b", "a.b"));
+        assert!(matches("a..b", "a.x[0].y.b"));
+        assert!(!matches("a..b", "x.a.b"));
+        assert!(matches("a.**", "a"));
+        assert!(matches("a.**", "a.b[1]"));
+        assert!(matches("**", ""));
+    }
+
+    #[test]
+    fn any_index_matches_indices_only() {
+        assert!(matches("items[*]", "items[0]"));
+        assert!(matches("items[*].id", "items[12].id"));
+        assert!(!matches("items[*]", "items.first"));
+        assert!(!matches("items[*]", "items"));
+        assert!(!matches("items[*]", "items[0].id"));
+        // `*` is any single segment, field or index.
+        assert!(matches("items.*", "items[0]"));
+        assert!(matches("items.*", "items.first"));
+        assert!(!matches("items.*", "items.first.id"));
+        assert!(matches("items[1]", "items[1]"));
+        assert!(!matches("items[1]", "items[2]"));
+    }
+
+    #[test]
+    fn globs_match_within_field_names() {
+        assert!(matches("*At", "createdAt"));
+        assert!(matches("meta.*At", "meta.At"));
+        assert!(!matches("*At", "created"));
+        assert!(matches("v?", "v1"));
+        assert!(!matches("v?", "v10"));
+        assert!(!matches("*At", "[0]"));
+        assert!(matches(r#"["a.b"]"#, r#"["a.b"]"#));
+        assert!(!matches(r#"["a.b"]"#, "a.b"));
+    }
+
+    #[test]
+    fn rejects_malformed_patterns() {
+        assert!(PathPattern::parse("items[0")
+            .unwrap_err()
+            .contains("unclosed"));
+        assert!(PathPattern::parse("items[x]")
+            .unwrap_err()
+            .contains("invalid index"));
+    }
+}
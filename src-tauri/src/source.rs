@@ -0,0 +1,531 @@
+use std::collections::{HashMap, HashSet};
+
+use serde::Serialize;
+use serde_json::{Number, Value};
+
+use crate::document::{Warning, WarningKind};
+
+/// 1-based, inclusive range of source lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LineRange {
+    pub start: usize,
+    pub end: usize,
+}
+
+/// Scans JSON source text for things `serde_json` accepts but silently
+/// resolves: repeated keys (the last one wins) and numbers that don't survive
+/// parsing into an `f64`/`i64`/`u64` unchanged. `text` must already have
+/// parsed successfully.
+pub fn json_warnings(text: &str) -> Vec<Warning> {
+    Scanner::run(text).warnings
+}
+
+/// Maps each display path of an already parsed JSON text to the lines its
+/// value spans. Object members start at their key.
+pub fn json_line_map(text: &str) -> HashMap<String, LineRange> {
+    let lines = LineIndex::new(text);
+    Scanner::run(text)
+        .spans
+        .into_iter()
+        .map(|(path, start, end)| {
+            let range = LineRange {
+                start: lines.line_of(start),
+                end: lines.line_of(end.saturating_sub(1).max(start)),
+            };
+            (path, range)
+        })
+        .collect()
+}
+
+/// Maps each display path of a block-style YAML text to the lines its value
+/// spans. This follows indentation rather than running a full YAML parser,
+/// so entries inside flow collections (`{a: 1}`, `[1, 2]`) share the line
+/// of their parent.
+pub fn yaml_line_map(text: &str) -> HashMap<String, LineRange> {
+    let mut map = HashMap::new();
+    let mut open: Vec<YamlEntry> = Vec::new();
+    let mut last_content_line = 0;
+    let mut root_seq_count = 0;
+    // Indentation a block scalar (`|`, `>`) runs deeper than.
+    let mut block_scalar: Option<usize> = None;
+
+    let close = |entry: YamlEntry, end: usize, map: &mut HashMap<String, LineRange>| {
+        map.insert(
+            entry.path,
+            LineRange {
+                start: entry.line,
+                end: end.max(entry.line),
+            },
+        );
+    };
+
+    for (idx, raw_line) in text.lines().enumerate() {
+        let line = idx + 1;
+        let trimmed = raw_line.trim_start();
+        let indent = raw_line.len() - trimmed.len();
+        if let Some(scalar_indent) = block_scalar {
+            if trimmed.is_empty() || indent > scalar_indent {
+                if !trimmed.is_empty() {
+                    last_content_line = line;
+                }
+                continue;
+            }
+            block_scalar = None;
+        }
+        if trimmed.is_empty() || trimmed.starts_with('#') {
+            continue;
+        }
+        if trimmed.starts_with("---") || trimmed.starts_with("...") {
+            continue;
+        }
+
+        let mut column = indent;
+        let mut rest = trimmed;
+        loop {
+            let (kind, after) = if let Some(after) = rest
+                .strip_prefix('-')
+                .filter(|a| a.is_empty() || a.starts_with(' '))
+            {
+                (YamlKind::Item, after)
+            } else if let Some((key, after)) = yaml_key(rest) {
+                (YamlKind::Key(key), after)
+            } else {
+                break;
+            };
+
+            while let Some(top) = open.last() {
+                let is_parent = top.column < column
+                    || (top.column == column
+                        && matches!(kind, YamlKind::Item)
+                        && matches!(top.kind, YamlKind::Key(_))
+                        && top.holds_items);
+                if is_parent {
+                    break;
+                }
+                let entry = open.pop().unwrap();
+                close(entry, last_content_line, &mut map);
+            }
+            let (parent_path, seq_count) = match open.last_mut() {
+                Some(parent) => (parent.path.as_str(), &mut parent.seq_count),
+                None => ("", &mut root_seq_count),
+            };
+            let path = match &kind {
+                YamlKind::Item => {
+                    let path = format!("{parent_path}[{seq_count}]");
+                    *seq_count += 1;
+                    path
+                }
+                YamlKind::Key(key) if parent_path.is_empty() => key.clone(),
+                YamlKind::Key(key) => format!("{parent_path}.{key}"),
+            };
+
+            let value = after.trim();
+            let holds_items = value.is_empty() || value.starts_with(['&', '!']);
+            if value.starts_with(['|', '>']) {
+                block_scalar = Some(column);
+            }
+            open.push(YamlEntry {
+                path,
+                kind,
+                column,
+                line,
+                seq_count: 0,
+                holds_items,
+            });
+
+            // `- key: value` and `- - item` put a nested entry on the same line.
+            let consumed = rest.len() - after.len();
+            let nested = after.trim_start();
+            column += consumed + (after.len() - nested.len());
+            rest = nested;
+            if !matches!(open.last().map(|e| &e.kind), Some(YamlKind::Item)) {
+                break;
+            }
+        }
+        last_content_line = line;
+    }
+
+    while let Some(entry) = open.pop() {
+        close(entry, last_content_line, &mut map);
+    }
+    if last_content_line > 0 {
+        let first_line = text
+            .lines()
+            .position(|l| {
+                let l = l.trim_start();
+                !l.is_empty() && !l.starts_with('#') && !l.starts_with("---")
+            })
+            .map_or(1, |idx| idx + 1);
+        map.insert(
+            String::new(),
+            LineRange {
+                start: first_line,
+                end: last_content_line,
+            },
+        );
+    }
+    map
+}
+
+struct YamlEntry {
+    path: String,
+    kind: YamlKind,
+    column: usize,
+    line: usize,
+    seq_count: usize,
+    /// Nothing but an anchor or tag follows the key, so a sequence at the
+    /// same indentation belongs to it.
+    holds_items: bool,
+}
+
+enum YamlKind {
+    Item,
+    Key(String),
+}
+
+/// Splits `key: value` into the (unquoted) key and what follows the colon.
+fn yaml_key(line: &str) -> Option<(String, &str)> {
+    if let Some(quote) = line.chars().next().filter(|c| matches!(c, '"' | '\'')) {
+        let close = line[1..].find(quote)? + 1;
+        let after = line[close + 1..].trim_start().strip_prefix(':')?;
+        return (after.is_empty() || after.starts_with(' '))
+            .then(|| (line[1..close].to_string(), after));
+    }
+    if line.starts_with(['{', '[', '|', '>', '?', '&', '*', '!', '%', '@', '`']) {
+        return None;
+    }
+    let colon = line
+        .match_indices(':')
+        .map(|(idx, _)| idx)
+        .find(|&idx| line[idx + 1..].is_empty() || line[idx + 1..].starts_with([' ', '\t']))?;
+    let key = line[..colon].trim_end();
+    (!key.is_empty() && !key.contains(" #")).then(|| (key.to_string(), &line[colon + 1..]))
+}
+
+/// Byte offset where 1-based `line` starts, or the end of `text` when it has
+/// fewer lines.
+pub fn line_start(text: &str, line: usize) -> usize {
+    if line <= 1 {
+        return 0;
+    }
+    text.match_indices('\n')
+        .nth(line - 2)
+        .map_or(text.len(), |(idx, _)| idx + 1)
+}
+
+pub struct LineIndex {
+    /// Byte offsets at which each line starts.
+    starts: Vec<usize>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(idx, _)| idx + 1))
+            .collect();
+        Self { starts }
+    }
+
+    /// The 1-based line holding byte `offset`.
+    pub fn line_of(&self, offset: usize) -> usize {
+        self.starts.partition_point(|&start| start <= offset)
+    }
+}
+
+struct Scanner<'t> {
+    text: &'t str,
+    pos: usize,
+    warnings: Vec<Warning>,
+    /// Display path and byte range of every value.
+    spans: Vec<(String, usize, usize)>,
+}
+
+impl<'t> Scanner<'t> {
+    fn run(text: &'t str) -> Self {
+        let mut scanner = Scanner {
+            text,
+            pos: 0,
+            warnings: Vec::new(),
+            spans: Vec::new(),
+        };
+        scanner.skip_whitespace();
+        scanner.entry("", scanner.pos);
+        scanner
+    }
+
+    /// Scans the value at the current position and records its span from
+    /// `start`.
+    fn entry(&mut self, path: &str, start: usize) {
+        self.value(path);
+        self.spans.push((path.to_string(), start, self.pos));
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while matches!(self.peek(), Some(b' ' | b'\t' | b'\n' | b'\r')) {
+            self.pos += 1;
+        }
+    }
+
+    fn value(&mut self, path: &str) {
+        self.skip_whitespace();
+        match self.peek() {
+            Some(b'{') => self.object(path),
+            Some(b'[') => self.array(path),
+            Some(b'"') => {
+                self.string();
+            }
+            Some(_) => self.scalar(path),
+            None => {}
+        }
+    }
+
+    fn object(&mut self, path: &str) {
+        self.pos += 1;
+        let mut seen = HashSet::new();
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return,
+                Some(b'}') => {
+                    self.pos += 1;
+                    return;
+                }
+                Some(b',') => {
+                    self.pos += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            let key_start = self.pos;
+            let key = self.string();
+            self.skip_whitespace();
+            self.pos += 1; // ':'
+            let child = if path.is_empty() {
+                key.clone()
+            } else {
+                format!("{path}.{key}")
+            };
+            if !seen.insert(key.clone()) {
+                self.warnings.push(Warning {
+                    kind: WarningKind::DuplicateKey,
+                    path: child.clone(),
+                    message: format!("duplicate key \"{key}\"; only the last value is kept"),
+                });
+            }
+            self.skip_whitespace();
+            self.entry(&child, key_start);
+        }
+    }
+
+    fn array(&mut self, path: &str) {
+        self.pos += 1;
+        let mut idx = 0;
+        loop {
+            self.skip_whitespace();
+            match self.peek() {
+                None => return,
+                Some(b']') => {
+                    self.pos += 1;
+                    return;
+                }
+                Some(b',') => {
+                    self.pos += 1;
+                    idx += 1;
+                }
+                _ => self.entry(&format!("{path}[{idx}]"), self.pos),
+            }
+        }
+    }
+
+    /// Consumes a string literal and returns its decoded contents.
+    fn string(&mut self) -> String {
+        let start = self.pos;
+        self.pos += 1;
+        let mut escaped = false;
+        while let Some(b) = self.peek() {
+            self.pos += 1;
+            match b {
+                b'"' => break,
+                b'\\' => {
+                    escaped = true;
+                    self.pos += 1;
+                }
+                _ => {}
+            }
+        }
+        let literal = &self.text[start..self.pos];
+        if escaped {
+            serde_json::from_str(literal).unwrap_or_else(|_| literal.to_string())
+        } else {
+            literal.trim_matches('"').to_string()
+        }
+    }
+
+    fn scalar(&mut self, path: &str) {
+        let start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|b| !matches!(b, b',' | b'}' | b']' | b' ' | b'\t' | b'\n' | b'\r'))
+        {
+            self.pos += 1;
+        }
+        let literal = &self.text[start..self.pos];
+        if !literal.starts_with(|c: char| c == '-' || c.is_ascii_digit()) {
+            return;
+        }
+        if let Some(warning) = precision_warning(literal, path) {
+            self.warnings.push(warning);
+        }
+    }
+}
+
+/// Warns about a number literal JavaScript can't hold exactly. The diff keeps
+/// and compares the literal as written, but the webview's `JSON.parse` and
+/// any other JavaScript reading the file see the rounded value.
+pub fn precision_warning(literal: &str, path: &str) -> Option<Warning> {
+    let shown = js_rounded(literal)?;
+    Some(Warning {
+        kind: WarningKind::PrecisionLoss,
+        path: path.to_string(),
+        message: format!(
+            "{literal} is more precise than a JavaScript number; it's compared as written, \
+             but JavaScript reads it as {shown}"
+        ),
+    })
+}
+
+/// How JavaScript would print the decimal number `literal` after parsing it,
+/// when that's a different value.
+pub fn js_rounded(literal: &str) -> Option<String> {
+    let parsed: f64 = literal.parse().ok()?;
+    let shown = ryu_js::Buffer::new().format(parsed).to_string();
+    (!parsed.is_finite() || number_key(literal) != number_key(&shown)).then_some(shown)
+}
+
+/// Identifies the value of a decimal number literal however it's written:
+/// `1`, `1.0`, `10e-1` and `-0` all give the same key as their equals.
+pub fn number_key(literal: &str) -> (bool, String, i64) {
+    let (digits, exponent) = significant_digits(literal);
+    (
+        literal.starts_with('-') && !digits.is_empty(),
+        digits,
+        exponent,
+    )
+}
+
+/// `Infinity`, `-Infinity` or `NaN` from a JSON5 text. JSON has no number
+/// for them, so they're kept as numbers holding that name: each only equals
+/// itself, never the string `"Infinity"` or `"NaN"`.
+pub fn non_finite(name: &str) -> Value {
+    Value::Number(Number::from_string_unchecked(name.to_string()))
+}
+
+/// `value` as the webview can take it: its `JSON.parse` rejects a bare
+/// `Infinity` or `NaN`, so those are sent as their names in strings.
+pub fn webview_value(value: &Value) -> Value {
+    match value {
+        Value::Number(n) if matches!(n.as_str(), "Infinity" | "-Infinity" | "NaN") => {
+            Value::String(n.to_string())
+        }
+        Value::Array(items) => Value::Array(items.iter().map(webview_value).collect()),
+        Value::Object(map) => Value::Object(
+            map.iter()
+                .map(|(key, child)| (key.clone(), webview_value(child)))
+                .collect(),
+        ),
+        _ => value.clone(),
+    }
+}
+
+/// Reduces a number literal to its significant digits and decimal exponent
+/// (`value = 0.DIGITS × 10^exponent`), so `1.50`, `15e-1` and `1.5` compare
+/// equal.
+pub fn significant_digits(literal: &str) -> (String, i64) {
+    let unsigned = literal.trim_start_matches('-');
+    let (mantissa, exponent) = match unsigned.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse().unwrap_or(0)),
+        None => (unsigned, 0),
+    };
+    let (int_part, frac_part) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let all = format!("{int_part}{frac_part}");
+    let digits = all.trim_start_matches('0');
+    if digits.is_empty() {
+        return (String::new(), 0);
+    }
+    let leading_zeros = (all.len() - digits.len()) as i64;
+    let exponent = int_part.len() as i64 - leading_zeros + exponent;
+    (digits.trim_end_matches('0').to_string(), exponent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(map: &HashMap<String, LineRange>, path: &str) -> Option<(usize, usize)> {
+        map.get(path).map(|range| (range.start, range.end))
+    }
+
+    #[test]
+    fn yaml_items_holding_mappings() {
+        let map = yaml_line_map("items:\n  - name: a\n    port: 1\n\n  - name: b\nlast: true\n");
+        assert_eq!(lines(&map, "items"), Some((1, 5)));
+        assert_eq!(lines(&map, "items[0]"), Some((2, 3)));
+        assert_eq!(lines(&map, "items[0].name"), Some((2, 2)));
+        assert_eq!(lines(&map, "items[0].port"), Some((3, 3)));
+        assert_eq!(lines(&map, "items[1]"), Some((5, 5)));
+        assert_eq!(lines(&map, "items[1].name"), Some((5, 5)));
+        assert_eq!(lines(&map, "last"), Some((6, 6)));
+        assert_eq!(lines(&map, ""), Some((1, 6)));
+
+        // Items at the key's own indentation, and items of items.
+        let map = yaml_line_map("list:\n- a\n- - b\n  - c\nnext: 1\n");
+        assert_eq!(lines(&map, "list"), Some((1, 4)));
+        assert_eq!(lines(&map, "list[0]"), Some((2, 2)));
+        assert_eq!(lines(&map, "list[1]"), Some((3, 4)));
+        assert_eq!(lines(&map, "list[1][0]"), Some((3, 3)));
+        assert_eq!(lines(&map, "list[1][1]"), Some((4, 4)));
+        assert_eq!(lines(&map, "next"), Some((5, 5)));
+    }
+
+    #[test]
+    fn yaml_block_scalars_span_their_text() {
+        let map = yaml_line_map(
+            "script: |\n  echo a\n\n  key: not a key\nfolded: >-\n  one\n  two\nother: 1\n",
+        );
+        assert_eq!(lines(&map, "script"), Some((1, 4)));
+        assert_eq!(lines(&map, "folded"), Some((5, 7)));
+        assert_eq!(lines(&map, "other"), Some((8, 8)));
+        assert_eq!(lines(&map, "script.key"), None);
+        assert_eq!(lines(&map, "key"), None);
+    }
+
+    #[test]
+    fn yaml_anchors_and_aliases() {
+        let map = yaml_line_map(
+            "base: &b\n  x: 1\nderived:\n  <<: *b\n  y: 2\nseq: &s\n- 1\n- 2\nref: *s\n",
+        );
+        assert_eq!(lines(&map, "base"), Some((1, 2)));
+        assert_eq!(lines(&map, "base.x"), Some((2, 2)));
+        assert_eq!(lines(&map, "derived"), Some((3, 5)));
+        assert_eq!(lines(&map, "derived.<<"), Some((4, 4)));
+        assert_eq!(lines(&map, "seq"), Some((6, 8)));
+        assert_eq!(lines(&map, "seq[1]"), Some((8, 8)));
+        assert_eq!(lines(&map, "ref"), Some((9, 9)));
+    }
+
+    #[test]
+    fn yaml_flow_collections_stay_on_their_line() {
+        let map = yaml_line_map("a: {x: 1, y: [1, 2]}\nb: [1, 2]\nc:\n  - [3, 4]\n  - {z: 5}\n");
+        assert_eq!(lines(&map, "a"), Some((1, 1)));
+        assert_eq!(lines(&map, "b"), Some((2, 2)));
+        assert_eq!(lines(&map, "c[0]"), Some((4, 4)));
+        assert_eq!(lines(&map, "c[1]"), Some((5, 5)));
+        for inner in ["a.x", "a.y", "b[0]", "c[0][0]", "c[1].z"] {
+            assert_eq!(lines(&map, inner), None, "{inner}");
+        }
+    }
+}
@@ -0,0 +1,1622 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering as AtomicOrdering};
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Number, Value};
+
+use crate::diff::{values_equal, DiffType};
+use crate::source;
+
+/// Leaves buffered in memory before a sorted run is spilled to disk.
+const RUN_BYTES: usize = 64 << 20;
+/// How often reading reports progress.
+const PROGRESS_BYTES: u64 = 4 << 20;
+
+/// How the two files are read.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct StreamOptions {
+    /// Read each line as a record (JSON Lines, NDJSON) rather than the whole
+    /// file as one document.
+    pub json_lines: bool,
+    /// Pairs records by this field instead of by position. A dotted path
+    /// reaches into nested objects when no field has the name as written.
+    pub key_field: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Phase {
+    ReadingLeft,
+    ReadingRight,
+    Comparing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Progress {
+    pub phase: Phase,
+    /// Bytes read, or rows written while comparing.
+    pub done: u64,
+    /// File size; 0 while comparing.
+    pub total: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Summary {
+    pub rows: u64,
+    /// Rows that aren't `Unchanged`.
+    pub changes: u64,
+    pub added: u64,
+    pub removed: u64,
+    pub changed: u64,
+    /// Set when reading JSON Lines. Record headers are counted in `rows`
+    /// and `changes` but not as added, removed or changed values.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub records: Option<RecordSummary>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecordSummary {
+    pub total: u64,
+    pub added: u64,
+    pub removed: u64,
+    pub changed: u64,
+}
+
+/// One leaf of the diff: a primitive or an empty container present on
+/// either side.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StreamRow {
+    pub path: String,
+    pub depth: usize,
+    pub diff_type: DiffType,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "present"
+    )]
+    pub left: Option<Value>,
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        deserialize_with = "present"
+    )]
+    pub right: Option<Value>,
+}
+
+/// Reads a side that is there, keeping `null` apart from a missing side.
+fn present<'de, D: serde::Deserializer<'de>>(deserializer: D) -> Result<Option<Value>, D::Error> {
+    Value::deserialize(deserializer).map(Some)
+}
+
+/// A diff of two JSON files that never holds either document in memory.
+///
+/// Both files are read with a pull tokenizer and flattened into leaves, which
+/// are spilled to disk as sorted runs and merge-joined by path. The rows land
+/// in a file next to an offset index, so any window of them can be read back
+/// cheaply. Rows come out ordered by path (fields alphabetically, indexes
+/// numerically) rather than in document order, and arrays are compared by
+/// index.
+///
+/// JSON Lines files are read a line at a time and diffed as if each were an
+/// array of its records, so records are paired by position, `[0]`, `[1]`…,
+/// or by key as `[id=42]`. Each record's rows follow a header row holding
+/// its overall status.
+pub struct StreamDiff {
+    rows: BufReader<File>,
+    index: File,
+    changes: File,
+    pub summary: Summary,
+    /// Last, so the files above are closed before it's removed.
+    _dir: Cleanup,
+}
+
+impl StreamDiff {
+    pub fn build(
+        left: &Path,
+        right: &Path,
+        options: &StreamOptions,
+        on_progress: &mut dyn FnMut(Progress),
+    ) -> Result<Self, String> {
+        Self::build_with(left, right, options, RUN_BYTES, on_progress)
+    }
+
+    /// [`build`](Self::build) with runs spilled every `run_bytes`.
+    fn build_with(
+        left: &Path,
+        right: &Path,
+        options: &StreamOptions,
+        run_bytes: usize,
+        on_progress: &mut dyn FnMut(Progress),
+    ) -> Result<Self, String> {
+        let dir = scratch_dir().map_err(|e| format!("cannot create a work directory: {e}"))?;
+        // From here on the directory is removed along with `cleanup`.
+        let cleanup = Cleanup(dir.clone());
+
+        let mut spill = |file, prefix, phase| {
+            spill_runs(file, &dir, prefix, phase, options, run_bytes, on_progress)
+                .map_err(|e| format!("{prefix}: {e}"))
+        };
+        let left_runs = spill(left, "left", Phase::ReadingLeft)?;
+        let right_runs = spill(right, "right", Phase::ReadingRight)?;
+
+        let io_error = |e: io::Error| e.to_string();
+        let mut out = RowWriter::create(&dir).map_err(io_error)?;
+        let mut record = options.json_lines.then(|| Record {
+            sort_key: String::new(),
+            key_field: options.key_field.clone(),
+            rows: Vec::new(),
+        });
+        if record.is_some() {
+            out.summary.records = Some(RecordSummary::default());
+        }
+        let mut compared = 0u64;
+
+        let key_field = options.key_field.as_deref().filter(|_| options.json_lines);
+        let mut left = MergedRuns::open(&left_runs, key_field).map_err(io_error)?;
+        let mut right = MergedRuns::open(&right_runs, key_field).map_err(io_error)?;
+        loop {
+            let order = match (left.peek(), right.peek()) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some(l), Some(r)) => l.sort_key.cmp(&r.sort_key),
+            };
+            let (l, r) = match order {
+                Ordering::Less => (left.next().map_err(io_error)?, None),
+                Ordering::Greater => (None, right.next().map_err(io_error)?),
+                Ordering::Equal => (
+                    left.next().map_err(io_error)?,
+                    right.next().map_err(io_error)?,
+                ),
+            };
+            let leaf = l.as_ref().or(r.as_ref()).expect("one side has a leaf");
+            let diff_type = match (&l, &r) {
+                (Some(l), Some(r)) if values_equal(&l.value, &r.value) => DiffType::Unchanged,
+                (Some(_), Some(_)) => DiffType::Changed,
+                (Some(_), None) => DiffType::Removed,
+                _ => DiffType::Added,
+            };
+            match diff_type {
+                DiffType::Added => out.summary.added += 1,
+                DiffType::Removed => out.summary.removed += 1,
+                DiffType::Changed => out.summary.changed += 1,
+                _ => {}
+            }
+            let row = StreamRow {
+                path: leaf.path.clone(),
+                depth: leaf.depth,
+                diff_type,
+                left: l.as_ref().map(|leaf| leaf.value.clone()),
+                right: r.as_ref().map(|leaf| leaf.value.clone()),
+            };
+            match &mut record {
+                Some(record) => {
+                    let sort_key = record_key(&leaf.sort_key);
+                    if sort_key != record.sort_key {
+                        record.flush(&mut out).map_err(io_error)?;
+                        record.sort_key = sort_key.to_string();
+                    }
+                    record.rows.push(row);
+                }
+                None => out.write(&row).map_err(io_error)?,
+            }
+
+            compared += 1;
+            if compared.is_multiple_of(100_000) {
+                on_progress(Progress {
+                    phase: Phase::Comparing,
+                    done: compared,
+                    total: 0,
+                });
+            }
+        }
+        if let Some(record) = &mut record {
+            record.flush(&mut out).map_err(io_error)?;
+        }
+        let summary = out.finish().map_err(io_error)?;
+        drop((left, right));
+        for run in left_runs.iter().chain(&right_runs) {
+            let _ = fs::remove_file(run);
+        }
+
+        let open = |name: &str| File::open(dir.join(name)).map_err(io_error);
+        let diff = StreamDiff {
+            rows: BufReader::new(open("rows.jsonl")?),
+            index: open("rows.idx")?,
+            changes: open("changes.idx")?,
+            summary,
+            _dir: cleanup,
+        };
+        Ok(diff)
+    }
+
+    /// Reads up to `count` rows starting at `start`, counting only changed
+    /// rows when `changes_only` is set.
+    pub fn rows(
+        &mut self,
+        start: u64,
+        count: usize,
+        changes_only: bool,
+    ) -> Result<Vec<StreamRow>, String> {
+        let total = if changes_only {
+            self.summary.changes
+        } else {
+            self.summary.rows
+        };
+        let end = total.min(start.saturating_add(count as u64));
+        (start..end)
+            .map(|position| {
+                let row = if changes_only {
+                    read_u64(&mut self.changes, position)?
+                } else {
+                    position
+                };
+                let offset = read_u64(&mut self.index, row)?;
+                self.rows.seek(SeekFrom::Start(offset))?;
+                let mut line = String::new();
+                self.rows.read_line(&mut line)?;
+                serde_json::from_str(&line).map_err(io::Error::from)
+            })
+            .collect::<io::Result<_>>()
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Removes a work directory when dropped.
+struct Cleanup(PathBuf);
+
+impl Drop for Cleanup {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Writes rows along with the offset index of all rows and the index of
+/// changed ones.
+struct RowWriter {
+    rows: BufWriter<File>,
+    index: BufWriter<File>,
+    changes: BufWriter<File>,
+    offset: u64,
+    summary: Summary,
+}
+
+impl RowWriter {
+    fn create(dir: &Path) -> io::Result<Self> {
+        let create = |name: &str| File::create(dir.join(name)).map(BufWriter::new);
+        Ok(RowWriter {
+            rows: create("rows.jsonl")?,
+            index: create("rows.idx")?,
+            changes: create("changes.idx")?,
+            offset: 0,
+            summary: Summary::default(),
+        })
+    }
+
+    fn write(&mut self, row: &StreamRow) -> io::Result<()> {
+        let mut line = serde_json::to_vec(row)?;
+        line.push(b'\n');
+        self.rows.write_all(&line)?;
+        self.index.write_all(&self.offset.to_le_bytes())?;
+        self.offset += line.len() as u64;
+        if row.diff_type != DiffType::Unchanged {
+            self.changes.write_all(&self.summary.rows.to_le_bytes())?;
+            self.summary.changes += 1;
+        }
+        self.summary.rows += 1;
+        Ok(())
+    }
+
+    fn finish(mut self) -> io::Result<Summary> {
+        self.rows.flush()?;
+        self.index.flush()?;
+        self.changes.flush()?;
+        Ok(self.summary)
+    }
+}
+
+/// The rows of the JSON Lines record being compared, held back until its
+/// header can be written.
+struct Record {
+    sort_key: String,
+    key_field: Option<String>,
+    rows: Vec<StreamRow>,
+}
+
+impl Record {
+    fn flush(&mut self, out: &mut RowWriter) -> io::Result<()> {
+        let Some(first) = self.rows.first() else {
+            return Ok(());
+        };
+        let status = if self.rows.iter().all(|row| row.diff_type == first.diff_type) {
+            first.diff_type
+        } else {
+            DiffType::Changed
+        };
+        if let Some(records) = &mut out.summary.records {
+            records.total += 1;
+            match status {
+                DiffType::Added => records.added += 1,
+                DiffType::Removed => records.removed += 1,
+                DiffType::Changed => records.changed += 1,
+                _ => {}
+            }
+        }
+        let header = StreamRow {
+            path: record_path(&self.sort_key, self.key_field.as_deref()),
+            depth: 0,
+            diff_type: status,
+            left: None,
+            right: None,
+        };
+        out.write(&header)?;
+        for row in self.rows.drain(..) {
+            out.write(&row)?;
+        }
+        Ok(())
+    }
+}
+
+fn scratch_dir() -> io::Result<PathBuf> {
+    static NEXT: AtomicU64 = AtomicU64::new(0);
+    let dir = std::env::temp_dir().join(format!(
+        "picosat-diff-{}-{}",
+        std::process::id(),
+        NEXT.fetch_add(1, AtomicOrdering::Relaxed)
+    ));
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+fn read_u64(file: &mut File, position: u64) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    file.seek(SeekFrom::Start(position * 8))?;
+    file.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+#[derive(Debug)]
+struct Leaf {
+    /// Path encoded so that byte order sorts children right after their
+    /// parent and array indexes numerically.
+    sort_key: String,
+    path: String,
+    depth: usize,
+    value: Value,
+    /// Order in the file, to tell which of two duplicate keys came last.
+    seq: u64,
+    /// Marks an object member holding a non-empty container rather than a
+    /// value, so a later duplicate of the member can drop its leaves.
+    opens: bool,
+}
+
+impl Leaf {
+    fn new(sort_key: &str, path: &str, depth: usize, value: Value) -> Self {
+        Leaf {
+            sort_key: sort_key.to_string(),
+            path: path.to_string(),
+            depth,
+            value,
+            seq: 0,
+            opens: false,
+        }
+    }
+
+    /// Bytes the leaf holds in memory, roughly.
+    fn size(&self) -> usize {
+        let value = match &self.value {
+            Value::String(s) => s.len(),
+            Value::Number(n) => n.as_str().len(),
+            _ => 0,
+        };
+        self.sort_key.len() + self.path.len() + value + 64
+    }
+
+    /// Writes the leaf as length-prefixed fields; far cheaper to read back
+    /// than a JSON record.
+    fn write_to(&self, out: &mut impl Write, scratch: &mut Vec<u8>) -> io::Result<()> {
+        scratch.clear();
+        serde_json::to_writer(&mut *scratch, &self.value)?;
+        for field in [self.sort_key.as_bytes(), self.path.as_bytes(), scratch] {
+            out.write_all(&(field.len() as u32).to_le_bytes())?;
+            out.write_all(field)?;
+        }
+        out.write_all(&(self.depth as u32).to_le_bytes())?;
+        out.write_all(&self.seq.to_le_bytes())?;
+        out.write_all(&[self.opens as u8])
+    }
+
+    /// Reads the next leaf, or `None` at the end of the run.
+    fn read_from(input: &mut impl Read) -> io::Result<Option<Leaf>> {
+        fn read_u32(input: &mut impl Read) -> io::Result<u32> {
+            let mut bytes = [0u8; 4];
+            input.read_exact(&mut bytes)?;
+            Ok(u32::from_le_bytes(bytes))
+        }
+        fn read_field(input: &mut impl Read) -> io::Result<Vec<u8>> {
+            let mut field = vec![0u8; read_u32(input)? as usize];
+            input.read_exact(&mut field)?;
+            Ok(field)
+        }
+        let sort_key = match read_field(input) {
+            Ok(field) => field,
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let invalid = |e| io::Error::new(io::ErrorKind::InvalidData, e);
+        let mut leaf = Leaf {
+            sort_key: String::from_utf8(sort_key).map_err(invalid)?,
+            path: String::from_utf8(read_field(input)?).map_err(invalid)?,
+            value: serde_json::from_slice(&read_field(input)?)?,
+            depth: read_u32(input)? as usize,
+            seq: 0,
+            opens: false,
+        };
+        let mut seq = [0u8; 8];
+        input.read_exact(&mut seq)?;
+        leaf.seq = u64::from_le_bytes(seq);
+        let mut opens = [0u8];
+        input.read_exact(&mut opens)?;
+        leaf.opens = opens[0] != 0;
+        Ok(Some(leaf))
+    }
+}
+
+/// Reads `file` and writes its leaves as sorted runs.
+fn spill_runs(
+    file: &Path,
+    dir: &Path,
+    prefix: &str,
+    phase: Phase,
+    options: &StreamOptions,
+    run_bytes: usize,
+    on_progress: &mut dyn FnMut(Progress),
+) -> Result<Vec<PathBuf>, String> {
+    let total = fs::metadata(file).map_err(|e| e.to_string())?.len();
+    let input = File::open(file).map_err(|e| e.to_string())?;
+    let mut runs = Vec::new();
+    let mut buffer: Vec<Leaf> = Vec::new();
+    let mut buffered_bytes = 0;
+    let mut reported = 0;
+    let mut seq = 0;
+
+    let mut spill = |buffer: &mut Vec<Leaf>| -> io::Result<()> {
+        buffer.sort_by(|a, b| a.sort_key.cmp(&b.sort_key));
+        let path = dir.join(format!("{prefix}-{}.run", runs.len()));
+        let mut out = BufWriter::new(File::create(&path)?);
+        let mut scratch = Vec::new();
+        for leaf in buffer.drain(..) {
+            leaf.write_to(&mut out, &mut scratch)?;
+        }
+        out.flush()?;
+        runs.push(path);
+        Ok(())
+    };
+
+    let mut on_leaf = |mut leaf: Leaf, offset: u64| {
+        leaf.seq = seq;
+        seq += 1;
+        buffered_bytes += leaf.size();
+        buffer.push(leaf);
+        if buffered_bytes >= run_bytes {
+            spill(&mut buffer).map_err(|e| e.to_string())?;
+            buffered_bytes = 0;
+        }
+        if offset - reported >= PROGRESS_BYTES {
+            reported = offset;
+            on_progress(Progress {
+                phase,
+                done: offset,
+                total,
+            });
+        }
+        Ok(())
+    };
+    if options.json_lines {
+        read_records(input, options.key_field.as_deref(), &mut on_leaf)?;
+    } else {
+        Tokenizer::new(input).leaves(&mut on_leaf)?;
+    }
+    if !buffer.is_empty() {
+        spill(&mut buffer).map_err(|e| e.to_string())?;
+    }
+    on_progress(Progress {
+        phase,
+        done: total,
+        total,
+    });
+    Ok(runs)
+}
+
+/// Reads a JSON Lines file one record at a time and emits the leaves of
+/// each under its record path. Blank lines are skipped. When pairing by
+/// key, the record's position follows the key in its sort key, so records
+/// sharing a key sort together in file order and [`Occurrences`] can tell
+/// them apart as `[id=42 #2]`.
+fn read_records(
+    input: File,
+    key_field: Option<&str>,
+    emit: &mut dyn FnMut(Leaf, u64) -> Result<(), String>,
+) -> Result<(), String> {
+    let mut reader = BufReader::with_capacity(1 << 20, input);
+    let mut line = String::new();
+    let mut offset = 0u64;
+    let mut line_number = 0;
+    let mut position = 0usize;
+    loop {
+        line.clear();
+        let read = reader.read_line(&mut line).map_err(|e| e.to_string())?;
+        if read == 0 {
+            return Ok(());
+        }
+        offset += read as u64;
+        line_number += 1;
+        // Only the end is trimmed, so error columns count from the line start.
+        let text = line.trim_end();
+        if text.is_empty() {
+            continue;
+        }
+        let record: Value = serde_json::from_str(text).map_err(|e| {
+            let message = e.to_string();
+            let message = message
+                .rfind(" at line ")
+                .map_or(&*message, |idx| &message[..idx]);
+            format!("{message} at line {line_number} column {}", e.column())
+        })?;
+        let (mut path, mut sort_key) = match key_field {
+            None => (format!("[{position}]"), format!("\0\u{1}{position:020}")),
+            Some(field) => {
+                let key = field_value(&record, field).ok_or_else(|| {
+                    format!("the record at line {line_number} has no `{field}` field")
+                })?;
+                let key = match key {
+                    Value::Number(n) => number_text(n),
+                    _ => key.to_string(),
+                };
+                let mut sort_key = match key.parse::<u64>() {
+                    Ok(n) => format!("\0\u{1}{n:020}"),
+                    Err(_) => format!("\0\u{2}{key}"),
+                };
+                let path = record_path(&sort_key, key_field);
+                // JSON text never holds a raw control character, so this
+                // can't clash with another key.
+                sort_key.push_str(&format!("\u{1}{position:020}"));
+                (path, sort_key)
+            }
+        };
+        position += 1;
+        flatten(&record, &mut path, &mut sort_key, 1, offset, emit)?;
+    }
+}
+
+/// A number key spelled one way however it's written, so `1`, `1.0` and
+/// `10e-1` pair up just as `values_equal` compares them.
+fn number_text(n: &Number) -> String {
+    let (negative, digits, exponent) = source::number_key(&n.to_string());
+    let len = digits.len() as i64;
+    let text = match exponent {
+        _ if digits.is_empty() => "0".to_string(),
+        e if (len..=21).contains(&e) => format!("{digits}{}", "0".repeat((e - len) as usize)),
+        e if (1..len).contains(&e) => {
+            let (int, frac) = digits.split_at(e as usize);
+            format!("{int}.{frac}")
+        }
+        e if (-5..=0).contains(&e) => format!("0.{}{digits}", "0".repeat(-e as usize)),
+        e => {
+            let (first, rest) = digits.split_at(1);
+            let point = if rest.is_empty() { "" } else { "." };
+            format!("{first}{point}{rest}e{}", e - 1)
+        }
+    };
+    if negative {
+        format!("-{text}")
+    } else {
+        text
+    }
+}
+
+/// The field a record is keyed by: `field` as written, or else followed as
+/// a dotted path.
+fn field_value<'v>(record: &'v Value, field: &str) -> Option<&'v Value> {
+    record.get(field).or_else(|| {
+        field
+            .split('.')
+            .try_fold(record, |value, key| value.get(key))
+    })
+}
+
+/// Emits the leaves of `value` with the paths and sort keys the tokenizer
+/// would give them. `depth` counts the containers around `value`.
+fn flatten(
+    value: &Value,
+    path: &mut String,
+    sort_key: &mut String,
+    depth: usize,
+    offset: u64,
+    emit: &mut dyn FnMut(Leaf, u64) -> Result<(), String>,
+) -> Result<(), String> {
+    let (path_len, sort_len) = (path.len(), sort_key.len());
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, child) in map {
+                path.push('.');
+                path.push_str(key);
+                sort_key.push_str("\0\u{2}");
+                sort_key.push_str(key);
+                flatten(child, path, sort_key, depth + 1, offset, emit)?;
+                path.truncate(path_len);
+                sort_key.truncate(sort_len);
+            }
+            Ok(())
+        }
+        Value::Array(items) if !items.is_empty() => {
+            for (index, child) in items.iter().enumerate() {
+                path.push_str(&format!("[{index}]"));
+                sort_key.push_str(&format!("\0\u{1}{index:020}"));
+                flatten(child, path, sort_key, depth + 1, offset, emit)?;
+                path.truncate(path_len);
+                sort_key.truncate(sort_len);
+            }
+            Ok(())
+        }
+        _ => emit(Leaf::new(sort_key, path, depth, value.clone()), offset),
+    }
+}
+
+/// The part of a leaf's sort key that names its JSON Lines record.
+fn record_key(sort_key: &str) -> &str {
+    let end = sort_key[1..]
+        .find('\0')
+        .map_or(sort_key.len(), |idx| idx + 1);
+    &sort_key[..end]
+}
+
+/// Turns a record's sort key back into the path its leaves start with.
+fn record_path(sort_key: &str, key_field: Option<&str>) -> String {
+    let encoded = &sort_key[2..];
+    let (key, count) = match encoded.split_once('\u{1}') {
+        Some((key, count)) => (key, count.trim_start_matches('0')),
+        None => (encoded, ""),
+    };
+    // Numbers are zero-padded to sort numerically, strings show without
+    // their JSON quotes.
+    let key = if sort_key.starts_with("\0\u{1}") {
+        match key.trim_start_matches('0') {
+            "" => "0".to_string(),
+            digits => digits.to_string(),
+        }
+    } else {
+        serde_json::from_str::<String>(key).unwrap_or_else(|_| key.to_string())
+    };
+    match key_field {
+        None => format!("[{key}]"),
+        Some(field) if count.is_empty() => format!("[{field}={key}]"),
+        Some(field) => format!("[{field}={key} #{count}]"),
+    }
+}
+
+/// Head of one run in the merge heap, ordered by path and then by run so
+/// that equal paths come out in document order.
+struct Head {
+    leaf: Leaf,
+    run: usize,
+}
+
+impl PartialEq for Head {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Head {}
+
+impl PartialOrd for Head {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Head {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (&self.leaf.sort_key, self.run).cmp(&(&other.leaf.sort_key, other.run))
+    }
+}
+
+/// K-way merge over sorted run files. Of several values for the same path
+/// (duplicate keys) only the last is kept, as when parsing normally, along
+/// with only the leaves below it that came after it.
+struct MergedRuns {
+    readers: Vec<BufReader<File>>,
+    heap: BinaryHeap<Reverse<Head>>,
+    /// Sort keys of the paths above the current leaf, each with the latest
+    /// `seq` that assigned it or anything above it.
+    assigned: Vec<(String, u64)>,
+    /// The next leaf, numbered if need be.
+    ahead: Option<Leaf>,
+    /// Set for JSON Lines records paired by key.
+    occurrences: Option<Occurrences>,
+}
+
+impl MergedRuns {
+    fn open(runs: &[PathBuf], key_field: Option<&str>) -> io::Result<Self> {
+        let mut merged = MergedRuns {
+            readers: Vec::new(),
+            heap: BinaryHeap::new(),
+            assigned: Vec::new(),
+            ahead: None,
+            occurrences: key_field.map(Occurrences::new),
+        };
+        for run in runs {
+            merged
+                .readers
+                .push(BufReader::with_capacity(1 << 16, File::open(run)?));
+            merged.advance(merged.readers.len() - 1)?;
+        }
+        merged.ahead = merged.pull()?;
+        Ok(merged)
+    }
+
+    fn advance(&mut self, run: usize) -> io::Result<()> {
+        if let Some(leaf) = Leaf::read_from(&mut self.readers[run])? {
+            self.heap.push(Reverse(Head { leaf, run }));
+        }
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<&Leaf> {
+        self.ahead.as_ref()
+    }
+
+    fn next(&mut self) -> io::Result<Option<Leaf>> {
+        let next = self.pull()?;
+        Ok(std::mem::replace(&mut self.ahead, next))
+    }
+
+    fn pull(&mut self) -> io::Result<Option<Leaf>> {
+        loop {
+            let Some(Reverse(head)) = self.heap.pop() else {
+                return Ok(None);
+            };
+            self.advance(head.run)?;
+            let mut leaf = head.leaf;
+            while self
+                .heap
+                .peek()
+                .is_some_and(|Reverse(next)| next.leaf.sort_key == leaf.sort_key)
+            {
+                let Reverse(head) = self.heap.pop().expect("peeked");
+                self.advance(head.run)?;
+                if head.leaf.seq > leaf.seq {
+                    leaf = head.leaf;
+                }
+            }
+            while self.assigned.last().is_some_and(|(key, _)| {
+                !(leaf.sort_key.starts_with(key.as_str())
+                    && leaf.sort_key[key.len()..].starts_with('\0'))
+            }) {
+                self.assigned.pop();
+            }
+            let above = self.assigned.last().map_or(0, |&(_, seq)| seq);
+            if leaf.seq < above {
+                // Left over from a value a later duplicate key replaced.
+                continue;
+            }
+            self.assigned.push((leaf.sort_key.clone(), leaf.seq));
+            if leaf.opens {
+                continue;
+            }
+            if let Some(occurrences) = &mut self.occurrences {
+                occurrences.number(&mut leaf);
+            }
+            return Ok(Some(leaf));
+        }
+    }
+}
+
+/// Numbers JSON Lines records that share a key, `[id=42 #2]`, in place of
+/// the position that keeps them apart while sorting. Their leaves arrive
+/// together and in file order, so only the current record is remembered.
+struct Occurrences {
+    key_field: String,
+    /// Record part of the last sort key, position included.
+    record: String,
+    /// Length of the key in `record`.
+    key_len: usize,
+    count: u64,
+    sort_prefix: String,
+    path_prefix: String,
+    /// Length of the record path as read.
+    read_path_len: usize,
+}
+
+impl Occurrences {
+    fn new(key_field: &str) -> Self {
+        Occurrences {
+            key_field: key_field.to_string(),
+            record: String::new(),
+            key_len: 0,
+            count: 0,
+            sort_prefix: String::new(),
+            path_prefix: String::new(),
+            read_path_len: 0,
+        }
+    }
+
+    fn number(&mut self, leaf: &mut Leaf) {
+        let record = record_key(&leaf.sort_key);
+        if record != self.record {
+            let key_len = record.rfind('\u{1}').expect("records keep their position");
+            let key = &record[..key_len];
+            self.count = if self.record.get(..self.key_len) == Some(key) {
+                self.count + 1
+            } else {
+                1
+            };
+            self.sort_prefix = key.to_string();
+            if self.count > 1 {
+                self.sort_prefix
+                    .push_str(&format!("\u{1}{:020}", self.count));
+            }
+            let field = Some(self.key_field.as_str());
+            self.read_path_len = record_path(key, field).len();
+            self.path_prefix = record_path(&self.sort_prefix, field);
+            self.record = record.to_string();
+            self.key_len = key_len;
+        }
+        leaf.sort_key
+            .replace_range(..self.record.len(), &self.sort_prefix);
+        leaf.path
+            .replace_range(..self.read_path_len, &self.path_prefix);
+    }
+}
+
+enum Frame {
+    Object {
+        path_len: usize,
+        sort_len: usize,
+    },
+    Array {
+        path_len: usize,
+        sort_len: usize,
+        index: usize,
+    },
+}
+
+/// Pull tokenizer over JSON bytes that reports every leaf with its path,
+/// keeping only the current path and one read buffer in memory.
+struct Tokenizer<R> {
+    reader: R,
+    buf: Box<[u8]>,
+    pos: usize,
+    len: usize,
+    /// Bytes consumed before `buf`.
+    consumed: u64,
+    path: String,
+    sort_key: String,
+    stack: Vec<Frame>,
+}
+
+impl<R: Read> Tokenizer<R> {
+    fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: vec![0; 1 << 20].into_boxed_slice(),
+            pos: 0,
+            len: 0,
+            consumed: 0,
+            path: String::new(),
+            sort_key: String::new(),
+            stack: Vec::new(),
+        }
+    }
+
+    fn offset(&self) -> u64 {
+        self.consumed + self.pos as u64
+    }
+
+    /// Makes sure the buffer holds at least one unread byte, unless the
+    /// input is exhausted.
+    fn fill(&mut self) -> Result<bool, String> {
+        if self.pos < self.len {
+            return Ok(true);
+        }
+        self.consumed += self.len as u64;
+        self.pos = 0;
+        self.len = loop {
+            match self.reader.read(&mut self.buf) {
+                Ok(n) => break n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.to_string()),
+            }
+        };
+        Ok(self.len > 0)
+    }
+
+    fn peek(&mut self) -> Result<Option<u8>, String> {
+        Ok(self.fill()?.then(|| self.buf[self.pos]))
+    }
+
+    fn bump(&mut self) {
+        self.pos += 1;
+    }
+
+    fn skip_whitespace(&mut self) -> Result<Option<u8>, String> {
+        while self.fill()? {
+            let rest = &self.buf[self.pos..self.len];
+            match rest
+                .iter()
+                .position(|b| !matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+            {
+                Some(skip) => {
+                    self.pos += skip;
+                    return Ok(Some(self.buf[self.pos]));
+                }
+                None => self.pos = self.len,
+            }
+        }
+        Ok(None)
+    }
+
+    fn unexpected(&self, found: Option<u8>, expected: &str) -> String {
+        match found {
+            Some(b) => format!(
+                "expected {expected}, found '{}' at byte {}",
+                b.escape_ascii(),
+                self.offset()
+            ),
+            None => format!("expected {expected}, found end of file"),
+        }
+    }
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        match self.skip_whitespace()? {
+            Some(b) if b == byte => {
+                self.bump();
+                Ok(())
+            }
+            found => Err(self.unexpected(found, &format!("'{}'", byte as char))),
+        }
+    }
+
+    /// Calls `emit` with each leaf and the byte offset reached so far.
+    fn leaves(
+        &mut self,
+        emit: &mut dyn FnMut(Leaf, u64) -> Result<(), String>,
+    ) -> Result<(), String> {
+        loop {
+            self.value(emit)?;
+            // Close every container the value completed, then move on to
+            // the next sibling.
+            loop {
+                let found = self.skip_whitespace()?;
+                let Some(frame) = self.stack.last_mut() else {
+                    return match found {
+                        None => Ok(()),
+                        found => Err(self.unexpected(found, "end of file")),
+                    };
+                };
+                match (frame, found) {
+                    (
+                        Frame::Object {
+                            path_len, sort_len, ..
+                        },
+                        Some(b','),
+                    ) => {
+                        let (path_len, sort_len) = (*path_len, *sort_len);
+                        self.bump();
+                        self.enter_field(path_len, sort_len)?;
+                        break;
+                    }
+                    (
+                        Frame::Array {
+                            path_len,
+                            sort_len,
+                            index,
+                        },
+                        Some(b','),
+                    ) => {
+                        *index += 1;
+                        let (path_len, sort_len, index) = (*path_len, *sort_len, *index);
+                        self.bump();
+                        self.enter_index(path_len, sort_len, index);
+                        break;
+                    }
+                    (Frame::Object { .. }, Some(b'}')) | (Frame::Array { .. }, Some(b']')) => {
+                        self.bump();
+                        self.leave();
+                    }
+                    (Frame::Object { .. }, found) => {
+                        return Err(self.unexpected(found, "',' or '}'"))
+                    }
+                    (Frame::Array { .. }, found) => {
+                        return Err(self.unexpected(found, "',' or ']'"))
+                    }
+                }
+            }
+        }
+    }
+
+    /// Emits a marker for a non-empty container that is an object member,
+    /// which may be a duplicate key replacing an earlier value.
+    fn opening(
+        &mut self,
+        emit: &mut dyn FnMut(Leaf, u64) -> Result<(), String>,
+    ) -> Result<(), String> {
+        if !matches!(self.stack.last(), Some(Frame::Object { .. })) {
+            return Ok(());
+        }
+        let mut marker = Leaf::new(&self.sort_key, &self.path, self.stack.len(), Value::Null);
+        marker.opens = true;
+        emit(marker, self.offset())
+    }
+
+    /// Reads one value. Containers are opened and positioned on their first
+    /// child; primitives and empty containers are emitted.
+    fn value(
+        &mut self,
+        emit: &mut dyn FnMut(Leaf, u64) -> Result<(), String>,
+    ) -> Result<(), String> {
+        loop {
+            let leaf_value = match self.skip_whitespace()? {
+                Some(b'{') => {
+                    self.bump();
+                    if self.skip_whitespace()? == Some(b'}') {
+                        self.bump();
+                        Value::Object(Map::new())
+                    } else {
+                        self.opening(emit)?;
+                        let (path_len, sort_len) = (self.path.len(), self.sort_key.len());
+                        self.stack.push(Frame::Object { path_len, sort_len });
+                        self.enter_field(path_len, sort_len)?;
+                        continue;
+                    }
+                }
+                Some(b'[') => {
+                    self.bump();
+                    if self.skip_whitespace()? == Some(b']') {
+                        self.bump();
+                        Value::Array(Vec::new())
+                    } else {
+                        self.opening(emit)?;
+                        let (path_len, sort_len) = (self.path.len(), self.sort_key.len());
+                        self.stack.push(Frame::Array {
+                            path_len,
+                            sort_len,
+                            index: 0,
+                        });
+                        self.enter_index(path_len, sort_len, 0);
+                        continue;
+                    }
+                }
+                Some(b'"') => Value::String(self.string()?),
+                Some(_) => self.scalar()?,
+                None => return Err(self.unexpected(None, "a value")),
+            };
+            let leaf = Leaf::new(&self.sort_key, &self.path, self.stack.len(), leaf_value);
+            return emit(leaf, self.offset());
+        }
+    }
+
+    fn enter_field(&mut self, path_len: usize, sort_len: usize) -> Result<(), String> {
+        match self.skip_whitespace()? {
+            Some(b'"') => {}
+            found => return Err(self.unexpected(found, "a string key")),
+        }
+        let key = self.string()?;
+        self.expect(b':')?;
+        self.path.truncate(path_len);
+        self.sort_key.truncate(sort_len);
+        if path_len > 0 {
+            self.path.push('.');
+        }
+        self.path.push_str(&key);
+        self.sort_key.push_str("\0\u{2}");
+        self.sort_key.push_str(&key);
+        Ok(())
+    }
+
+    fn enter_index(&mut self, path_len: usize, sort_len: usize, index: usize) {
+        self.path.truncate(path_len);
+        self.sort_key.truncate(sort_len);
+        self.path.push_str(&format!("[{index}]"));
+        self.sort_key.push_str(&format!("\0\u{1}{index:020}"));
+    }
+
+    /// Restores the path of the container being closed.
+    fn leave(&mut self) {
+        let (path_len, sort_len) = match self.stack.pop() {
+            Some(Frame::Object { path_len, sort_len })
+            | Some(Frame::Array {
+                path_len, sort_len, ..
+            }) => (path_len, sort_len),
+            None => return,
+        };
+        self.path.truncate(path_len);
+        self.sort_key.truncate(sort_len);
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        let mut raw = vec![b'"'];
+        self.bump();
+        let mut escaped = false;
+        loop {
+            if !self.fill()? {
+                return Err(self.unexpected(None, "'\"'"));
+            }
+            let rest = &self.buf[self.pos..self.len];
+            match rest.iter().position(|&b| b == b'"' || b == b'\\') {
+                None => {
+                    raw.extend_from_slice(rest);
+                    self.pos = self.len;
+                }
+                Some(at) => {
+                    let b = rest[at];
+                    raw.extend_from_slice(&rest[..=at]);
+                    self.pos += at + 1;
+                    if b == b'"' {
+                        break;
+                    }
+                    escaped = true;
+                    match self.peek()? {
+                        Some(next) => {
+                            raw.push(next);
+                            self.bump();
+                        }
+                        None => return Err(self.unexpected(None, "an escape sequence")),
+                    }
+                }
+            }
+        }
+        let at = self.offset();
+        let invalid =
+            |e: &dyn std::fmt::Display| format!("invalid string ending at byte {at}: {e}");
+        if escaped {
+            serde_json::from_slice(&raw).map_err(|e| invalid(&e))
+        } else {
+            raw.pop();
+            String::from_utf8(raw.split_off(1)).map_err(|e| invalid(&e))
+        }
+    }
+
+    fn scalar(&mut self) -> Result<Value, String> {
+        let start = self.offset();
+        let mut token = Vec::new();
+        while self.fill()? {
+            let rest = &self.buf[self.pos..self.len];
+            let end = rest
+                .iter()
+                .position(|b| matches!(b, b',' | b'}' | b']' | b' ' | b'\t' | b'\n' | b'\r'));
+            token.extend_from_slice(&rest[..end.unwrap_or(rest.len())]);
+            match end {
+                Some(end) => {
+                    self.pos += end;
+                    break;
+                }
+                None => self.pos = self.len,
+            }
+        }
+        if token.is_empty() {
+            let found = self.peek()?;
+            return Err(self.unexpected(found, "a value"));
+        }
+        serde_json::from_slice(&token).map_err(|_| {
+            format!(
+                "invalid value '{}' at byte {start}",
+                String::from_utf8_lossy(&token)
+            )
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Input files in a work directory that is removed with it.
+    struct Inputs(Cleanup);
+
+    impl Inputs {
+        fn new() -> Self {
+            Inputs(Cleanup(scratch_dir().unwrap()))
+        }
+
+        fn dir(&self) -> &Path {
+            &self.0 .0
+        }
+
+        fn file(&self, name: &str, text: &str) -> PathBuf {
+            let path = self.dir().join(name);
+            fs::write(&path, text).unwrap();
+            path
+        }
+    }
+
+    fn build(left: &str, right: &str, options: &StreamOptions, run_bytes: usize) -> StreamDiff {
+        let inputs = Inputs::new();
+        let (left, right) = (inputs.file("left", left), inputs.file("right", right));
+        StreamDiff::build_with(&left, &right, options, run_bytes, &mut |_| {}).unwrap()
+    }
+
+    fn all_rows(diff: &mut StreamDiff, changes_only: bool) -> Vec<StreamRow> {
+        diff.rows(0, usize::MAX, changes_only).unwrap()
+    }
+
+    fn listing(rows: &[StreamRow]) -> Vec<(String, DiffType)> {
+        rows.iter()
+            .map(|row| (row.path.clone(), row.diff_type))
+            .collect()
+    }
+
+    /// A document with enough leaves to spread over many small runs.
+    fn document(seed: u64) -> String {
+        let items: Vec<Value> = (0..40)
+            .map(|idx| json!({ "id": idx, "score": (idx * seed) % 7, "tags": ["a", idx % 3] }))
+            .collect();
+        json!({ "items": items, "meta": { "seed": seed, "empty": {} } }).to_string()
+    }
+
+    #[test]
+    fn small_runs_give_the_same_rows() {
+        let options = StreamOptions::default();
+        let (left, right) = (document(3), document(5));
+        let mut whole = build(&left, &right, &options, RUN_BYTES);
+        // Every leaf costs at least 64 bytes, so runs hold a couple each.
+        let mut spilled = build(&left, &right, &options, 150);
+        let inputs = Inputs::new();
+        let file = inputs.file("left", &left);
+        let runs = spill_runs(
+            &file,
+            inputs.dir(),
+            "runs",
+            Phase::ReadingLeft,
+            &options,
+            150,
+            &mut |_| {},
+        );
+        assert!(runs.unwrap().len() > 40);
+        let rows = all_rows(&mut whole, false);
+        assert_eq!(listing(&rows), listing(&all_rows(&mut spilled, false)));
+        assert_eq!(whole.summary.rows, 40 * 4 + 2);
+        assert_eq!(spilled.summary.changes, whole.summary.changes);
+
+        // Indexes sort numerically, fields alphabetically.
+        let paths: Vec<_> = rows.iter().map(|row| row.path.as_str()).collect();
+        let position = |path| paths.iter().position(|p| *p == path).unwrap();
+        assert!(position("items[2].id") < position("items[10].id"));
+        assert!(position("items[10].tags[1]") < position("meta.empty"));
+        let seed = &rows[position("meta.seed")];
+        assert_eq!(seed.diff_type, DiffType::Changed);
+        assert_eq!(
+            (seed.left.clone(), seed.right.clone()),
+            (Some(json!(3)), Some(json!(5)))
+        );
+        assert_eq!(rows[position("meta.empty")].left, Some(json!({})));
+    }
+
+    #[test]
+    fn work_directory_goes_with_the_diff() {
+        let diff = build("[1]", "[2]", &StreamOptions::default(), RUN_BYTES);
+        let dir = diff._dir.0.clone();
+        assert!(dir.join("rows.jsonl").exists());
+        drop(diff);
+        assert!(!dir.exists());
+    }
+
+    #[test]
+    fn duplicate_keys_across_runs_keep_the_last() {
+        let filler: Vec<_> = (0..20).map(|idx| format!("\"k{idx:02}\": {idx}")).collect();
+        let left = format!("{{\"a\": 1, {}, \"a\": 2}}", filler.join(", "));
+        let right = format!("{{{}, \"a\": 2}}", filler.join(", "));
+        let mut diff = build(&left, &right, &StreamOptions::default(), 100);
+        let rows = all_rows(&mut diff, false);
+        assert_eq!(rows.len(), 21);
+        assert_eq!(rows[0].path, "a");
+        assert_eq!(rows[0].diff_type, DiffType::Unchanged);
+        assert_eq!(rows[0].left, Some(json!(2)));
+        assert_eq!(diff.summary.changes, 0);
+    }
+
+    #[test]
+    fn duplicate_keys_drop_what_they_replace() {
+        let left = r#"{"a": {"x": 1, "y": [1, {"z": 2}]}, "b": 1, "a": 5,
+            "c": {"k": 1}, "c": {"j": 2}, "d": 1, "d": [3, 4], "e": [1, 2], "e": [3]}"#;
+        let right = r#"{"a": 5, "b": 1, "c": {"j": 2}, "d": [3, 4], "e": [3]}"#;
+        for run_bytes in [RUN_BYTES, 100] {
+            let mut diff = build(left, right, &StreamOptions::default(), run_bytes);
+            let rows = all_rows(&mut diff, false);
+            assert_eq!(
+                listing(&rows),
+                [
+                    ("a".to_string(), DiffType::Unchanged),
+                    ("b".to_string(), DiffType::Unchanged),
+                    ("c.j".to_string(), DiffType::Unchanged),
+                    ("d[0]".to_string(), DiffType::Unchanged),
+                    ("d[1]".to_string(), DiffType::Unchanged),
+                    ("e[0]".to_string(), DiffType::Unchanged),
+                ],
+                "{run_bytes}"
+            );
+        }
+        // Nested: only the last `b` of the last `a` counts.
+        let left = r#"{"a": {"b": {"x": 1}}, "a": {"b": {"y": 1}, "b": {"z": 1}}}"#;
+        let mut diff = build(
+            left,
+            r#"{"a": {"b": {"z": 1}}}"#,
+            &StreamOptions::default(),
+            100,
+        );
+        assert_eq!(
+            listing(&all_rows(&mut diff, false)),
+            [("a.b.z".to_string(), DiffType::Unchanged)]
+        );
+    }
+
+    #[test]
+    fn runs_count_the_size_of_string_values() {
+        let inputs = Inputs::new();
+        let long = "x".repeat(1000);
+        let file = inputs.file("left", &json!([&long, &long, &long, &long]).to_string());
+        let runs = spill_runs(
+            &file,
+            inputs.dir(),
+            "runs",
+            Phase::ReadingLeft,
+            &StreamOptions::default(),
+            1500,
+            &mut |_| {},
+        );
+        assert_eq!(runs.unwrap().len(), 2);
+    }
+
+    #[test]
+    fn pages_read_back_through_the_indexes() {
+        let mut diff = build(&document(3), &document(5), &StreamOptions::default(), 150);
+        for changes_only in [false, true] {
+            let all = listing(&all_rows(&mut diff, changes_only));
+            let mut paged = Vec::new();
+            for start in (0..all.len() as u64 + 7).step_by(7) {
+                paged.extend(listing(&diff.rows(start, 7, changes_only).unwrap()));
+            }
+            assert_eq!(paged, all);
+        }
+        let changes = all_rows(&mut diff, true);
+        assert_eq!(changes.len() as u64, diff.summary.changes);
+        assert!(changes
+            .iter()
+            .all(|row| row.diff_type != DiffType::Unchanged));
+        let total = diff.summary.rows;
+        assert!(diff.rows(total, 10, false).unwrap().is_empty());
+    }
+
+    #[test]
+    fn null_and_missing_stay_apart() {
+        let mut diff = build(
+            r#"{"a": null, "b": 1}"#,
+            r#"{"b": 1.0, "c": null}"#,
+            &StreamOptions::default(),
+            RUN_BYTES,
+        );
+        let rows = all_rows(&mut diff, false);
+        assert_eq!(
+            listing(&rows),
+            [
+                ("a".to_string(), DiffType::Removed),
+                ("b".to_string(), DiffType::Unchanged),
+                ("c".to_string(), DiffType::Added),
+            ]
+        );
+        assert_eq!(
+            (rows[0].left.clone(), rows[0].right.clone()),
+            (Some(Value::Null), None)
+        );
+    }
+
+    #[test]
+    fn json_lines_by_position() {
+        let options = StreamOptions {
+            json_lines: true,
+            key_field: None,
+        };
+        let mut diff = build("{\"a\": 1}\n5\n[]\n", "{\"a\": 2}\r\n\n5\n", &options, 100);
+        let rows = all_rows(&mut diff, false);
+        assert_eq!(
+            listing(&rows),
+            [
+                ("[0]".to_string(), DiffType::Changed),
+                ("[0].a".to_string(), DiffType::Changed),
+                ("[1]".to_string(), DiffType::Unchanged),
+                ("[1]".to_string(), DiffType::Unchanged),
+                ("[2]".to_string(), DiffType::Removed),
+                ("[2]".to_string(), DiffType::Removed),
+            ]
+        );
+        assert_eq!((rows[0].depth, rows[1].depth, rows[3].depth), (0, 2, 1));
+        let records = diff.summary.records.clone().unwrap();
+        assert_eq!(
+            (
+                records.total,
+                records.added,
+                records.removed,
+                records.changed
+            ),
+            (3, 0, 1, 1)
+        );
+    }
+
+    #[test]
+    fn json_lines_by_key() {
+        let options = StreamOptions {
+            json_lines: true,
+            key_field: Some("id".into()),
+        };
+        let left = [
+            r#"{"id": 1, "v": "a"}"#,
+            r#"{"id": "x", "v": 1}"#,
+            r#"{"id": 10, "v": "b"}"#,
+            r#"{"id": 1, "v": "c"}"#,
+            r#"{"id": "q\"t", "v": 2}"#,
+        ];
+        let right = [
+            r#"{"id": 10, "v": "b"}"#,
+            r#"{"id": 1, "v": "a"}"#,
+            r#"{"id": 1, "v": "C"}"#,
+            r#"{"id": "x", "v": 1}"#,
+            r#"{"id": 1, "v": "d"}"#,
+        ];
+        for run_bytes in [RUN_BYTES, 100] {
+            let mut diff = build(&left.join("\n"), &right.join("\n"), &options, run_bytes);
+            let rows = all_rows(&mut diff, true);
+            assert_eq!(
+                listing(&rows),
+                [
+                    ("[id=1 #2]".to_string(), DiffType::Changed),
+                    ("[id=1 #2].v".to_string(), DiffType::Changed),
+                    ("[id=1 #3]".to_string(), DiffType::Added),
+                    ("[id=1 #3].id".to_string(), DiffType::Added),
+                    ("[id=1 #3].v".to_string(), DiffType::Added),
+                    ("[id=q\"t]".to_string(), DiffType::Removed),
+                    ("[id=q\"t].id".to_string(), DiffType::Removed),
+                    ("[id=q\"t].v".to_string(), DiffType::Removed),
+                ]
+            );
+            let headers: Vec<_> = all_rows(&mut diff, false)
+                .into_iter()
+                .filter(|row| row.depth == 0)
+                .map(|row| row.path)
+                .collect();
+            // Numbers sort numerically and before strings.
+            assert_eq!(
+                headers,
+                [
+                    "[id=1]",
+                    "[id=1 #2]",
+                    "[id=1 #3]",
+                    "[id=10]",
+                    "[id=q\"t]",
+                    "[id=x]"
+                ]
+            );
+            let records = diff.summary.records.clone().unwrap();
+            assert_eq!(
+                (
+                    records.total,
+                    records.added,
+                    records.removed,
+                    records.changed
+                ),
+                (6, 1, 1, 1)
+            );
+        }
+    }
+
+    #[test]
+    fn number_keys_pair_by_value() {
+        let options = StreamOptions {
+            json_lines: true,
+            key_field: Some("id".into()),
+        };
+        let left = [
+            r#"{"id": 1, "v": "a"}"#,
+            r#"{"id": 1.50, "v": "b"}"#,
+            r#"{"id": -0.0001, "v": "c"}"#,
+            r#"{"id": 2e30, "v": "d"}"#,
+        ];
+        let right = [
+            r#"{"id": 1.0, "v": "a"}"#,
+            r#"{"id": 15e-1, "v": "B"}"#,
+            r#"{"id": -1e-4, "v": "c"}"#,
+            r#"{"id": 20e29, "v": "d"}"#,
+        ];
+        let mut diff = build(&left.join("\n"), &right.join("\n"), &options, RUN_BYTES);
+        let headers: Vec<_> = all_rows(&mut diff, false)
+            .into_iter()
+            .filter(|row| row.depth == 0)
+            .map(|row| (row.path, row.diff_type))
+            .collect();
+        assert_eq!(
+            headers,
+            [
+                ("[id=1]".to_string(), DiffType::Unchanged),
+                ("[id=-0.0001]".to_string(), DiffType::Unchanged),
+                ("[id=1.5]".to_string(), DiffType::Changed),
+                ("[id=2e30]".to_string(), DiffType::Unchanged),
+            ]
+        );
+        assert_eq!(
+            number_text(&"1e20".parse().unwrap()),
+            "100000000000000000000"
+        );
+        assert_eq!(number_text(&"10e20".parse().unwrap()), "1e21");
+        assert_eq!(number_text(&"-0".parse().unwrap()), "0");
+        assert_eq!(number_text(&"123.4500e2".parse().unwrap()), "12345");
+        assert_eq!(number_text(&"0.25e-6".parse().unwrap()), "2.5e-7");
+    }
+
+    #[test]
+    fn repeated_keys_are_numbered_in_file_order() {
+        let options = StreamOptions {
+            json_lines: true,
+            key_field: Some("meta.kind".into()),
+        };
+        let records = |changed: usize| {
+            (0..12)
+                .map(|n| {
+                    let v = if n == changed { 99 } else { n };
+                    json!({ "meta": { "kind": "k" }, "n": v }).to_string()
+                })
+                .collect::<Vec<_>>()
+                .join("\n")
+        };
+        // Small runs split the records of one key across many of them.
+        let mut diff = build(&records(usize::MAX), &records(10), &options, 150);
+        let changes = all_rows(&mut diff, true);
+        assert_eq!(
+            listing(&changes),
+            [
+                ("[meta.kind=k #11]".to_string(), DiffType::Changed),
+                ("[meta.kind=k #11].n".to_string(), DiffType::Changed),
+            ]
+        );
+        assert_eq!(changes[1].left, Some(json!(10)));
+        let headers: Vec<_> = all_rows(&mut diff, false)
+            .into_iter()
+            .filter(|row| row.depth == 0)
+            .map(|row| row.path)
+            .collect();
+        assert_eq!(headers.len(), 12);
+        assert_eq!(
+            (&*headers[0], &*headers[1]),
+            ("[meta.kind=k]", "[meta.kind=k #2]")
+        );
+        assert_eq!(headers[11], "[meta.kind=k #12]");
+    }
+
+    #[test]
+    fn json_lines_errors_name_the_line() {
+        let inputs = Inputs::new();
+        let bad = inputs.file("bad", "{\"a\": 1}\n{\"a\": }\n");
+        let good = inputs.file("good", "{\"a\": 1}\n");
+        let mut options = StreamOptions {
+            json_lines: true,
+            key_field: None,
+        };
+        let error = StreamDiff::build(&good, &bad, &options, &mut |_| {})
+            .err()
+            .unwrap();
+        assert_eq!(error, "right: expected value at line 2 column 7");
+        options.key_field = Some("id".into());
+        let error = StreamDiff::build(&good, &good, &options, &mut |_| {})
+            .err()
+            .unwrap();
+        assert_eq!(error, "left: the record at line 1 has no `id` field");
+    }
+}
@@ -0,0 +1,666 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE, WINDOWS_1252};
+use serde::{Deserialize, Serialize};
+
+use crate::diff::DiffType;
+
+/// Delimiters tried when sniffing, in order of preference on a tie.
+const DELIMITERS: [char; 4] = [',', '\t', ';', '|'];
+/// Records looked at when sniffing the delimiter.
+const SNIFF_RECORDS: usize = 20;
+/// Duplicate keys listed in a diff; the rest are only counted.
+const MAX_DUPLICATES: usize = 20;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TableDiffOptions {
+    /// Header names whose values identify a row. Without any, rows are
+    /// paired by position.
+    pub key_columns: Vec<String>,
+    /// Also return rows that didn't change; they're only counted otherwise.
+    pub include_unchanged: bool,
+}
+
+/// How a file was read.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Dialect {
+    pub delimiter: char,
+    pub quote: char,
+    /// The WHATWG name, e.g. `UTF-8` or `windows-1252`.
+    pub encoding: String,
+    pub bom: bool,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableInfo {
+    pub dialect: Dialect,
+    pub headers: Vec<String>,
+    /// Data rows, not counting the header.
+    pub rows: usize,
+}
+
+/// A column of either file, matched by header name. Columns only on one
+/// side are `added` or `removed` and their cells don't count as changes.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Column {
+    pub name: String,
+    pub status: DiffType,
+}
+
+/// A pair of rows, or a row on one side only. Cells follow
+/// [`TableDiff::columns`]; a side's missing columns are empty.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RowDiff {
+    pub status: DiffType,
+    /// The key values joined with ` / `, or `#n` when paired by position.
+    pub key: String,
+    /// 1-based line where the row starts in each file.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub right_line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub right: Option<Vec<String>>,
+    /// Indexes into the columns of the cells that differ.
+    pub changed: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableStats {
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+    pub unchanged: usize,
+    pub changed_cells: usize,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableDiff {
+    pub left: TableInfo,
+    pub right: TableInfo,
+    pub columns: Vec<Column>,
+    /// Rows in the right file's order, with removed rows placed after the
+    /// row that preceded them on the left.
+    pub rows: Vec<RowDiff>,
+    pub stats: TableStats,
+    /// Keys that identify more than one row on a side. Such rows are paired
+    /// in the order they appear.
+    pub duplicate_keys: Vec<String>,
+}
+
+/// A CSV or TSV file as read: a header row and data rows padded to its
+/// width.
+pub struct Table {
+    pub dialect: Dialect,
+    pub headers: Vec<String>,
+    pub rows: Vec<Row>,
+}
+
+pub struct Row {
+    /// 1-based.
+    pub line: usize,
+    pub cells: Vec<String>,
+}
+
+/// Reads a delimited file, sniffing its encoding, delimiter and quote
+/// character. The first record is the header.
+pub fn read(path: &Path) -> Result<Table, String> {
+    let bytes = std::fs::read(path).map_err(|e| format!("{}: {e}", path.display()))?;
+    Ok(parse(&bytes))
+}
+
+pub fn parse(bytes: &[u8]) -> Table {
+    let (text, encoding, bom) = decode(bytes);
+    let delimiter = sniff_delimiter(&text);
+    let quote = sniff_quote(&text, delimiter);
+    let mut records = records(&text, delimiter, quote, None).into_iter();
+    let header = records.next().map(|(_, fields)| fields).unwrap_or_default();
+    let mut rows: Vec<Row> = records.map(|(line, cells)| Row { line, cells }).collect();
+    let width = rows
+        .iter()
+        .map(|row| row.cells.len())
+        .chain(std::iter::once(header.len()))
+        .max()
+        .unwrap_or_default();
+    for row in &mut rows {
+        row.cells.resize(width, String::new());
+    }
+    Table {
+        dialect: Dialect {
+            delimiter,
+            quote,
+            encoding: encoding.name().to_string(),
+            bom,
+        },
+        headers: header_names(header, width),
+        rows,
+    }
+}
+
+/// Diffs two tables, pairing rows by the key columns.
+pub fn diff_tables(
+    left: &Table,
+    right: &Table,
+    options: &TableDiffOptions,
+) -> Result<TableDiff, String> {
+    let columns = columns(&left.headers, &right.headers);
+    let left_index = column_index(&left.headers, &columns);
+    let right_index = column_index(&right.headers, &columns);
+    let left_keys = keys(left, &options.key_columns, "left")?;
+    let right_keys = keys(right, &options.key_columns, "right")?;
+
+    let mut by_key: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (idx, key) in left_keys.iter().enumerate() {
+        by_key.entry(key).or_default().push(idx);
+    }
+    let mut right_counts: HashMap<&str, usize> = HashMap::new();
+    for key in &right_keys {
+        *right_counts.entry(key).or_default() += 1;
+    }
+    let mut listed = HashSet::new();
+    let duplicate_keys: Vec<String> = left_keys
+        .iter()
+        .chain(right_keys.iter())
+        .filter(|key| {
+            let key = key.as_str();
+            by_key.get(key).map_or(0, Vec::len) > 1 || right_counts.get(key).copied() > Some(1)
+        })
+        .filter(|key| listed.insert(key.as_str()))
+        .take(MAX_DUPLICATES)
+        .cloned()
+        .collect();
+
+    // Pair each right row with the first unused left row of the same key.
+    let mut next: HashMap<&str, usize> = HashMap::new();
+    let pairs: Vec<Option<usize>> = right_keys
+        .iter()
+        .map(|key| {
+            let taken = next.entry(key).or_default();
+            let left = by_key.get(key.as_str())?.get(*taken).copied();
+            *taken += 1;
+            left
+        })
+        .collect();
+    let mut matched = vec![false; left.rows.len()];
+    for &idx in pairs.iter().flatten() {
+        matched[idx] = true;
+    }
+
+    let mut stats = TableStats::default();
+    let mut rows = Vec::new();
+    let mut emit = |row: RowDiff| {
+        match row.status {
+            DiffType::Added => stats.added += 1,
+            DiffType::Removed => stats.removed += 1,
+            DiffType::Changed => {
+                stats.changed += 1;
+                stats.changed_cells += row.changed.len();
+            }
+            _ => stats.unchanged += 1,
+        }
+        if row.status != DiffType::Unchanged || options.include_unchanged {
+            rows.push(row);
+        }
+    };
+    let removed = |idx: usize| RowDiff {
+        status: DiffType::Removed,
+        key: left_keys[idx].clone(),
+        left_line: Some(left.rows[idx].line),
+        right_line: None,
+        left: Some(aligned(&left.rows[idx], &left_index)),
+        right: None,
+        changed: Vec::new(),
+    };
+    let mut next_left = 0;
+    for (right_idx, pair) in pairs.iter().enumerate() {
+        let right_row = &right.rows[right_idx];
+        let cells = aligned(right_row, &right_index);
+        let Some(left_idx) = *pair else {
+            let row = RowDiff {
+                status: DiffType::Added,
+                key: right_keys[right_idx].clone(),
+                left_line: None,
+                right_line: Some(right_row.line),
+                left: None,
+                right: Some(cells),
+                changed: Vec::new(),
+            };
+            emit(row);
+            continue;
+        };
+        // Rows removed since the last pair that came earlier on the left.
+        for idx in (next_left..left_idx).filter(|&idx| !matched[idx]) {
+            emit(removed(idx));
+        }
+        next_left = next_left.max(left_idx + 1);
+        let left_cells = aligned(&left.rows[left_idx], &left_index);
+        let changed: Vec<usize> = columns
+            .iter()
+            .enumerate()
+            .filter(|(idx, column)| {
+                column.status == DiffType::Unchanged && left_cells[*idx] != cells[*idx]
+            })
+            .map(|(idx, _)| idx)
+            .collect();
+        let row = RowDiff {
+            status: if changed.is_empty() {
+                DiffType::Unchanged
+            } else {
+                DiffType::Changed
+            },
+            key: right_keys[right_idx].clone(),
+            left_line: Some(left.rows[left_idx].line),
+            right_line: Some(right_row.line),
+            left: Some(left_cells),
+            right: Some(cells),
+            changed,
+        };
+        emit(row);
+    }
+    for idx in (next_left..left.rows.len()).filter(|&idx| !matched[idx]) {
+        emit(removed(idx));
+    }
+
+    Ok(TableDiff {
+        left: info(left),
+        right: info(right),
+        columns,
+        rows,
+        stats,
+        duplicate_keys,
+    })
+}
+
+fn info(table: &Table) -> TableInfo {
+    TableInfo {
+        dialect: table.dialect.clone(),
+        headers: table.headers.clone(),
+        rows: table.rows.len(),
+    }
+}
+
+/// Text of the file and how it was decoded. A byte order mark decides;
+/// otherwise NUL bytes in every other position mean UTF-16, valid UTF-8 is
+/// UTF-8, and anything else is taken for windows-1252, which is what Excel
+/// writes on Windows.
+fn decode(bytes: &[u8]) -> (String, &'static Encoding, bool) {
+    if let Some((encoding, bom_len)) = Encoding::for_bom(bytes) {
+        let (text, _) = encoding.decode_without_bom_handling(&bytes[bom_len..]);
+        return (text.into_owned(), encoding, true);
+    }
+    let sample = &bytes[..bytes.len().min(1024)];
+    let nul_at = |parity: usize| {
+        sample
+            .iter()
+            .skip(parity)
+            .step_by(2)
+            .filter(|&&b| b == 0)
+            .count()
+    };
+    let pairs = sample.len() / 2;
+    if pairs > 0 {
+        for (parity, encoding) in [(1, UTF_16LE), (0, UTF_16BE)] {
+            if nul_at(parity) * 2 > pairs {
+                let (text, _) = encoding.decode_without_bom_handling(bytes);
+                return (text.into_owned(), encoding, false);
+            }
+        }
+    }
+    match std::str::from_utf8(bytes) {
+        Ok(text) => (text.to_string(), encoding_rs::UTF_8, false),
+        Err(_) => {
+            let (text, _) = WINDOWS_1252.decode_without_bom_handling(bytes);
+            (text.into_owned(), WINDOWS_1252, false)
+        }
+    }
+}
+
+/// The delimiter that splits the first records into the most columns the
+/// most consistently. Files with a single column read as comma-separated.
+fn sniff_delimiter(text: &str) -> char {
+    let mut best = (',', 0);
+    for delimiter in DELIMITERS {
+        let widths: Vec<usize> = records(text, delimiter, '"', Some(SNIFF_RECORDS))
+            .iter()
+            .map(|(_, fields)| fields.len())
+            .collect();
+        let Some(&first) = widths.first() else {
+            continue;
+        };
+        if first < 2 {
+            continue;
+        }
+        let consistent = widths.iter().filter(|&&w| w == first).count();
+        // Columns count for more than consistency, so a stray comma in one
+        // line of a TSV doesn't make it comma-separated.
+        let score = consistent * first;
+        if score > best.1 {
+            best = (delimiter, score);
+        }
+    }
+    best.0
+}
+
+/// `'` when more fields open with it than with `"`.
+fn sniff_quote(text: &str, delimiter: char) -> char {
+    let opens = |quote: char| {
+        text.lines()
+            .take(SNIFF_RECORDS * 5)
+            .flat_map(|line| line.split(delimiter))
+            .filter(|field| field.trim_start().starts_with(quote))
+            .count()
+    };
+    if opens('\'') > opens('"') {
+        '\''
+    } else {
+        '"'
+    }
+}
+
+/// Splits `text` into records of fields, RFC 4180 style: quoted fields may
+/// hold delimiters, line breaks and doubled quotes. Blank lines are
+/// skipped; an unterminated quote runs to the end of the file. Each record
+/// comes with the line it starts on.
+fn records(
+    text: &str,
+    delimiter: char,
+    quote: char,
+    limit: Option<usize>,
+) -> Vec<(usize, Vec<String>)> {
+    let mut records = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    // Whether the current field has content or was quoted, so `a,` ends
+    // with an empty field but a blank line isn't a record.
+    let mut started = false;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quoted {
+            if c == quote {
+                if chars.peek() == Some(&quote) {
+                    field.push(quote);
+                    chars.next();
+                } else {
+                    quoted = false;
+                }
+            } else {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+            continue;
+        }
+        match c {
+            c if c == quote && field.trim().is_empty() => {
+                field.clear();
+                quoted = true;
+                started = true;
+            }
+            c if c == delimiter => {
+                fields.push(std::mem::take(&mut field));
+                started = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' | '\r' => {
+                if started || !field.is_empty() {
+                    fields.push(std::mem::take(&mut field));
+                    records.push((record_line, std::mem::take(&mut fields)));
+                    if limit.is_some_and(|limit| records.len() >= limit) {
+                        return records;
+                    }
+                }
+                started = false;
+                line += 1;
+                record_line = line;
+            }
+            c => field.push(c),
+        }
+    }
+    if started || !field.is_empty() {
+        fields.push(field);
+        records.push((record_line, fields));
+    }
+    records
+}
+
+/// Header names padded to `width`, with blank ones named by position and
+/// repeated ones numbered, so every column can be picked as a key by name.
+fn header_names(header: Vec<String>, width: usize) -> Vec<String> {
+    let mut seen = HashSet::new();
+    (0..width)
+        .map(|idx| {
+            let name = header.get(idx).map(|h| h.trim()).unwrap_or_default();
+            let name = if name.is_empty() {
+                format!("Column {}", idx + 1)
+            } else {
+                name.to_string()
+            };
+            let mut unique = name.clone();
+            let mut n = 2;
+            while !seen.insert(unique.clone()) {
+                unique = format!("{name} ({n})");
+                n += 1;
+            }
+            unique
+        })
+        .collect()
+}
+
+/// The left file's columns in order, then those only on the right.
+fn columns(left: &[String], right: &[String]) -> Vec<Column> {
+    let status = |name: &String, other: &[String], only: DiffType| {
+        if other.contains(name) {
+            DiffType::Unchanged
+        } else {
+            only
+        }
+    };
+    left.iter()
+        .map(|name| Column {
+            name: name.clone(),
+            status: status(name, right, DiffType::Removed),
+        })
+        .chain(
+            right
+                .iter()
+                .filter(|name| !left.contains(name))
+                .map(|name| Column {
+                    name: name.clone(),
+                    status: DiffType::Added,
+                }),
+        )
+        .collect()
+}
+
+/// For each column, where a side's rows hold it.
+fn column_index(headers: &[String], columns: &[Column]) -> Vec<Option<usize>> {
+    columns
+        .iter()
+        .map(|column| headers.iter().position(|h| *h == column.name))
+        .collect()
+}
+
+fn aligned(row: &Row, index: &[Option<usize>]) -> Vec<String> {
+    index
+        .iter()
+        .map(|idx| idx.map(|idx| row.cells[idx].clone()).unwrap_or_default())
+        .collect()
+}
+
+/// Each row's key, or its position when no key columns are given.
+fn keys(table: &Table, key_columns: &[String], side: &str) -> Result<Vec<String>, String> {
+    if key_columns.is_empty() {
+        return Ok((1..=table.rows.len()).map(|n| format!("#{n}")).collect());
+    }
+    let positions = key_columns
+        .iter()
+        .map(|name| {
+            table
+                .headers
+                .iter()
+                .position(|h| h == name)
+                .ok_or_else(|| format!("the {side} file has no column `{name}`"))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    Ok(table
+        .rows
+        .iter()
+        .map(|row| {
+            positions
+                .iter()
+                .map(|&idx| row.cells[idx].trim())
+                .collect::<Vec<_>>()
+                .join(" / ")
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cells(table: &Table) -> Vec<Vec<&str>> {
+        table
+            .rows
+            .iter()
+            .map(|row| row.cells.iter().map(String::as_str).collect())
+            .collect()
+    }
+
+    fn keyed(columns: &[&str]) -> TableDiffOptions {
+        TableDiffOptions {
+            key_columns: columns.iter().map(|c| c.to_string()).collect(),
+            include_unchanged: true,
+        }
+    }
+
+    #[test]
+    fn sniffs_tabs_and_semicolons() {
+        let table = parse(b"name\tnote\nx\t1,5\ny\ta, b, c\n");
+        assert_eq!(table.dialect.delimiter, '\t');
+        assert_eq!(table.headers, ["name", "note"]);
+        assert_eq!(cells(&table), [["x", "1,5"], ["y", "a, b, c"]]);
+
+        let table = parse(b"name;price\nx;1,5\ny;2,0\n");
+        assert_eq!(table.dialect.delimiter, ';');
+        assert_eq!(cells(&table), [["x", "1,5"], ["y", "2,0"]]);
+
+        let table = parse(b"single\nx\n");
+        assert_eq!(table.dialect.delimiter, ',');
+        assert_eq!(cells(&table), [["x"]]);
+    }
+
+    #[test]
+    fn quoted_fields_hold_delimiters_quotes_and_line_breaks() {
+        let table = parse(b"id,note\r\n1,\"a, b\"\r\n2,\"say \"\"hi\"\"\r\nthere\"\r\n\r\n3,x\r\n");
+        assert_eq!(table.dialect.quote, '"');
+        assert_eq!(
+            cells(&table),
+            [["1", "a, b"], ["2", "say \"hi\"\r\nthere"], ["3", "x"]]
+        );
+        let lines: Vec<usize> = table.rows.iter().map(|row| row.line).collect();
+        assert_eq!(lines, [2, 3, 6]);
+
+        let table = parse(b"id;note\n1;'it''s; here'\n2;'x'\n");
+        assert_eq!((table.dialect.delimiter, table.dialect.quote), (';', '\''));
+        assert_eq!(cells(&table), [["1", "it's; here"], ["2", "x"]]);
+    }
+
+    #[test]
+    fn decodes_utf16_with_and_without_bom() {
+        let utf16 = |bom: &[u8], text: &str| {
+            let mut bytes = bom.to_vec();
+            bytes.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+            bytes
+        };
+        let text = "name\tcity\nJosé\tZürich\n";
+        let table = parse(&utf16(&[0xFF, 0xFE], text));
+        assert_eq!(table.dialect.encoding, "UTF-16LE");
+        assert!(table.dialect.bom);
+        assert_eq!(table.dialect.delimiter, '\t');
+        assert_eq!(table.headers, ["name", "city"]);
+        assert_eq!(cells(&table), [["José", "Zürich"]]);
+
+        let table = parse(&utf16(&[], text));
+        assert_eq!(table.dialect.encoding, "UTF-16LE");
+        assert!(!table.dialect.bom);
+        assert_eq!(cells(&table), [["José", "Zürich"]]);
+
+        let table = parse(b"\xEF\xBB\xBFid,name\n1,x\n");
+        assert_eq!(
+            (table.dialect.encoding.as_str(), table.dialect.bom),
+            ("UTF-8", true)
+        );
+        assert_eq!(table.headers, ["id", "name"]);
+
+        let table = parse(b"id,name\n1,Jos\xE9\n");
+        assert_eq!(table.dialect.encoding, "windows-1252");
+        assert_eq!(cells(&table), [["1", "José"]]);
+    }
+
+    #[test]
+    fn duplicate_keys_pair_in_order() {
+        let left = parse(b"id,v\n1,a\n1,b\n2,c\n3,d\n");
+        let right = parse(b"id,v\n1,a\n1,B\n2,c\n2,e\n");
+        let diff = diff_tables(&left, &right, &keyed(&["id"])).unwrap();
+        assert_eq!(diff.duplicate_keys, ["1", "2"]);
+        let rows: Vec<(&str, DiffType)> = diff
+            .rows
+            .iter()
+            .map(|row| (row.key.as_str(), row.status))
+            .collect();
+        assert_eq!(
+            rows,
+            [
+                ("1", DiffType::Unchanged),
+                ("1", DiffType::Changed),
+                ("2", DiffType::Unchanged),
+                ("2", DiffType::Added),
+                ("3", DiffType::Removed),
+            ]
+        );
+        assert_eq!(diff.rows[1].changed, [1]);
+        assert_eq!(
+            (
+                diff.stats.added,
+                diff.stats.removed,
+                diff.stats.changed,
+                diff.stats.unchanged
+            ),
+            (1, 1, 1, 2)
+        );
+    }
+
+    #[test]
+    fn compound_keys_and_missing_key_columns() {
+        let left = parse(b"a,b,v\n1,x,old\n1,y,same\n");
+        let right = parse(b"b,a,v,extra\ny,1,same,n\nx,1,new,n\n");
+        let diff = diff_tables(&left, &right, &keyed(&["a", "b"])).unwrap();
+        assert!(diff.duplicate_keys.is_empty());
+        let keys: Vec<&str> = diff.rows.iter().map(|row| row.key.as_str()).collect();
+        assert_eq!(keys, ["1 / y", "1 / x"]);
+        assert_eq!(diff.rows[1].status, DiffType::Changed);
+        assert_eq!(diff.columns[3].status, DiffType::Added);
+        // Cells of a column only one side has aren't changes.
+        assert_eq!(diff.rows[0].status, DiffType::Unchanged);
+
+        let err = diff_tables(&left, &right, &keyed(&["extra"]))
+            .err()
+            .unwrap();
+        assert_eq!(err, "the left file has no column `extra`");
+    }
+}
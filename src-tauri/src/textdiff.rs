@@ -0,0 +1,382 @@
+use std::borrow::Cow;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use similar::{Algorithm, DiffOp, DiffTag};
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct TextDiffOptions {
+    pub algorithm: LineAlgorithm,
+    /// Lines that only differ in whitespace count as equal.
+    pub ignore_whitespace: bool,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineAlgorithm {
+    #[default]
+    Myers,
+    /// Anchors on lines that occur once on each side, which keeps moved
+    /// blocks and reformatted code readable.
+    Patience,
+}
+
+/// Same vocabulary as `DiffType`; a changed row pairs a removed with an
+/// added line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RowKind {
+    Added,
+    Removed,
+    Changed,
+    Unchanged,
+}
+
+/// A run of text within a line, flagged when it differs from the other side.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Span {
+    pub text: String,
+    pub changed: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextLine {
+    /// 1-based line number in its side.
+    pub number: usize,
+    pub text: String,
+    /// Word-level highlights, only on changed rows.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<Span>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextRow {
+    pub kind: RowKind,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub left: Option<TextLine>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub right: Option<TextLine>,
+}
+
+/// Side-by-side rows of a line diff.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TextDiff {
+    pub rows: Vec<TextRow>,
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+}
+
+/// How long the line diff may search for a minimal result before settling
+/// for a coarser one, so huge unrelated files don't hang the command.
+const DIFF_DEADLINE: Duration = Duration::from_secs(5);
+
+/// Diffs two texts line by line. Within a run of replaced lines, lines are
+/// paired in order and their differing words highlighted; the surplus on
+/// either side is reported as removed or added.
+pub fn diff_lines(left: &str, right: &str, options: &TextDiffOptions) -> TextDiff {
+    let left_lines: Vec<&str> = left.lines().collect();
+    let right_lines: Vec<&str> = right.lines().collect();
+    let ops = line_ops(&left_lines, &right_lines, options);
+
+    let line = |lines: &[&str], idx: usize| TextLine {
+        number: idx + 1,
+        text: lines[idx].to_string(),
+        spans: Vec::new(),
+    };
+    let mut diff = TextDiff::default();
+    for op in ops {
+        let (tag, old, new) = op.as_tag_tuple();
+        let paired = match tag {
+            DiffTag::Equal | DiffTag::Replace => old.len().min(new.len()),
+            _ => 0,
+        };
+        for (l, r) in old.clone().zip(new.clone()).take(paired) {
+            let (mut left, mut right) = (line(&left_lines, l), line(&right_lines, r));
+            let kind = if tag == DiffTag::Equal {
+                RowKind::Unchanged
+            } else {
+                (left.spans, right.spans) = word_spans(&left.text, &right.text);
+                diff.changed += 1;
+                RowKind::Changed
+            };
+            diff.rows.push(TextRow {
+                kind,
+                left: Some(left),
+                right: Some(right),
+            });
+        }
+        for l in old.skip(paired) {
+            diff.removed += 1;
+            diff.rows.push(TextRow {
+                kind: RowKind::Removed,
+                left: Some(line(&left_lines, l)),
+                right: None,
+            });
+        }
+        for r in new.skip(paired) {
+            diff.added += 1;
+            diff.rows.push(TextRow {
+                kind: RowKind::Added,
+                left: None,
+                right: Some(line(&right_lines, r)),
+            });
+        }
+    }
+    diff
+}
+
+/// Line-level edit script between two texts split into lines.
+pub fn line_ops(
+    left_lines: &[&str],
+    right_lines: &[&str],
+    options: &TextDiffOptions,
+) -> Vec<DiffOp> {
+    let ignore_whitespace = options.ignore_whitespace;
+    let left_keys: Vec<Cow<str>> = left_lines
+        .iter()
+        .map(|line| line_key(line, ignore_whitespace))
+        .collect();
+    let right_keys: Vec<Cow<str>> = right_lines
+        .iter()
+        .map(|line| line_key(line, ignore_whitespace))
+        .collect();
+    let algorithm = match options.algorithm {
+        LineAlgorithm::Myers => Algorithm::Myers,
+        LineAlgorithm::Patience => Algorithm::Patience,
+    };
+    similar::capture_diff_slices_deadline(
+        algorithm,
+        &left_keys,
+        &right_keys,
+        Some(Instant::now() + DIFF_DEADLINE),
+    )
+}
+
+/// What a line is compared by.
+fn line_key(line: &str, ignore_whitespace: bool) -> Cow<'_, str> {
+    if ignore_whitespace {
+        Cow::Owned(line.split_whitespace().collect::<Vec<_>>().join(" "))
+    } else {
+        Cow::Borrowed(line)
+    }
+}
+
+/// Splits two versions of a line into spans, marking the words that differ.
+pub fn word_spans(left: &str, right: &str) -> (Vec<Span>, Vec<Span>) {
+    let left_words = words(left);
+    let right_words = words(right);
+    let mut left_spans = Vec::new();
+    let mut right_spans = Vec::new();
+    for op in similar::capture_diff_slices(Algorithm::Myers, &left_words, &right_words) {
+        let (tag, old, new) = op.as_tag_tuple();
+        let changed = tag != DiffTag::Equal;
+        push_span(&mut left_spans, left_words[old].concat(), changed);
+        push_span(&mut right_spans, right_words[new].concat(), changed);
+    }
+    (left_spans, right_spans)
+}
+
+fn push_span(spans: &mut Vec<Span>, text: String, changed: bool) {
+    if text.is_empty() {
+        return;
+    }
+    match spans.last_mut() {
+        Some(last) if last.changed == changed => last.text.push_str(&text),
+        _ => spans.push(Span { text, changed }),
+    }
+}
+
+/// Splits a line into words, whitespace runs and single punctuation marks.
+fn words(line: &str) -> Vec<&str> {
+    #[derive(PartialEq)]
+    enum Class {
+        Word,
+        Space,
+        Other,
+    }
+    let class = |c: char| {
+        if c.is_alphanumeric() || c == '_' {
+            Class::Word
+        } else if c.is_whitespace() {
+            Class::Space
+        } else {
+            Class::Other
+        }
+    };
+    let mut words = Vec::new();
+    let mut start = 0;
+    let mut current: Option<Class> = None;
+    for (idx, c) in line.char_indices() {
+        let next = class(c);
+        let split = match &current {
+            Some(Class::Other) => true,
+            Some(prev) => *prev != next,
+            None => false,
+        };
+        if split {
+            words.push(&line[start..idx]);
+            start = idx;
+        }
+        current = Some(next);
+    }
+    if start < line.len() {
+        words.push(&line[start..]);
+    }
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn kinds(diff: &TextDiff) -> Vec<RowKind> {
+        diff.rows.iter().map(|row| row.kind).collect()
+    }
+
+    fn numbers(row: &TextRow) -> (Option<usize>, Option<usize>) {
+        (
+            row.left.as_ref().map(|line| line.number),
+            row.right.as_ref().map(|line| line.number),
+        )
+    }
+
+    fn spans(spans: &[Span]) -> Vec<(&str, bool)> {
+        spans
+            .iter()
+            .map(|span| (span.text.as_str(), span.changed))
+            .collect()
+    }
+
+    #[test]
+    fn patience_keeps_a_moved_block_whole() {
+        use RowKind::*;
+
+        let left = "fn a() {\n    one();\n}\n\nfn b() {\n    two();\n}\n";
+        let right = "fn b() {\n    two();\n}\n\nfn a() {\n    one();\n}\n";
+
+        // Myers pairs `fn a`'s body with the last closing brace, splitting
+        // `fn b` around it.
+        let myers = diff_lines(left, right, &TextDiffOptions::default());
+        assert_eq!(
+            kinds(&myers),
+            [
+                Added, Added, Added, Added, Unchanged, Unchanged, Removed, Removed, Removed,
+                Removed, Unchanged
+            ]
+        );
+        assert_eq!(numbers(&myers.rows[10]), (Some(7), Some(7)));
+
+        let options = TextDiffOptions {
+            algorithm: LineAlgorithm::Patience,
+            ..TextDiffOptions::default()
+        };
+        let patience = diff_lines(left, right, &options);
+        assert_eq!(
+            kinds(&patience),
+            [
+                Added, Added, Added, Added, Unchanged, Unchanged, Unchanged, Removed, Removed,
+                Removed, Removed
+            ]
+        );
+        assert_eq!(numbers(&patience.rows[6]), (Some(3), Some(7)));
+        assert_eq!((patience.added, patience.removed), (4, 4));
+    }
+
+    #[test]
+    fn ignore_whitespace_compares_collapsed_lines() {
+        use RowKind::*;
+
+        let left = "a  b\n\tc\nd\n";
+        let right = "a b\nc  \ne\n";
+        let diff = diff_lines(left, right, &TextDiffOptions::default());
+        assert_eq!(diff.changed, 3);
+
+        let options = TextDiffOptions {
+            ignore_whitespace: true,
+            ..TextDiffOptions::default()
+        };
+        let diff = diff_lines(left, right, &options);
+        assert_eq!(kinds(&diff), [Unchanged, Unchanged, Changed]);
+        // Rows still show each side as written.
+        let row = &diff.rows[1];
+        assert_eq!(row.left.as_ref().unwrap().text, "\tc");
+        assert_eq!(row.right.as_ref().unwrap().text, "c  ");
+        // Whitespace inside a word still splits it.
+        let diff = diff_lines("ab\n", "a b\n", &options);
+        assert_eq!(kinds(&diff), [Changed]);
+    }
+
+    #[test]
+    fn replaced_runs_pair_in_order_and_report_the_surplus() {
+        use RowKind::*;
+
+        let diff = diff_lines(
+            "keep\nx1\ny1\nz1\nend\n",
+            "keep\nx2\ny2\nend\n",
+            &TextDiffOptions::default(),
+        );
+        assert_eq!(
+            kinds(&diff),
+            [Unchanged, Changed, Changed, Removed, Unchanged]
+        );
+        assert_eq!(numbers(&diff.rows[2]), (Some(3), Some(3)));
+        assert_eq!(numbers(&diff.rows[3]), (Some(4), None));
+        assert_eq!(numbers(&diff.rows[4]), (Some(5), Some(4)));
+        assert_eq!((diff.added, diff.removed, diff.changed), (0, 1, 2));
+        assert!(diff.rows[3].left.as_ref().unwrap().spans.is_empty());
+
+        let diff = diff_lines(
+            "keep\nx1\nend\n",
+            "keep\nx2\ny2\nz2\nend\n",
+            &TextDiffOptions::default(),
+        );
+        assert_eq!(kinds(&diff), [Unchanged, Changed, Added, Added, Unchanged]);
+        assert_eq!(numbers(&diff.rows[2]), (None, Some(3)));
+        assert_eq!((diff.added, diff.removed, diff.changed), (2, 0, 1));
+    }
+
+    #[test]
+    fn word_spans_split_punctuation_marks() {
+        let (left, right) = word_spans("call(a, b);", "call(a, c);");
+        assert_eq!(
+            spans(&left),
+            [("call(a, ", false), ("b", true), (");", false)]
+        );
+        assert_eq!(
+            spans(&right),
+            [("call(a, ", false), ("c", true), (");", false)]
+        );
+        // Each mark is its own token, so `--` and `-+` share the first.
+        let (left, right) = word_spans("a--b", "a-+b");
+        assert_eq!(spans(&left), [("a-", false), ("-", true), ("b", false)]);
+        assert_eq!(spans(&right), [("a-", false), ("+", true), ("b", false)]);
+    }
+
+    #[test]
+    fn word_spans_keep_multibyte_text_whole() {
+        let (left, right) = word_spans("价格: 10€ naïve", "价格: 12€ naïf");
+        assert_eq!(
+            spans(&left),
+            [
+                ("价格: ", false),
+                ("10", true),
+                ("€ ", false),
+                ("naïve", true)
+            ]
+        );
+        assert_eq!(
+            spans(&right),
+            [
+                ("价格: ", false),
+                ("12", true),
+                ("€ ", false),
+                ("naïf", true)
+            ]
+        );
+    }
+}
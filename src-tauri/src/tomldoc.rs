@@ -0,0 +1,491 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use serde_json::{Map, Number, Value};
+use toml_edit::{
+    Array, ArrayOfTables, Datetime, Decor, DocumentMut, ImDocument, InlineTable, Item, RawString,
+    Table, TomlError,
+};
+
+use crate::document::ParseError;
+use crate::source::{LineIndex, LineRange};
+
+/// Parses TOML into the same value model as JSON and YAML. Tables keep their
+/// key order; dates and times become their TOML text, e.g.
+/// `1979-05-27T07:32:00Z`, so a changed offset or precision shows up in the
+/// diff. `nan` and `inf` have no JSON number and become strings too.
+pub fn parse(text: &str) -> Result<Value, ParseError> {
+    let document = ImDocument::parse(text).map_err(|e| parse_error(text, &e))?;
+    Ok(table_value(document.as_table()))
+}
+
+/// Maps each display path to the source lines it spans. A table's lines run
+/// from its header to its last key, wherever its subtables were written.
+pub fn line_map(text: &str) -> Result<HashMap<String, LineRange>, ParseError> {
+    let document = ImDocument::parse(text).map_err(|e| parse_error(text, &e))?;
+    let mut spans = HashMap::new();
+    if let Some(range) = table_spans(document.as_table(), "", &mut spans) {
+        spans.insert(String::new(), range);
+    }
+    let lines = LineIndex::new(text);
+    Ok(spans
+        .into_iter()
+        .map(|(path, range)| {
+            let range = LineRange {
+                start: lines.line_of(range.start),
+                end: lines.line_of(range.end.saturating_sub(1).max(range.start)),
+            };
+            (path, range)
+        })
+        .collect())
+}
+
+/// Tidies whitespace without changing how the document is laid out:
+/// `[table]` headers, dotted keys and inline tables stay what they are,
+/// values keep their literal form (datetimes, hex integers, string quoting),
+/// and comments are kept. Arrays with comments inside are left untouched;
+/// others are put on one line.
+pub fn format(text: &str) -> Result<String, ParseError> {
+    let mut document: DocumentMut = text.parse().map_err(|e| parse_error(text, &e))?;
+    tidy_table(document.as_table_mut(), true);
+    let trailing = comments(raw(Some(document.trailing())));
+    document.set_trailing(trailing);
+    let formatted = document.to_string();
+    Ok(format!("{}\n", formatted.trim_matches('\n')))
+}
+
+/// Writes `value` as TOML. Objects become `[table]`s and arrays of objects
+/// `[[array]]`s, except inside other arrays where they have to be inline.
+/// Strings that read as a TOML date or time are written as one, which is how
+/// [`parse`] represents them. TOML has no null, so documents holding one are
+/// rejected, as are numbers a TOML integer or float can't hold.
+pub fn to_string(value: &Value) -> Result<String, String> {
+    let Value::Object(map) = value else {
+        return Err("a TOML document must be a table".to_string());
+    };
+    let mut document = DocumentMut::new();
+    fill_table(document.as_table_mut(), map, "")?;
+    Ok(document.to_string())
+}
+
+fn parse_error(text: &str, error: &TomlError) -> ParseError {
+    let message = error.message().trim();
+    match error.span() {
+        Some(span) => ParseError::at(&message, text, span.start),
+        None => ParseError::new(message),
+    }
+}
+
+fn table_value(table: &Table) -> Value {
+    Value::Object(
+        table
+            .iter()
+            .filter_map(|(key, item)| Some((key.to_string(), item_value(item)?)))
+            .collect(),
+    )
+}
+
+fn item_value(item: &Item) -> Option<Value> {
+    match item {
+        Item::None => None,
+        Item::Value(value) => Some(value_of(value)),
+        Item::Table(table) => Some(table_value(table)),
+        Item::ArrayOfTables(array) => Some(Value::Array(array.iter().map(table_value).collect())),
+    }
+}
+
+fn value_of(value: &toml_edit::Value) -> Value {
+    match value {
+        toml_edit::Value::String(s) => Value::String(s.value().clone()),
+        toml_edit::Value::Integer(i) => Value::from(*i.value()),
+        toml_edit::Value::Float(f) => {
+            let f = *f.value();
+            Number::from_f64(f).map_or_else(|| Value::String(float_name(f)), Value::Number)
+        }
+        toml_edit::Value::Boolean(b) => Value::Bool(*b.value()),
+        toml_edit::Value::Datetime(dt) => Value::String(dt.value().to_string()),
+        toml_edit::Value::Array(array) => Value::Array(array.iter().map(value_of).collect()),
+        toml_edit::Value::InlineTable(table) => Value::Object(
+            table
+                .iter()
+                .map(|(key, value)| (key.to_string(), value_of(value)))
+                .collect(),
+        ),
+    }
+}
+
+fn float_name(f: f64) -> String {
+    match f {
+        f if f.is_nan() => "nan".to_string(),
+        f if f > 0.0 => "inf".to_string(),
+        _ => "-inf".to_string(),
+    }
+}
+
+fn child(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn union(a: Option<Range<usize>>, b: Option<Range<usize>>) -> Option<Range<usize>> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.start.min(b.start)..a.end.max(b.end)),
+        (a, b) => a.or(b),
+    }
+}
+
+/// Records the span of every entry below `table` and returns the span of
+/// the table itself: its header, if written, through its last entry.
+fn table_spans(
+    table: &Table,
+    path: &str,
+    spans: &mut HashMap<String, Range<usize>>,
+) -> Option<Range<usize>> {
+    let mut range = table.span();
+    for (key, item) in table.iter() {
+        let child = child(path, key);
+        let own = match item {
+            Item::None => None,
+            Item::Value(value) => value_spans(value, &child, spans),
+            Item::Table(table) => table_spans(table, &child, spans),
+            Item::ArrayOfTables(array) => array_of_tables_spans(array, &child, spans),
+        };
+        let key_span = table.key(key).and_then(|k| k.span());
+        // A `[header]`'s key lies inside the header; a `key = value`'s starts
+        // the entry.
+        let entry = union(own, key_span);
+        if let Some(entry) = &entry {
+            spans.insert(child, entry.clone());
+        }
+        range = union(range, entry);
+    }
+    range
+}
+
+fn array_of_tables_spans(
+    array: &ArrayOfTables,
+    path: &str,
+    spans: &mut HashMap<String, Range<usize>>,
+) -> Option<Range<usize>> {
+    let mut range = None;
+    for (idx, table) in array.iter().enumerate() {
+        let child = format!("{path}[{idx}]");
+        if let Some(own) = table_spans(table, &child, spans) {
+            spans.insert(child, own.clone());
+            range = union(range, Some(own));
+        }
+    }
+    range
+}
+
+fn value_spans(
+    value: &toml_edit::Value,
+    path: &str,
+    spans: &mut HashMap<String, Range<usize>>,
+) -> Option<Range<usize>> {
+    match value {
+        toml_edit::Value::Array(array) => {
+            for (idx, element) in array.iter().enumerate() {
+                let child = format!("{path}[{idx}]");
+                if let Some(own) = value_spans(element, &child, spans) {
+                    spans.insert(child, own);
+                }
+            }
+        }
+        toml_edit::Value::InlineTable(table) => {
+            for (key, element) in table.iter() {
+                let child = child(path, key);
+                let own = value_spans(element, &child, spans);
+                let key_span = table.key(key).and_then(|k| k.span());
+                if let Some(entry) = union(own, key_span) {
+                    spans.insert(child, entry);
+                }
+            }
+        }
+        _ => {}
+    }
+    value.span()
+}
+
+fn raw(raw: Option<&RawString>) -> &str {
+    raw.and_then(RawString::as_str).unwrap_or_default()
+}
+
+/// The comment lines of the whitespace before a key or header, one per
+/// line, with a single blank line wherever the original had blank lines.
+fn comments(raw: &str) -> String {
+    let mut lines: Vec<&str> = raw.split('\n').collect();
+    // After the last newline comes the indentation of the key itself.
+    lines.pop();
+    let mut out = String::new();
+    let mut blank = false;
+    for line in lines.iter().map(|l| l.trim()) {
+        if line.is_empty() {
+            blank = true;
+            continue;
+        }
+        if blank {
+            out.push('\n');
+            blank = false;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    if blank {
+        out.push('\n');
+    }
+    out
+}
+
+/// A comment after a value or header on the same line, one space away.
+fn trailing_comment(raw: &str) -> String {
+    let comment = raw.trim();
+    if comment.is_empty() {
+        String::new()
+    } else {
+        format!(" {comment}")
+    }
+}
+
+fn tidy_decor(decor: &mut Decor, prefix: String, suffix: String) {
+    *decor = Decor::new(prefix, suffix);
+}
+
+fn tidy_table(table: &mut Table, root: bool) {
+    if !root && !table.is_dotted() && !table.is_implicit() {
+        let decor = table.decor_mut();
+        // Every header gets one blank line above it and its comments.
+        let prefix = format!(
+            "\n{}",
+            comments(raw(decor.prefix())).trim_start_matches('\n')
+        );
+        let suffix = trailing_comment(raw(decor.suffix()));
+        tidy_decor(decor, prefix, suffix);
+    }
+    let dotted = table.is_dotted();
+    for (mut key, item) in table.iter_mut() {
+        match item {
+            Item::None => {}
+            Item::Value(value) => {
+                let decor = key.leaf_decor_mut();
+                let prefix = if dotted {
+                    String::new()
+                } else {
+                    comments(raw(decor.prefix()))
+                };
+                tidy_decor(decor, prefix, " ".to_string());
+                tidy_value(value);
+                let decor = value.decor_mut();
+                let suffix = trailing_comment(raw(decor.suffix()));
+                tidy_decor(decor, " ".to_string(), suffix);
+            }
+            Item::Table(child) => {
+                if child.is_dotted() {
+                    let decor = key.leaf_decor_mut();
+                    let prefix = if dotted {
+                        String::new()
+                    } else {
+                        comments(raw(decor.prefix()))
+                    };
+                    tidy_decor(decor, prefix, String::new());
+                    key.dotted_decor_mut().clear();
+                } else {
+                    key.leaf_decor_mut().clear();
+                }
+                tidy_table(child, false);
+            }
+            Item::ArrayOfTables(array) => {
+                key.leaf_decor_mut().clear();
+                for table in array.iter_mut() {
+                    tidy_table(table, false);
+                }
+            }
+        }
+    }
+}
+
+fn tidy_value(value: &mut toml_edit::Value) {
+    match value {
+        toml_edit::Value::Array(array) if !has_comments(array) => {
+            for element in array.iter_mut() {
+                tidy_value(element);
+            }
+            array.fmt();
+        }
+        toml_edit::Value::InlineTable(table) => {
+            for (_, element) in table.iter_mut() {
+                tidy_value(element);
+            }
+            table.fmt();
+        }
+        _ => {}
+    }
+}
+
+fn has_comments(array: &Array) -> bool {
+    let decor_has =
+        |decor: &Decor| raw(decor.prefix()).contains('#') || raw(decor.suffix()).contains('#');
+    raw(Some(array.trailing())).contains('#')
+        || array.iter().any(|value| {
+            decor_has(value.decor())
+                || matches!(value, toml_edit::Value::Array(inner) if has_comments(inner))
+        })
+}
+
+fn fill_table(table: &mut Table, map: &Map<String, Value>, path: &str) -> Result<(), String> {
+    for (key, value) in map {
+        let child = child(path, key);
+        let item = match value {
+            Value::Object(inner) => {
+                let mut sub = Table::new();
+                fill_table(&mut sub, inner, &child)?;
+                Item::Table(sub)
+            }
+            Value::Array(elements)
+                if !elements.is_empty() && elements.iter().all(Value::is_object) =>
+            {
+                let mut array = ArrayOfTables::new();
+                for (idx, element) in elements.iter().enumerate() {
+                    let mut sub = Table::new();
+                    if let Value::Object(inner) = element {
+                        fill_table(&mut sub, inner, &format!("{child}[{idx}]"))?;
+                    }
+                    array.push(sub);
+                }
+                Item::ArrayOfTables(array)
+            }
+            _ => Item::Value(toml_value(value, &child)?),
+        };
+        table.insert(key, item);
+    }
+    Ok(())
+}
+
+fn toml_value(value: &Value, path: &str) -> Result<toml_edit::Value, String> {
+    Ok(match value {
+        Value::Null => return Err(format!("TOML has no null (at {path})")),
+        Value::Bool(b) => (*b).into(),
+        Value::Number(n) => toml_number(n, path)?,
+        Value::String(s) => match s.parse::<Datetime>() {
+            Ok(datetime) => datetime.into(),
+            Err(_) => s.as_str().into(),
+        },
+        Value::Array(elements) => {
+            let mut array = Array::new();
+            for (idx, element) in elements.iter().enumerate() {
+                array.push(toml_value(element, &format!("{path}[{idx}]"))?);
+            }
+            toml_edit::Value::Array(array)
+        }
+        Value::Object(map) => {
+            let mut table = InlineTable::new();
+            for (key, element) in map {
+                table.insert(key, toml_value(element, &child(path, key))?);
+            }
+            toml_edit::Value::InlineTable(table)
+        }
+    })
+}
+
+/// TOML integers are signed 64-bit and floats are doubles. Anything beyond
+/// them would be written as a different number.
+fn toml_number(n: &Number, path: &str) -> Result<toml_edit::Value, String> {
+    if let Some(i) = n.as_i64() {
+        return Ok(i.into());
+    }
+    if !n.as_str().contains(['.', 'e', 'E']) {
+        return Err(format!("{n} is too large for a TOML integer (at {path})"));
+    }
+    match n.as_f64().filter(|f| f.is_finite()) {
+        Some(f) => Ok(f.into()),
+        None => Err(format!("{n} is out of range for a TOML float (at {path})")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn lines(map: &HashMap<String, LineRange>, path: &str) -> Option<(usize, usize)> {
+        map.get(path).map(|range| (range.start, range.end))
+    }
+
+    #[test]
+    fn format_keeps_comments_and_layout() {
+        let text = "# config\ntitle   =   \"x\"   # the title\n\n[server]\n  host=\"a\"\n# port below\nport = 0x50\nlist = [ 1,2 ,\n 3 ]\nkept = [\n  1, # one\n  2,\n]\n\n\n[server.tls]\nwhen = 1979-05-27T07:32:00Z\n# trailing\n";
+        let formatted = format(text).unwrap();
+        assert_eq!(
+            formatted,
+            "# config\ntitle = \"x\" # the title\n\n[server]\nhost = \"a\"\n# port below\nport = 0x50\nlist = [1, 2, 3]\nkept = [\n  1, # one\n  2,\n]\n\n[server.tls]\nwhen = 1979-05-27T07:32:00Z\n# trailing\n"
+        );
+        assert_eq!(format(&formatted).unwrap(), formatted);
+        assert_eq!(parse(&formatted).unwrap(), parse(text).unwrap());
+    }
+
+    #[test]
+    fn parse_keeps_datetime_text() {
+        let value = parse(
+            "odt = 1979-05-27T07:32:00.999-08:00\nldt = 1979-05-27 07:32:00\nld = 1979-05-27\nlt = 07:32:00\nf = nan\ng = -inf\n",
+        )
+        .unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "odt": "1979-05-27T07:32:00.999-08:00",
+                "ldt": "1979-05-27T07:32:00",
+                "ld": "1979-05-27",
+                "lt": "07:32:00",
+                "f": "nan",
+                "g": "-inf",
+            })
+        );
+        // And are written back as datetimes.
+        let text = to_string(&json!({"when": "1979-05-27T07:32:00Z", "s": "not a date"})).unwrap();
+        assert_eq!(text, "when = 1979-05-27T07:32:00Z\ns = \"not a date\"\n");
+    }
+
+    #[test]
+    fn tables_span_from_header_to_last_key() {
+        let text = "title = \"x\"\n\n[server]\nhost = \"a\"\n# comment\nport = 80\n\n[other]\nk = 1\n\n[server.tls]\non = true\n";
+        let map = line_map(text).unwrap();
+        assert_eq!(lines(&map, "title"), Some((1, 1)));
+        assert_eq!(lines(&map, "server.port"), Some((6, 6)));
+        assert_eq!(lines(&map, "other"), Some((8, 9)));
+        assert_eq!(lines(&map, "server.tls"), Some((11, 12)));
+        // The subtable written after `[other]` is part of `[server]`.
+        assert_eq!(lines(&map, "server"), Some((3, 12)));
+        assert_eq!(lines(&map, ""), Some((1, 12)));
+
+        let map = line_map("[[item]]\nid = 1\n\n[[item]]\nid = 2\nname = \"b\"\n").unwrap();
+        assert_eq!(lines(&map, "item[0]"), Some((1, 2)));
+        assert_eq!(lines(&map, "item[1]"), Some((4, 6)));
+        assert_eq!(lines(&map, "item"), Some((1, 6)));
+    }
+
+    #[test]
+    fn numbers_a_toml_value_cant_hold_are_errors() {
+        let value: Value =
+            serde_json::from_str(r#"{"a": 9223372036854775807, "b": -1.5, "c": 2e0}"#).unwrap();
+        assert_eq!(
+            to_string(&value).unwrap(),
+            "a = 9223372036854775807\nb = -1.5\nc = 2.0\n"
+        );
+        for (json, error) in [
+            (
+                r#"{"a": 18446744073709551615}"#,
+                "18446744073709551615 is too large for a TOML integer (at a)",
+            ),
+            (
+                r#"{"t": {"b": [1e400]}}"#,
+                "1e+400 is out of range for a TOML float (at t.b[0])",
+            ),
+        ] {
+            let value: Value = serde_json::from_str(json).unwrap();
+            assert_eq!(to_string(&value).unwrap_err(), error);
+        }
+    }
+}
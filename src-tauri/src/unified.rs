@@ -0,0 +1,442 @@
+use std::fmt::Write;
+use std::ops::Range;
+
+use serde::Serialize;
+use similar::DiffTag;
+
+use crate::textdiff::{self, RowKind, TextDiffOptions, TextLine, TextRow};
+
+/// Lines of unchanged context around each hunk, as `git diff` writes.
+const CONTEXT_LINES: usize = 3;
+
+/// The changes to one file in a unified diff.
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FilePatch {
+    /// Path before the change; `None` for a created file.
+    pub old_path: Option<String>,
+    /// Path after the change; `None` for a deleted file.
+    pub new_path: Option<String>,
+    pub hunks: Vec<Hunk>,
+    pub added: usize,
+    pub removed: usize,
+    pub changed: usize,
+}
+
+/// A hunk as side-by-side rows, numbered from its `@@` header.
+#[derive(Debug, Clone, Serialize)]
+pub struct Hunk {
+    pub header: String,
+    pub rows: Vec<TextRow>,
+}
+
+/// Parses the output of `git diff`, `git format-patch` or `diff -u`. Text
+/// outside of file headers and hunks (commit messages, `index` lines) is
+/// skipped.
+pub fn parse(text: &str) -> Result<Vec<FilePatch>, String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let mut files = Vec::new();
+    let mut current: Option<FilePatch> = None;
+    let mut idx = 0;
+    while idx < lines.len() {
+        let line = lines[idx];
+        if let Some(paths) = line.strip_prefix("diff --git ") {
+            files.extend(current.take());
+            let (old_path, new_path) = git_header_paths(paths);
+            current = Some(FilePatch {
+                old_path,
+                new_path,
+                ..FilePatch::default()
+            });
+        } else if let (Some(old), Some(new)) = (
+            line.strip_prefix("--- "),
+            lines.get(idx + 1).and_then(|l| l.strip_prefix("+++ ")),
+        ) {
+            // A `diff --git` header is followed by its own `---`/`+++` pair;
+            // in plain `diff -u` output the pair starts the next file.
+            if current.as_ref().is_some_and(|f| !f.hunks.is_empty()) {
+                files.extend(current.take());
+            }
+            let file = current.get_or_insert_with(FilePatch::default);
+            file.old_path = header_path(old);
+            file.new_path = header_path(new);
+            idx += 2;
+            continue;
+        } else if line.starts_with("@@") {
+            let file = current.get_or_insert_with(FilePatch::default);
+            idx += parse_hunk(&lines[idx..], idx + 1, file)?;
+            continue;
+        } else if let Some(path) = line.strip_prefix("rename from ") {
+            if let Some(file) = &mut current {
+                file.old_path = Some(path.to_string());
+            }
+        } else if let Some(path) = line.strip_prefix("rename to ") {
+            if let Some(file) = &mut current {
+                file.new_path = Some(path.to_string());
+            }
+        }
+        idx += 1;
+    }
+    files.extend(current);
+    Ok(files)
+}
+
+/// Writes the line diff of `left` and `right` as a unified diff with git's
+/// `a/` and `b/` prefixes. Returns an empty string when the texts match.
+pub fn write(
+    left: &str,
+    right: &str,
+    left_name: &str,
+    right_name: &str,
+    options: &TextDiffOptions,
+) -> String {
+    let left_lines: Vec<&str> = left.lines().collect();
+    let right_lines: Vec<&str> = right.lines().collect();
+    // A last line without a newline differs from the same line with one.
+    let keys = |text: &str, lines: &[&str]| -> Vec<String> {
+        let mut keys: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        if let Some(last) = keys.last_mut().filter(|_| !text.ends_with('\n')) {
+            last.push_str("\n\\");
+        }
+        keys
+    };
+    let (left_keys, right_keys) = (keys(left, &left_lines), keys(right, &right_lines));
+    let ops = textdiff::line_ops(
+        &left_keys.iter().map(String::as_str).collect::<Vec<_>>(),
+        &right_keys.iter().map(String::as_str).collect::<Vec<_>>(),
+        options,
+    );
+    let groups = similar::group_diff_ops(ops, CONTEXT_LINES);
+    if groups.is_empty() {
+        return String::new();
+    }
+
+    let mut out = String::new();
+    let _ = writeln!(out, "--- a/{left_name}");
+    let _ = writeln!(out, "+++ b/{right_name}");
+    let line = |out: &mut String, prefix: char, lines: &[&str], idx: usize, text: &str| {
+        let _ = writeln!(out, "{prefix}{}", lines[idx]);
+        if idx + 1 == lines.len() && !text.ends_with('\n') {
+            out.push_str("\\ No newline at end of file\n");
+        }
+    };
+    for group in groups {
+        let (Some(first), Some(last)) = (group.first(), group.last()) else {
+            continue;
+        };
+        let old = first.old_range().start..last.old_range().end;
+        let new = first.new_range().start..last.new_range().end;
+        let _ = writeln!(out, "@@ -{} +{} @@", hunk_range(old), hunk_range(new));
+        for op in &group {
+            let (tag, old, new) = op.as_tag_tuple();
+            if tag == DiffTag::Equal {
+                old.for_each(|l| line(&mut out, ' ', &left_lines, l, left));
+                continue;
+            }
+            old.for_each(|l| line(&mut out, '-', &left_lines, l, left));
+            new.for_each(|r| line(&mut out, '+', &right_lines, r, right));
+        }
+    }
+    out
+}
+
+/// `start,len` of a hunk header. Git leaves out a length of one, and an
+/// empty range starts at the line before it.
+fn hunk_range(range: Range<usize>) -> String {
+    match range.len() {
+        0 => format!("{},0", range.start),
+        1 => format!("{}", range.start + 1),
+        len => format!("{},{len}", range.start + 1),
+    }
+}
+
+/// Paths from `diff --git a/old b/new`. Ambiguous when a path itself
+/// contains ` b/`; the `---`/`+++` lines that follow settle it.
+fn git_header_paths(paths: &str) -> (Option<String>, Option<String>) {
+    match paths.rfind(" b/") {
+        Some(split) => (
+            header_path(&paths[..split]),
+            header_path(&paths[split + 1..]),
+        ),
+        None => (None, None),
+    }
+}
+
+/// Path from a `---`/`+++` line: drops the timestamp `diff -u` appends and
+/// git's `a/`/`b/` prefix. `/dev/null` means the file doesn't exist.
+fn header_path(raw: &str) -> Option<String> {
+    let path = raw.split('\t').next().unwrap_or(raw).trim_end();
+    if path == "/dev/null" {
+        return None;
+    }
+    let path = path
+        .strip_prefix("a/")
+        .or_else(|| path.strip_prefix("b/"))
+        .unwrap_or(path);
+    Some(path.to_string())
+}
+
+/// Parses `-start,len +start,len` out of a hunk header.
+fn hunk_header(line: &str) -> Option<(usize, usize, usize, usize)> {
+    let ranges = line.strip_prefix("@@ ")?.split(" @@").next()?;
+    let (old, new) = ranges.split_once(' ')?;
+    let range = |r: &str| -> Option<(usize, usize)> {
+        match r.split_once(',') {
+            Some((start, len)) => Some((start.parse().ok()?, len.parse().ok()?)),
+            None => Some((r.parse().ok()?, 1)),
+        }
+    };
+    let (old_start, old_len) = range(old.strip_prefix('-')?)?;
+    let (new_start, new_len) = range(new.strip_prefix('+')?)?;
+    Some((old_start, old_len, new_start, new_len))
+}
+
+/// Parses the hunk starting at `lines[0]` (line `line_no` of the input)
+/// into `file`. Returns how many lines it took up.
+fn parse_hunk(lines: &[&str], line_no: usize, file: &mut FilePatch) -> Result<usize, String> {
+    let header = lines[0];
+    let (old_start, mut old_left, new_start, mut new_left) =
+        hunk_header(header).ok_or_else(|| format!("line {line_no}: invalid hunk header"))?;
+    let (mut old_no, mut new_no) = (old_start, new_start);
+
+    let mut rows = Vec::new();
+    let mut removed: Vec<TextLine> = Vec::new();
+    let mut added: Vec<TextLine> = Vec::new();
+    let text_line = |number: usize, text: &str| TextLine {
+        number,
+        text: text.to_string(),
+        spans: Vec::new(),
+    };
+
+    let mut idx = 1;
+    while old_left > 0 || new_left > 0 {
+        let Some(&line) = lines.get(idx) else {
+            return Err(format!("line {}: hunk ends early", line_no + idx));
+        };
+        let mut chars = line.chars();
+        let marker = chars.next();
+        let text = chars.as_str();
+        match marker {
+            // Editors often strip the single space of an empty context line.
+            Some(' ') | None if old_left > 0 && new_left > 0 => {
+                flush_change(&mut rows, &mut removed, &mut added, file);
+                rows.push(TextRow {
+                    kind: RowKind::Unchanged,
+                    left: Some(text_line(old_no, text)),
+                    right: Some(text_line(new_no, text)),
+                });
+                old_no += 1;
+                new_no += 1;
+                old_left -= 1;
+                new_left -= 1;
+            }
+            Some('-') if old_left > 0 => {
+                if !added.is_empty() {
+                    flush_change(&mut rows, &mut removed, &mut added, file);
+                }
+                removed.push(text_line(old_no, text));
+                old_no += 1;
+                old_left -= 1;
+            }
+            Some('+') if new_left > 0 => {
+                added.push(text_line(new_no, text));
+                new_no += 1;
+                new_left -= 1;
+            }
+            Some('\\') => {}
+            _ => {
+                return Err(format!(
+                    "line {}: unexpected '{line}' in hunk {header}",
+                    line_no + idx
+                ))
+            }
+        }
+        idx += 1;
+    }
+    flush_change(&mut rows, &mut removed, &mut added, file);
+    // A trailing "\ No newline at end of file" belongs to this hunk.
+    while lines.get(idx).is_some_and(|l| l.starts_with('\\')) {
+        idx += 1;
+    }
+    file.hunks.push(Hunk {
+        header: header.to_string(),
+        rows,
+    });
+    Ok(idx)
+}
+
+/// Turns a run of removed and added lines into rows: pairs in order with
+/// word highlights, then the surplus on either side.
+fn flush_change(
+    rows: &mut Vec<TextRow>,
+    removed: &mut Vec<TextLine>,
+    added: &mut Vec<TextLine>,
+    file: &mut FilePatch,
+) {
+    let mut added = added.drain(..);
+    for mut left in removed.drain(..) {
+        match added.next() {
+            Some(mut right) => {
+                (left.spans, right.spans) = textdiff::word_spans(&left.text, &right.text);
+                file.changed += 1;
+                rows.push(TextRow {
+                    kind: RowKind::Changed,
+                    left: Some(left),
+                    right: Some(right),
+                });
+            }
+            None => {
+                file.removed += 1;
+                rows.push(TextRow {
+                    kind: RowKind::Removed,
+                    left: Some(left),
+                    right: None,
+                });
+            }
+        }
+    }
+    for right in added {
+        file.added += 1;
+        rows.push(TextRow {
+            kind: RowKind::Added,
+            left: None,
+            right: Some(right),
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The old and new text a file's hunks cover, line by line.
+    fn sides(file: &FilePatch) -> (Vec<String>, Vec<String>) {
+        let rows = file.hunks.iter().flat_map(|hunk| &hunk.rows);
+        let texts = |side: fn(&TextRow) -> &Option<TextLine>| {
+            rows.clone()
+                .filter_map(|row| side(row).as_ref())
+                .map(|line| line.text.clone())
+                .collect()
+        };
+        (texts(|row| &row.left), texts(|row| &row.right))
+    }
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn written_diffs_parse_back() {
+        let left = "a\nb\nc\nd\n";
+        let right = "a\nB\nc\nd\ne\n";
+        let patch = write(left, right, "x.txt", "x.txt", &TextDiffOptions::default());
+        assert_eq!(
+            patch,
+            "--- a/x.txt\n+++ b/x.txt\n@@ -1,4 +1,5 @@\n a\n-b\n+B\n c\n d\n+e\n"
+        );
+        let files = parse(&patch).unwrap();
+        assert_eq!(files.len(), 1);
+        let file = &files[0];
+        assert_eq!(file.old_path.as_deref(), Some("x.txt"));
+        assert_eq!((file.added, file.removed, file.changed), (1, 0, 1));
+        assert_eq!(sides(file), (lines(left), lines(right)));
+        let numbers: Vec<_> = file.hunks[0]
+            .rows
+            .iter()
+            .map(|row| row.right.as_ref().map(|l| l.number))
+            .collect();
+        assert_eq!(numbers, [Some(1), Some(2), Some(3), Some(4), Some(5)]);
+        assert_eq!(write(left, left, "x", "x", &TextDiffOptions::default()), "");
+    }
+
+    #[test]
+    fn missing_newline_at_end() {
+        let patch = write("a\nb", "a\nb\n", "f", "f", &TextDiffOptions::default());
+        assert_eq!(
+            patch,
+            "--- a/f\n+++ b/f\n@@ -1,2 +1,2 @@\n a\n-b\n\\ No newline at end of file\n+b\n"
+        );
+        let files = parse(&patch).unwrap();
+        assert_eq!(sides(&files[0]), (lines("a\nb"), lines("a\nb")));
+        assert_eq!(files[0].changed, 1);
+
+        // The marker after the last line of a hunk belongs to it.
+        let patch = "--- a/f\n+++ b/f\n@@ -1 +1 @@\n-x\n+y\n\\ No newline at end of file\n@@ -5 +5 @@\n-p\n+q\n";
+        let files = parse(patch).unwrap();
+        assert_eq!(files[0].hunks.len(), 2);
+    }
+
+    #[test]
+    fn multiple_files_and_dev_null() {
+        let patch = "\
+From 1234 Mon Sep 17 00:00:00 2001
+Subject: [PATCH] Touch files
+
+diff --git a/new.txt b/new.txt
+new file mode 100644
+index 0000000..e69de29
+--- /dev/null
++++ b/new.txt
+@@ -0,0 +1,2 @@
++one
++two
+diff --git a/old.txt b/old.txt
+deleted file mode 100644
+--- a/old.txt
++++ /dev/null
+@@ -1 +0,0 @@
+-gone
+diff --git a/a.txt b/b.txt
+similarity index 90%
+rename from a.txt
+rename to b.txt
+--- a/a.txt
++++ b/b.txt
+@@ -1,2 +1,2 @@
+ same
+-old
++new
+";
+        let files = parse(patch).unwrap();
+        let paths: Vec<_> = files
+            .iter()
+            .map(|f| (f.old_path.as_deref(), f.new_path.as_deref()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                (None, Some("new.txt")),
+                (Some("old.txt"), None),
+                (Some("a.txt"), Some("b.txt"))
+            ]
+        );
+        assert_eq!(files[0].added, 2);
+        assert_eq!(files[1].removed, 1);
+        assert_eq!(files[2].changed, 1);
+
+        // Plain `diff -u` output, one `---`/`+++` pair per file.
+        let patch = "--- x\t2024-01-01\n+++ y\t2024-01-02\n@@ -1 +1 @@\n-a\n+b\n--- p\n+++ q\n@@ -1 +1 @@\n-c\n+d\n";
+        let files = parse(patch).unwrap();
+        assert_eq!(files.len(), 2);
+        assert_eq!(files[0].new_path.as_deref(), Some("y"));
+        assert_eq!(files[1].old_path.as_deref(), Some("p"));
+    }
+
+    #[test]
+    fn malformed_hunks_are_errors() {
+        let header = "--- a/f\n+++ b/f\n";
+        // A multi-byte first character must not split a code point.
+        let error = parse(&format!("{header}@@ -1 +1 @@\n\u{e9}t\u{e9}\n")).unwrap_err();
+        assert_eq!(
+            error,
+            "line 4: unexpected '\u{e9}t\u{e9}' in hunk @@ -1 +1 @@"
+        );
+        let error = parse(&format!("{header}@@ -1,2 +1,2 @@\n a\n")).unwrap_err();
+        assert_eq!(error, "line 5: hunk ends early");
+        let error = parse(&format!("{header}@@ -x +1 @@\n")).unwrap_err();
+        assert_eq!(error, "line 3: invalid hunk header");
+        // An empty context line whose space an editor stripped.
+        let files = parse(&format!("{header}@@ -1,2 +1,2 @@\n\n-a\n+b\n")).unwrap();
+        assert_eq!(sides(&files[0]), (lines("\na"), lines("\nb")));
+    }
+}
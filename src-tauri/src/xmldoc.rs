@@ -0,0 +1,673 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use quick_xml::escape::{escape, resolve_predefined_entity};
+use quick_xml::events::{BytesStart, BytesText, Event};
+use quick_xml::name::{LocalName, ResolveResult};
+use quick_xml::{NsReader, Reader, Writer};
+use serde_json::{Map, Value};
+
+use crate::document::ParseError;
+use crate::source::{LineIndex, LineRange};
+
+/// Attribute keys start with this, so they can't collide with child
+/// elements of the same name.
+const ATTRIBUTE: char = '@';
+/// Key of an element's text when it also has attributes or children.
+const TEXT: &str = "#text";
+/// What the reserved `xml` prefix is bound to.
+const XML_NAMESPACE: &[u8] = b"http://www.w3.org/XML/1998/namespace";
+
+/// Parses XML into the same value model as JSON and YAML, so it diffs the
+/// same way: the document is an object holding the root element.
+///
+/// - An element with only text is that text; an empty element is `""`.
+/// - Otherwise it's an object: attributes as `@name`, child elements by
+///   name, and any text under `#text`. Children repeated under one name
+///   become an array in document order. Their order relative to siblings
+///   of other names is lost: `<a/><b/><a/>` reads the same as
+///   `<a/><a/><b/>`.
+/// - Names in a namespace are written `{uri}local`, whatever prefix the
+///   document binds the namespace to, so `<soap:Body>` and `<s:Body>`
+///   compare equal when both prefixes name the same URI; so does a default
+///   `xmlns` namespace. `xmlns` declarations themselves are left out, and
+///   names with the reserved `xml` prefix keep it (`@xml:lang`).
+/// - Attribute order doesn't matter. Whitespace between elements is
+///   indentation and is dropped; the text of elements with children is
+///   trimmed and joined with single spaces.
+/// - Comments, processing instructions and the doctype are left out.
+pub fn parse(text: &str) -> Result<Value, ParseError> {
+    let root = read(text)?;
+    let mut map = Map::new();
+    map.insert(root.name.clone(), element_value(&root, "", None));
+    Ok(Value::Object(map))
+}
+
+/// Maps each display path to the source lines it spans. Attributes and
+/// text map to the lines of their element.
+pub fn line_map(text: &str) -> Result<HashMap<String, LineRange>, ParseError> {
+    let root = read(text)?;
+    let mut spans = HashMap::new();
+    spans.insert(String::new(), root.span.clone());
+    spans.insert(root.name.clone(), root.span.clone());
+    element_value(&root, &root.name, Some(&mut spans));
+    let lines = LineIndex::new(text);
+    Ok(spans
+        .into_iter()
+        .map(|(path, range)| {
+            let range = LineRange {
+                start: lines.line_of(range.start),
+                end: lines.line_of(range.end.saturating_sub(1).max(range.start)),
+            };
+            (path, range)
+        })
+        .collect())
+}
+
+/// Re-indents `text` by two spaces per level, keeping the declaration,
+/// comments, CDATA sections and processing instructions. Text inside an
+/// element is kept as written; whitespace between elements is replaced.
+pub fn format(text: &str) -> Result<String, ParseError> {
+    // Checks well-formedness the same way `parse` does first.
+    read(text)?;
+    let mut reader = Reader::from_str(text);
+    let mut writer = Writer::new_with_indent(Vec::new(), b' ', 2);
+    // Whitespace-only text is indentation unless it's all an element holds.
+    let mut pending = None;
+    loop {
+        let event = reader
+            .read_event()
+            .map_err(|e| located(text, &e, reader.error_position()))?;
+        match event {
+            Event::Eof => break,
+            Event::Text(ref t) if t.iter().all(u8::is_ascii_whitespace) => {
+                pending = Some(event.into_owned());
+                continue;
+            }
+            Event::End(_) => {
+                if let Some(whitespace) = pending.take() {
+                    write(&mut writer, whitespace)?;
+                }
+            }
+            // As text, so the writer doesn't break the line after it.
+            Event::GeneralRef(r) => {
+                pending = None;
+                let reference = format!("&{};", String::from_utf8_lossy(&r));
+                write(&mut writer, Event::Text(BytesText::from_escaped(reference)))?;
+                continue;
+            }
+            _ => pending = None,
+        }
+        write(&mut writer, event)?;
+    }
+    let mut out =
+        String::from_utf8(writer.into_inner()).map_err(|e| ParseError::new(e.to_string()))?;
+    out.push('\n');
+    Ok(out)
+}
+
+/// Writes `value` as XML, reversing [`parse`]. The value must be an object
+/// with a single key, the root element.
+pub fn to_string(value: &Value) -> Result<String, String> {
+    let root = match value {
+        Value::Object(map) if map.len() == 1 => map.iter().next(),
+        _ => None,
+    };
+    let Some((name, value)) = root else {
+        return Err("an XML document must have exactly one root element".to_string());
+    };
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    write_element(&mut out, name, value, 0, "")?;
+    Ok(out)
+}
+
+/// An element as read, before it's turned into a value.
+struct Element {
+    /// As expanded by [`expanded_name`].
+    name: String,
+    /// As written in the start tag.
+    tag: String,
+    /// Sorted by name.
+    attributes: Vec<(String, String)>,
+    children: Vec<Element>,
+    /// Text segments between children, in order.
+    text: Vec<String>,
+    /// From `<` of the start tag to past `>` of the end tag.
+    span: Range<usize>,
+}
+
+/// Reads the document into a tree, checking that it's well formed.
+fn read(text: &str) -> Result<Element, ParseError> {
+    let mut reader = NsReader::from_str(text);
+    let mut open: Vec<Element> = Vec::new();
+    let mut root = None;
+    loop {
+        let start = reader.buffer_position() as usize;
+        let event = reader
+            .read_event()
+            .map_err(|e| located(text, &e, reader.error_position()))?;
+        let end = reader.buffer_position() as usize;
+        match event {
+            Event::Start(ref tag) | Event::Empty(ref tag) => {
+                if root.is_some() {
+                    return Err(at(text, "only one root element is allowed", start));
+                }
+                open.push(start_element(text, &reader, tag, start)?);
+                if matches!(event, Event::Empty(_)) {
+                    close(&mut open, &mut root, end);
+                }
+            }
+            Event::End(_) => close(&mut open, &mut root, end),
+            Event::Text(t) => {
+                let content = t.xml_content().map_err(|e| at(text, e, start))?;
+                push_text(text, &mut open, &content, start)?;
+            }
+            Event::CData(t) => {
+                let content = t.decode().map_err(|e| at(text, e, start))?;
+                push_text(text, &mut open, &content, start)?;
+            }
+            Event::GeneralRef(r) => {
+                let resolved = match r.resolve_char_ref() {
+                    Ok(Some(c)) => c.to_string(),
+                    Ok(None) => {
+                        let name = r.decode().map_err(|e| at(text, e, start))?;
+                        resolve_predefined_entity(&name)
+                            .ok_or_else(|| at(text, format!("unknown entity `&{name};`"), start))?
+                            .to_string()
+                    }
+                    Err(e) => return Err(at(text, e, start)),
+                };
+                push_text(text, &mut open, &resolved, start)?;
+            }
+            Event::Eof => break,
+            Event::Comment(_) | Event::Decl(_) | Event::PI(_) | Event::DocType(_) => {}
+        }
+    }
+    if let Some(element) = open.last() {
+        let mut error = at(
+            text,
+            format!("unclosed element `<{}>`", element.tag),
+            text.len(),
+        );
+        error.expected = Some(format!("`</{}>`", element.tag));
+        return Err(error);
+    }
+    root.ok_or_else(|| ParseError::new("no root element"))
+}
+
+fn start_element(
+    text: &str,
+    reader: &NsReader<&[u8]>,
+    tag: &BytesStart,
+    start: usize,
+) -> Result<Element, ParseError> {
+    let name = expanded_name(text, reader.resolve_element(tag.name()), start)?;
+    let mut attributes = Vec::new();
+    for attribute in tag.attributes() {
+        let attribute = attribute.map_err(|e| at(text, e, start))?;
+        if attribute.key.as_namespace_binding().is_some() {
+            continue;
+        }
+        let key = expanded_name(text, reader.resolve_attribute(attribute.key), start)?;
+        let value = attribute.unescape_value().map_err(|e| at(text, e, start))?;
+        attributes.push((key, value.into_owned()));
+    }
+    attributes.sort_by(|a, b| a.0.cmp(&b.0));
+    Ok(Element {
+        name,
+        tag: String::from_utf8_lossy(tag.name().as_ref()).into_owned(),
+        attributes,
+        children: Vec::new(),
+        text: Vec::new(),
+        span: start..start,
+    })
+}
+
+/// `{uri}local` for a name in a namespace, the local name for one that's
+/// in none.
+fn expanded_name(
+    text: &str,
+    (namespace, local): (ResolveResult, LocalName),
+    start: usize,
+) -> Result<String, ParseError> {
+    let local = String::from_utf8_lossy(local.as_ref());
+    match namespace {
+        ResolveResult::Bound(uri) if uri.as_ref() == XML_NAMESPACE => Ok(format!("xml:{local}")),
+        // `xmlns=""` takes an element out of the default namespace.
+        ResolveResult::Bound(uri) if !uri.as_ref().is_empty() => Ok(format!(
+            "{{{}}}{local}",
+            String::from_utf8_lossy(uri.as_ref())
+        )),
+        ResolveResult::Bound(_) | ResolveResult::Unbound => Ok(local.into_owned()),
+        ResolveResult::Unknown(prefix) => Err(at(
+            text,
+            format!(
+                "unbound namespace prefix `{}`",
+                String::from_utf8_lossy(&prefix)
+            ),
+            start,
+        )),
+    }
+}
+
+/// Splits an expanded name into its namespace URI, empty for none, and
+/// local name.
+fn split_name(name: &str) -> (&str, &str) {
+    name.strip_prefix('{')
+        .and_then(|rest| rest.split_once('}'))
+        .unwrap_or(("", name))
+}
+
+fn close(open: &mut Vec<Element>, root: &mut Option<Element>, end: usize) {
+    let Some(mut element) = open.pop() else {
+        return;
+    };
+    element.span.end = end;
+    match open.last_mut() {
+        Some(parent) => parent.children.push(element),
+        None => *root = Some(element),
+    }
+}
+
+fn push_text(
+    text: &str,
+    open: &mut [Element],
+    content: &str,
+    start: usize,
+) -> Result<(), ParseError> {
+    match open.last_mut() {
+        Some(element) => {
+            // One segment before each child and one after the last; entity
+            // references split a segment into several text events.
+            element
+                .text
+                .resize(element.children.len() + 1, String::new());
+            if let Some(segment) = element.text.last_mut() {
+                segment.push_str(content);
+            }
+            Ok(())
+        }
+        None if content.trim().is_empty() => Ok(()),
+        None => Err(at(text, "text outside the root element", start)),
+    }
+}
+
+/// The element's value; records the span of it and everything below when
+/// `spans` is given.
+fn element_value(
+    element: &Element,
+    path: &str,
+    mut spans: Option<&mut HashMap<String, Range<usize>>>,
+) -> Value {
+    if element.attributes.is_empty() && element.children.is_empty() {
+        return Value::String(element.text.concat());
+    }
+    let mut map = Map::new();
+    for (key, value) in &element.attributes {
+        let key = format!("{ATTRIBUTE}{key}");
+        if let Some(spans) = spans.as_deref_mut() {
+            spans.insert(child(path, &key), element.span.clone());
+        }
+        map.insert(key, Value::String(value.clone()));
+    }
+
+    let mut names: Vec<&str> = Vec::new();
+    let mut groups: HashMap<&str, Vec<&Element>> = HashMap::new();
+    for child in &element.children {
+        let group = groups.entry(&child.name).or_default();
+        if group.is_empty() {
+            names.push(&child.name);
+        }
+        group.push(child);
+    }
+    for name in names {
+        let group = &groups[name];
+        let path = child(path, name);
+        let value = if let [only] = group[..] {
+            if let Some(spans) = spans.as_deref_mut() {
+                spans.insert(path.clone(), only.span.clone());
+            }
+            element_value(only, &path, spans.as_deref_mut())
+        } else {
+            if let Some(spans) = spans.as_deref_mut() {
+                let range = group[0].span.start..group[group.len() - 1].span.end;
+                spans.insert(path.clone(), range);
+            }
+            Value::Array(
+                group
+                    .iter()
+                    .enumerate()
+                    .map(|(idx, element)| {
+                        let path = format!("{path}[{idx}]");
+                        if let Some(spans) = spans.as_deref_mut() {
+                            spans.insert(path.clone(), element.span.clone());
+                        }
+                        element_value(element, &path, spans.as_deref_mut())
+                    })
+                    .collect(),
+            )
+        };
+        map.insert(name.to_string(), value);
+    }
+
+    let text = if element.children.is_empty() {
+        element.text.concat()
+    } else {
+        element
+            .text
+            .iter()
+            .map(|segment| segment.trim())
+            .filter(|segment| !segment.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    if !text.trim().is_empty() {
+        if let Some(spans) = spans {
+            spans.insert(child(path, TEXT), element.span.clone());
+        }
+        map.insert(TEXT.to_string(), Value::String(text));
+    }
+    Value::Object(map)
+}
+
+fn child(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{path}.{key}")
+    }
+}
+
+fn write(writer: &mut Writer<Vec<u8>>, event: Event) -> Result<(), ParseError> {
+    writer
+        .write_event(event)
+        .map_err(|e| ParseError::new(e.to_string()))
+}
+
+fn located(text: &str, error: &quick_xml::Error, offset: u64) -> ParseError {
+    at(text, error, offset as usize)
+}
+
+fn at(text: &str, error: impl std::fmt::Display, offset: usize) -> ParseError {
+    ParseError::at(&error, text, offset)
+}
+
+/// Writes one element, or one per item of an array. `namespace` is the
+/// default namespace in scope, declared again wherever it changes; namespaced
+/// attributes get `ns0`, `ns1`, ... prefixes declared on their element.
+fn write_element(
+    out: &mut String,
+    name: &str,
+    value: &Value,
+    depth: usize,
+    namespace: &str,
+) -> Result<(), String> {
+    if let Value::Array(elements) = value {
+        for element in elements {
+            if element.is_array() {
+                return Err(format!(
+                    "`{name}` holds an array of arrays, which XML can't"
+                ));
+            }
+            write_element(out, name, element, depth, namespace)?;
+        }
+        return Ok(());
+    }
+    let indent = "  ".repeat(depth);
+    let (uri, tag) = split_name(name);
+    out.push_str(&format!("{indent}<{tag}"));
+    if uri != namespace {
+        out.push_str(&format!(" xmlns=\"{}\"", escape(uri)));
+    }
+    let Value::Object(map) = value else {
+        let text = scalar(value).unwrap_or_default();
+        if text.is_empty() {
+            out.push_str("/>\n");
+        } else {
+            out.push_str(&format!(">{}</{tag}>\n", escape(text.as_str())));
+        }
+        return Ok(());
+    };
+
+    let mut prefixes: Vec<&str> = Vec::new();
+    let mut attributes = String::new();
+    for (key, value) in map {
+        if let Some(attribute) = key.strip_prefix(ATTRIBUTE) {
+            let value = scalar(value)
+                .ok_or_else(|| format!("attribute `{key}` of `{name}` must be a scalar"))?;
+            let attribute = match split_name(attribute) {
+                ("", local) => local.to_string(),
+                (uri, local) => {
+                    let idx = match prefixes.iter().position(|p| *p == uri) {
+                        Some(idx) => idx,
+                        None => {
+                            prefixes.push(uri);
+                            prefixes.len() - 1
+                        }
+                    };
+                    format!("ns{idx}:{local}")
+                }
+            };
+            attributes.push_str(&format!(" {attribute}=\"{}\"", escape(value.as_str())));
+        }
+    }
+    for (idx, uri) in prefixes.iter().enumerate() {
+        out.push_str(&format!(" xmlns:ns{idx}=\"{}\"", escape(*uri)));
+    }
+    out.push_str(&attributes);
+
+    let text = map.get(TEXT).and_then(scalar).unwrap_or_default();
+    let children: Vec<_> = map
+        .iter()
+        .filter(|(key, _)| !key.starts_with(ATTRIBUTE) && key.as_str() != TEXT)
+        .collect();
+    if children.is_empty() && text.is_empty() {
+        out.push_str("/>\n");
+        return Ok(());
+    }
+    out.push('>');
+    out.push_str(&escape(text.as_str()));
+    if !children.is_empty() {
+        out.push('\n');
+        for (key, value) in children {
+            write_element(out, key, value, depth + 1, uri)?;
+        }
+        out.push_str(&indent);
+    }
+    out.push_str(&format!("</{tag}>\n"));
+    Ok(())
+}
+
+/// The text of a string, number or boolean; `null` is empty.
+fn scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => Some(String::new()),
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        Value::Array(_) | Value::Object(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::diff::{self, DiffOptions, DiffType};
+
+    const POM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- a pom -->
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <modelVersion>4.0.0</modelVersion>
+  <dependencies>
+    <dependency><groupId>junit</groupId></dependency>
+    <dependency>
+      <groupId>a&amp;b</groupId>
+      <artifactId><![CDATA[x<y]]></artifactId>
+    </dependency>
+  </dependencies>
+  <description>Hello <b>big</b>   world &#x41;</description>
+  <empty/>
+</project>
+"#;
+
+    fn diff(left: &str, right: &str, options: &DiffOptions) -> diff::DiffNode {
+        let (left, right) = (parse(left).unwrap(), parse(right).unwrap());
+        diff::diff_documents(Some(&left), Some(&right), options).unwrap()
+    }
+
+    #[test]
+    fn value_model() {
+        let ns = "{http://maven.apache.org/POM/4.0.0}";
+        let dependency = format!("{ns}dependency");
+        let (group, artifact) = (format!("{ns}groupId"), format!("{ns}artifactId"));
+        assert_eq!(
+            parse(POM).unwrap(),
+            json!({format!("{ns}project"): {
+                format!("{ns}modelVersion"): "4.0.0",
+                format!("{ns}dependencies"): {dependency: [
+                    {&group: "junit"},
+                    {&group: "a&b", artifact: "x<y"},
+                ]},
+                format!("{ns}description"): {format!("{ns}b"): "big", "#text": "Hello world A"},
+                format!("{ns}empty"): "",
+            }})
+        );
+        let lines = line_map(POM).unwrap();
+        let dependencies = format!("{ns}project.{ns}dependencies.{ns}dependency");
+        assert_eq!(
+            (lines[&dependencies].start, lines[&dependencies].end),
+            (6, 10)
+        );
+        assert_eq!(lines[&format!("{dependencies}[1]")].start, 7);
+    }
+
+    #[test]
+    fn prefixes_resolve_to_namespaces() {
+        let soap = r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope">
+  <soap:Body soap:role="next"><m:Get xmlns:m="urn:m">1</m:Get></soap:Body>
+</soap:Envelope>"#;
+        let s = r#"<s:Envelope xmlns:s="http://www.w3.org/2003/05/soap-envelope" xmlns:x="urn:m">
+  <s:Body s:role="next"><x:Get>1</x:Get></s:Body>
+</s:Envelope>"#;
+        let default = r#"<Envelope xmlns="http://www.w3.org/2003/05/soap-envelope">
+  <Body xmlns:s="http://www.w3.org/2003/05/soap-envelope" s:role="next">
+    <Get xmlns="urn:m">1</Get>
+  </Body>
+</Envelope>"#;
+        let expected = json!({"{http://www.w3.org/2003/05/soap-envelope}Envelope": {
+            "{http://www.w3.org/2003/05/soap-envelope}Body": {
+                "@{http://www.w3.org/2003/05/soap-envelope}role": "next",
+                "{urn:m}Get": "1",
+            }
+        }});
+        for text in [soap, s, default] {
+            assert_eq!(parse(text).unwrap(), expected, "{text}");
+        }
+        let options = DiffOptions::default();
+        assert_eq!(diff(soap, s, &options).diff_type, DiffType::Unchanged);
+
+        // Unprefixed attributes are in no namespace; `xmlns=""` leaves the default one.
+        let value = parse(r#"<a xmlns="urn:a" id="1" xml:lang="en"><b xmlns=""/></a>"#).unwrap();
+        assert_eq!(
+            value,
+            json!({"{urn:a}a": {"@id": "1", "@xml:lang": "en", "b": ""}})
+        );
+        // Different namespaces stay apart under the same prefix.
+        assert_ne!(
+            parse(r#"<p:a xmlns:p="urn:one"/>"#).unwrap(),
+            parse(r#"<p:a xmlns:p="urn:two"/>"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn to_string_declares_namespaces() {
+        let value = json!({"{urn:a}root": {
+            "@{urn:x}attr": "1",
+            "@plain": "2",
+            "{urn:a}child": ["x", "y"],
+            "local": {"{urn:b}deep": ""},
+        }});
+        let text = to_string(&value).unwrap();
+        assert_eq!(
+            text,
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<root xmlns="urn:a" xmlns:ns0="urn:x" ns0:attr="1" plain="2">
+  <child>x</child>
+  <child>y</child>
+  <local xmlns="">
+    <deep xmlns="urn:b"/>
+  </local>
+</root>
+"#
+        );
+        assert_eq!(parse(&text).unwrap(), value);
+        assert_eq!(
+            parse(&to_string(&parse(POM).unwrap()).unwrap()).unwrap(),
+            parse(POM).unwrap()
+        );
+    }
+
+    #[test]
+    fn attribute_order_does_not_matter() {
+        let options = DiffOptions::default();
+        let node = diff(
+            r#"<a x="1" y="2" z="3"/>"#,
+            r#"<a z="3" x="1" y="2"/>"#,
+            &options,
+        );
+        assert_eq!(node.diff_type, DiffType::Unchanged);
+        let node = diff(r#"<a x="1" y="2"/>"#, r#"<a y="2" x="9"/>"#, &options);
+        assert_eq!(node.stats().changed, 1);
+    }
+
+    #[test]
+    fn collapse_whitespace_equates_reflowed_text() {
+        let left = "<a><t>hello  world</t><u>one two</u></a>";
+        let right = "<a><t>\n  hello\n  world\n</t><u>one  two </u></a>";
+        let mut options = DiffOptions::default();
+        assert_eq!(diff(left, right, &options).stats().changed, 2);
+        options.equivalence.collapse_whitespace = true;
+        let stats = diff(left, right, &options).stats();
+        assert_eq!((stats.changed, stats.equivalent), (0, 2));
+    }
+
+    #[test]
+    fn interleaved_siblings_lose_their_order() {
+        // A documented limitation: siblings are grouped by name.
+        let interleaved = parse("<r><a>1</a><b/><a>2</a></r>").unwrap();
+        assert_eq!(interleaved, json!({"r": {"a": ["1", "2"], "b": ""}}));
+        assert_eq!(interleaved, parse("<r><a>1</a><a>2</a><b/></r>").unwrap());
+        assert_ne!(interleaved, parse("<r><a>2</a><b/><a>1</a></r>").unwrap());
+    }
+
+    #[test]
+    fn format_keeps_comments_and_text() {
+        let formatted = format(POM).unwrap();
+        assert!(formatted.contains("<!-- a pom -->"));
+        assert!(formatted.contains("<![CDATA[x<y]]>"));
+        assert!(formatted.contains("<groupId>a&amp;b</groupId>"));
+        assert_eq!(parse(&formatted).unwrap(), parse(POM).unwrap());
+        assert_eq!(format(&formatted).unwrap(), formatted);
+    }
+
+    #[test]
+    fn errors() {
+        for (text, message) in [
+            ("<a/><b/>", "only one root element is allowed"),
+            ("<x:a/>", "unbound namespace prefix `x`"),
+            ("<a x:b='1'/>", "unbound namespace prefix `x`"),
+            ("<a>&foo;</a>", "unknown entity `&foo;`"),
+            ("text", "text outside the root element"),
+            ("<!-- only -->", "no root element"),
+        ] {
+            assert_eq!(parse(text).unwrap_err().message, message, "{text}");
+        }
+        let error = parse("<p:a xmlns:p='u'>\n  <p:b>").unwrap_err();
+        assert_eq!(error.message, "unclosed element `<p:b>`");
+        assert_eq!(error.expected.as_deref(), Some("`</p:b>`"));
+        assert_eq!(error.line, Some(2));
+        assert!(parse("<a><b></a>").is_err());
+    }
+}
@@ -4,9 +4,43 @@ import MarkdownViewer from './components/MarkdownViewer'
 import MermaidViewer from './components/MermaidViewer'
 import ObjectDiffView from './components/ObjectDiffView'
 import EpochConverter from './components/EpochConverter'
+import MergeView from './components/MergeView'
+import LargeFileDiffView from './components/LargeFileDiffView'
+import TextDiffView from './components/TextDiffView'
+import DirectoryDiffView, { type OpenedFilePair } from './components/DirectoryDiffView'
+import FileHistoryView from './components/FileHistoryView'
+import TableDiffView from './components/TableDiffView'
 import type { ParseFn, FormatFn } from './components/ObjectDiffView'
+import { type DocumentFormat, type ParseError, formatDocument, isTauriEnv } from './utils/backend'
 import yaml from 'js-yaml'
 
+const diffViews: Record<DocumentFormat, ViewType> = {
+  json: 'json-diff',
+  yaml: 'yaml-diff',
+  toml: 'toml-diff',
+  xml: 'xml-diff'
+}
+
+// TOML and XML are parsed by the backend, which reports errors with their position.
+// So is JSON in the desktop app, where comments and other JSON5 syntax are accepted.
+const acceptInBackend: ParseFn = () => ({ valid: true, error: null, parsed: null })
+
+function formatInBackend(format: DocumentFormat): FormatFn {
+  return (text, setText, setError) => {
+    if (!text.trim()) return
+    formatDocument(text, format)
+      .then((formatted) => {
+        setText(formatted)
+        setError(null)
+      })
+      .catch((e: ParseError) => setError(e.message))
+  }
+}
+
+const formatJsonInBackend = formatInBackend('json')
+const formatToml = formatInBackend('toml')
+const formatXml = formatInBackend('xml')
+
 function isEditableElement(target: EventTarget | null): boolean {
   if (!target || !(target instanceof HTMLElement)) return false
   const tag = target.tagName.toLowerCase()
@@ -18,6 +52,7 @@ function isEditableElement(target: EventTarget | null): boolean {
 function App() {
   const [currentView, setCurrentView] = useState<ViewType>('json-diff')
   const [showShortcutHints, setShowShortcutHints] = useState(false)
+  const [openedPair, setOpenedPair] = useState<OpenedFilePair | null>(null)
 
   useEffect(() => {
     const handleKeyDown = (e: KeyboardEvent) => {
@@ -36,10 +71,44 @@ function App() {
           e.preventDefault()
           setCurrentView('yaml-diff')
           break
+        case 'g':
+          e.preventDefault()
+          setCurrentView('merge')
+          break
+        case 'l':
+          e.preventDefault()
+          setCurrentView('large-diff')
+          break
+        case 't':
+          e.preventDefault()
+          setCurrentView(e.shiftKey ? 'toml-diff' : 'text-diff')
+          break
+        case 'd':
+          e.preventDefault()
+          setCurrentView('dir-diff')
+          break
+        case 'h':
+          // Plain Cmd+H hides the app on macOS.
+          if (!e.shiftKey) break
+          e.preventDefault()
+          setCurrentView('history')
+          break
         case 'm':
           e.preventDefault()
           setCurrentView(e.shiftKey ? 'markdown' : 'mermaid')
           break
+        case 'x':
+          // Plain Cmd+X is cut.
+          if (!e.shiftKey) break
+          e.preventDefault()
+          setCurrentView('xml-diff')
+          break
+        case 'c':
+          // Plain Cmd+C is copy.
+          if (!e.shiftKey) break
+          e.preventDefault()
+          setCurrentView('table-diff')
+          break
         case 'e':
           e.preventDefault()
           setCurrentView('epoch')
@@ -121,6 +190,11 @@ function App() {
     }
   }, [])
 
+  const openFilePair = useCallback((pair: OpenedFilePair) => {
+    setOpenedPair(pair)
+    setCurrentView(diffViews[pair.format])
+  }, [])
+
   return (
     <div className="min-h-screen bg-gray-950 text-gray-100 flex">
       <Sidebar currentView={currentView} onViewChange={setCurrentView} showShortcutHints={showShortcutHints} />
@@ -128,11 +202,15 @@ function App() {
         {currentView === 'json-diff' && (
           <ObjectDiffView
             title="JSON Diff"
-            parseFn={validateJson}
-            formatFn={formatJson}
+            parseFn={isTauriEnv() ? acceptInBackend : validateJson}
+            formatFn={isTauriEnv() ? formatJsonInBackend : formatJson}
+            format="json"
+            validateInBackend
+            storageKey="json-diff"
             placeholder='{"key": "value"}'
             emptyMessage="Enter JSON in both panels to compare"
             errorMessage="Fix JSON errors to see diff"
+            initialDocuments={openedPair?.format === 'json' ? openedPair : undefined}
           />
         )}
         {currentView === 'yaml-diff' && (
@@ -140,11 +218,52 @@ function App() {
             title="YAML Diff"
             parseFn={validateYaml}
             formatFn={formatYaml}
+            format="yaml"
+            storageKey="yaml-diff"
             placeholder="key: value"
             emptyMessage="Enter YAML in both panels to compare"
             errorMessage="Fix YAML errors to see diff"
+            initialDocuments={openedPair?.format === 'yaml' ? openedPair : undefined}
+          />
+        )}
+        {currentView === 'toml-diff' && (
+          <ObjectDiffView
+            title="TOML Diff"
+            parseFn={acceptInBackend}
+            formatFn={formatToml}
+            format="toml"
+            validateInBackend
+            storageKey="toml-diff"
+            placeholder='key = "value"'
+            emptyMessage={
+              isTauriEnv() ? 'Enter TOML in both panels to compare' : 'TOML diff is only available in the desktop app.'
+            }
+            errorMessage="Fix TOML errors to see diff"
+            initialDocuments={openedPair?.format === 'toml' ? openedPair : undefined}
+          />
+        )}
+        {currentView === 'xml-diff' && (
+          <ObjectDiffView
+            title="XML Diff"
+            parseFn={acceptInBackend}
+            formatFn={formatXml}
+            format="xml"
+            validateInBackend
+            storageKey="xml-diff"
+            placeholder="<key>value</key>"
+            emptyMessage={
+              isTauriEnv() ? 'Enter XML in both panels to compare' : 'XML diff is only available in the desktop app.'
+            }
+            errorMessage="Fix XML errors to see diff"
+            initialDocuments={openedPair?.format === 'xml' ? openedPair : undefined}
           />
         )}
+        {currentView === 'table-diff' && <TableDiffView />}
+        {currentView === 'merge' && <MergeView />}
+        {currentView === 'large-diff' && <LargeFileDiffView />}
+        {currentView === 'text-diff' && <TextDiffView />}
+        {currentView === 'dir-diff' && <DirectoryDiffView onOpenFile={openFilePair} />}
+        {currentView === 'history' && <FileHistoryView onOpenFile={openFilePair} />}
         {currentView === 'markdown' && (
           <div className="max-w-[1800px] mx-auto px-4 py-6 w-full flex-1 flex flex-col min-h-0">
             <MarkdownViewer />
@@ -0,0 +1,228 @@
+import { useState, useCallback } from 'react'
+import type { DiffType } from '../utils/diffTree'
+import {
+  type DirDiffNode,
+  type DocumentFormat,
+  diffDirectories,
+  isTauriEnv,
+  pickDirectory,
+  readTextFile
+} from '../utils/backend'
+
+/** A pair of files handed to the JSON, YAML, TOML or XML diff view. */
+export interface OpenedFilePair {
+  format: DocumentFormat
+  left: string
+  right: string
+  leftLabel: string
+  rightLabel: string
+}
+
+interface DirectoryDiffViewProps {
+  onOpenFile: (pair: OpenedFilePair) => void
+}
+
+type Side = 'left' | 'right'
+
+const statusClasses: Record<DiffType, string> = {
+  added: 'text-green-400',
+  removed: 'text-red-400',
+  changed: 'text-yellow-400',
+  unchanged: 'text-gray-400',
+  moved: 'text-blue-400',
+  equivalent: 'text-teal-400'
+}
+
+const statusMarks: Record<DiffType, string> = {
+  added: 'A',
+  removed: 'D',
+  changed: 'M',
+  unchanged: '',
+  moved: 'R',
+  equivalent: '≈'
+}
+
+function dirName(path: string | null): string {
+  return path ? (path.split(/[/\\]/).pop() ?? path) : 'Choose folder…'
+}
+
+function join(root: string, path: string): string {
+  return `${root.replace(/[/\\]+$/, '')}/${path}`
+}
+
+export default function DirectoryDiffView({ onOpenFile }: DirectoryDiffViewProps) {
+  const available = isTauriEnv()
+  const [roots, setRoots] = useState<Record<Side, string | null>>({ left: null, right: null })
+  const [tree, setTree] = useState<DirDiffNode | null>(null)
+  const [running, setRunning] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+  const [changesOnly, setChangesOnly] = useState(true)
+  const [collapsed, setCollapsed] = useState<Set<string>>(new Set())
+
+  const handlePick = useCallback(async (side: Side) => {
+    const dir = await pickDirectory()
+    if (!dir) return
+    setRoots((prev) => ({ ...prev, [side]: dir }))
+    setTree(null)
+  }, [])
+
+  const handleCompare = useCallback(async () => {
+    if (!roots.left || !roots.right) return
+    setRunning(true)
+    setError(null)
+    try {
+      setTree(await diffDirectories(roots.left, roots.right, { detectMoves: true }))
+      setCollapsed(new Set())
+    } catch (e) {
+      setTree(null)
+      setError(e instanceof Error ? e.message : String(e))
+    } finally {
+      setRunning(false)
+    }
+  }, [roots])
+
+  const handleOpen = useCallback(
+    async (node: DirDiffNode) => {
+      if (!roots.left || !roots.right || !node.format) return
+      try {
+        const [left, right] = await Promise.all([
+          node.status === 'added' ? '' : readTextFile(join(roots.left, node.path)),
+          node.status === 'removed' ? '' : readTextFile(join(roots.right, node.path))
+        ])
+        onOpenFile({
+          format: node.format,
+          left,
+          right,
+          leftLabel: `${dirName(roots.left)}/${node.path}`,
+          rightLabel: `${dirName(roots.right)}/${node.path}`
+        })
+      } catch (e) {
+        setError(e instanceof Error ? e.message : String(e))
+      }
+    },
+    [roots, onOpenFile]
+  )
+
+  const toggleCollapse = useCallback((path: string) => {
+    setCollapsed((prev) => {
+      const next = new Set(prev)
+      if (next.has(path)) next.delete(path)
+      else next.add(path)
+      return next
+    })
+  }, [])
+
+  if (!available) {
+    return (
+      <div className="max-w-[1800px] mx-auto px-4 py-6 w-full">
+        <h1 className="text-lg font-medium text-gray-200 mb-4">Directory Diff</h1>
+        <p className="text-xs text-gray-500">Directory diff is only available in the desktop app.</p>
+      </div>
+    )
+  }
+
+  const renderNode = (node: DirDiffNode, depth: number): React.ReactNode => {
+    if (changesOnly && node.status === 'unchanged') return null
+    const isDir = !!node.children
+    const isCollapsed = collapsed.has(node.path)
+    const { stats } = node
+    return (
+      <div key={node.path}>
+        <button
+          onClick={() => (isDir ? toggleCollapse(node.path) : handleOpen(node))}
+          className="w-full flex items-center gap-3 px-3 py-1 text-left text-sm hover:bg-gray-800/60 transition-colors"
+          style={{ paddingLeft: `${12 + depth * 20}px` }}
+          title={isDir ? undefined : 'Open in the diff view'}
+        >
+          <span className="w-3 text-xs text-gray-500">{isDir ? (isCollapsed ? '▸' : '▾') : ''}</span>
+          <span className={`w-4 text-xs font-mono ${statusClasses[node.status]}`}>{statusMarks[node.status]}</span>
+          <span className={`font-mono truncate ${isDir ? 'text-gray-300' : statusClasses[node.status]}`}>
+            {node.name}
+            {isDir && '/'}
+          </span>
+          <span className="ml-auto flex items-center gap-2 text-xs">
+            {stats.added > 0 && <span className="text-green-400">+{stats.added}</span>}
+            {stats.removed > 0 && <span className="text-red-400">-{stats.removed}</span>}
+            {stats.changed > 0 && <span className="text-yellow-400">~{stats.changed}</span>}
+            {stats.moved > 0 && <span className="text-blue-400">↕{stats.moved}</span>}
+            {stats.equivalent > 0 && <span className="text-teal-400">≈{stats.equivalent}</span>}
+          </span>
+        </button>
+        {node.error && (
+          <div className="px-3 pb-1 text-xs text-red-400" style={{ paddingLeft: `${48 + depth * 20}px` }}>
+            {node.error}
+          </div>
+        )}
+        {isDir && !isCollapsed && node.children!.map((child) => renderNode(child, depth + 1))}
+      </div>
+    )
+  }
+
+  const fileCount = (node: DirDiffNode, status?: DiffType): number =>
+    node.children
+      ? node.children.reduce((sum, child) => sum + fileCount(child, status), 0)
+      : !status || node.status === status
+        ? 1
+        : 0
+
+  return (
+    <div className="max-w-[1800px] mx-auto px-4 py-6 w-full space-y-4">
+      <div className="flex items-center justify-between">
+        <h1 className="text-lg font-medium text-gray-200">Directory Diff</h1>
+        <button
+          onClick={() => setChangesOnly((v) => !v)}
+          className={`px-3 py-1.5 text-xs font-medium rounded-md transition-colors ${
+            changesOnly
+              ? 'bg-violet-600 text-white'
+              : 'bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700'
+          }`}
+        >
+          Changes Only
+        </button>
+      </div>
+
+      <div className="flex items-center gap-2">
+        {(['left', 'right'] as const).map((side) => (
+          <button
+            key={side}
+            onClick={() => handlePick(side)}
+            className="px-3 py-1.5 text-xs font-medium rounded-md bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700 transition-colors max-w-[300px] truncate"
+            title={roots[side] ?? undefined}
+          >
+            {side === 'left' ? 'Original: ' : 'Modified: '}
+            {dirName(roots[side])}
+          </button>
+        ))}
+        <button
+          onClick={handleCompare}
+          disabled={!roots.left || !roots.right || running}
+          className="px-3 py-1.5 text-xs font-medium rounded-md bg-violet-600 text-white hover:bg-violet-500 transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+        >
+          {running ? 'Comparing…' : 'Compare'}
+        </button>
+      </div>
+
+      {error && (
+        <div className="p-2 text-xs bg-red-950/50 border border-red-900/50 rounded-md text-red-400">{error}</div>
+      )}
+
+      {tree && (
+        <div className="rounded-lg border border-gray-800 overflow-hidden bg-gray-950">
+          <div className="flex items-center gap-3 px-3 py-2 bg-gray-900/50 border-b border-gray-800 text-xs">
+            <span className="font-medium text-gray-500">{fileCount(tree)} files</span>
+            <span className="text-green-400">{fileCount(tree, 'added')} added</span>
+            <span className="text-red-400">{fileCount(tree, 'removed')} removed</span>
+            <span className="text-yellow-400">{fileCount(tree, 'changed')} changed</span>
+          </div>
+          <div className="max-h-[600px] overflow-auto py-1">
+            {tree.status === 'unchanged' ? (
+              <p className="px-3 py-2 text-xs text-green-400">No differences</p>
+            ) : (
+              tree.children!.map((child) => renderNode(child, 0))
+            )}
+          </div>
+        </div>
+      )}
+    </div>
+  )
+}
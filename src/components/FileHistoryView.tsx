@@ -0,0 +1,205 @@
+import { useState, useCallback, useMemo } from 'react'
+import type { DiffType } from '../utils/diffTree'
+import {
+  type DocumentFormat,
+  type GitRevision,
+  gitFileHistory,
+  gitShowFile,
+  isTauriEnv,
+  pickFilePath
+} from '../utils/backend'
+import type { OpenedFilePair } from './DirectoryDiffView'
+
+interface FileHistoryViewProps {
+  onOpenFile: (pair: OpenedFilePair) => void
+}
+
+const changeClasses: Record<DiffType, string> = {
+  added: 'text-green-400',
+  removed: 'text-red-400',
+  changed: 'text-yellow-400',
+  unchanged: 'text-gray-400',
+  moved: 'text-blue-400',
+  equivalent: 'text-teal-400'
+}
+
+function formatOf(path: string): DocumentFormat {
+  if (/\.ya?ml$/i.test(path)) return 'yaml'
+  if (/\.toml$/i.test(path)) return 'toml'
+  return /\.(xml|pom|xsd|wsdl|svg)$/i.test(path) ? 'xml' : 'json'
+}
+
+function formatDate(timestamp: number): string {
+  return new Date(timestamp * 1000).toLocaleString()
+}
+
+export default function FileHistoryView({ onOpenFile }: FileHistoryViewProps) {
+  const available = isTauriEnv()
+  const [file, setFile] = useState<{ path: string; name: string } | null>(null)
+  const [revisions, setRevisions] = useState<GitRevision[]>([])
+  const [loading, setLoading] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+  const [keyFilter, setKeyFilter] = useState('')
+  const [expanded, setExpanded] = useState<Set<string>>(new Set())
+
+  const handlePick = useCallback(async () => {
+    const path = await pickFilePath([
+      { name: 'JSON / YAML / TOML / XML', extensions: ['json', 'jsonc', 'json5', 'yaml', 'yml', 'toml', 'xml', 'pom'] },
+      { name: 'All', extensions: ['*'] }
+    ])
+    if (!path) return
+    setFile({ path, name: path.split(/[/\\]/).pop() ?? path })
+    setLoading(true)
+    setError(null)
+    setExpanded(new Set())
+    try {
+      setRevisions(await gitFileHistory(path, formatOf(path), { detectMoves: true }))
+    } catch (e) {
+      setRevisions([])
+      setError(e instanceof Error ? e.message : String(e))
+    } finally {
+      setLoading(false)
+    }
+  }, [])
+
+  const handleOpen = useCallback(
+    async (revision: GitRevision) => {
+      if (!file) return
+      const { previousHash, previousPath } = revision
+      try {
+        const [left, right] = await Promise.all([
+          previousHash ? gitShowFile(file.path, previousHash, previousPath) : '',
+          gitShowFile(file.path, revision.hash, revision.path)
+        ])
+        onOpenFile({
+          format: formatOf(file.path),
+          left,
+          right,
+          leftLabel: previousHash ? `${file.name} @ ${previousHash.slice(0, revision.shortHash.length)}` : 'Before',
+          rightLabel: `${file.name} @ ${revision.shortHash}`
+        })
+      } catch (e) {
+        setError(e instanceof Error ? e.message : String(e))
+      }
+    },
+    [file, onOpenFile]
+  )
+
+  const toggleExpanded = useCallback((hash: string) => {
+    setExpanded((prev) => {
+      const next = new Set(prev)
+      if (next.has(hash)) next.delete(hash)
+      else next.add(hash)
+      return next
+    })
+  }, [])
+
+  const visible = useMemo(() => {
+    const query = keyFilter.trim()
+    return query ? revisions.filter((revision) => revision.changes.some((c) => c.path.includes(query))) : revisions
+  }, [revisions, keyFilter])
+
+  if (!available) {
+    return (
+      <div className="max-w-[1800px] mx-auto px-4 py-6 w-full">
+        <h1 className="text-lg font-medium text-gray-200 mb-4">File History</h1>
+        <p className="text-xs text-gray-500">File history is only available in the desktop app.</p>
+      </div>
+    )
+  }
+
+  return (
+    <div className="max-w-[1800px] mx-auto px-4 py-6 w-full space-y-4">
+      <div className="flex items-center justify-between">
+        <h1 className="text-lg font-medium text-gray-200">File History</h1>
+        <input
+          type="text"
+          value={keyFilter}
+          onChange={(e) => setKeyFilter(e.target.value)}
+          placeholder="Only commits changing… (e.g. spec.replicas)"
+          spellCheck={false}
+          className="w-72 px-2 py-1 font-mono text-xs rounded-md border border-gray-700 bg-gray-900 text-gray-200 placeholder-gray-600 focus:outline-none focus:ring-1 focus:ring-violet-500"
+        />
+      </div>
+
+      <button
+        onClick={handlePick}
+        disabled={loading}
+        className="px-3 py-1.5 text-xs font-medium rounded-md bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700 transition-colors max-w-[400px] truncate disabled:opacity-50"
+        title={file?.path}
+      >
+        {loading ? 'Reading history…' : file ? file.name : 'Choose a JSON, YAML, TOML or XML file in a git repository…'}
+      </button>
+
+      {error && (
+        <div className="p-2 text-xs bg-red-950/50 border border-red-900/50 rounded-md text-red-400">{error}</div>
+      )}
+
+      {file && !loading && !error && revisions.length === 0 && (
+        <p className="text-xs text-gray-500">No commits touch this file.</p>
+      )}
+
+      {visible.length > 0 && (
+        <ol className="relative border-l border-gray-800 ml-2 space-y-3">
+          {visible.map((revision) => {
+            const { stats } = revision
+            const isExpanded = expanded.has(revision.hash)
+            const total = stats.added + stats.removed + stats.changed + stats.moved + stats.equivalent
+            return (
+              <li key={revision.hash} className="ml-4">
+                <span className="absolute -left-1.5 mt-1.5 w-3 h-3 rounded-full bg-violet-600 border-2 border-gray-950" />
+                <div className="rounded-lg border border-gray-800 bg-gray-950 px-3 py-2 space-y-1">
+                  <div className="flex items-center gap-3 text-xs">
+                    <span className="font-mono text-violet-300">{revision.shortHash}</span>
+                    <span className="text-gray-200 truncate" title={revision.subject}>
+                      {revision.subject}
+                    </span>
+                    <span className="text-gray-400" title={revision.email}>
+                      {revision.author}
+                    </span>
+                    <span className="text-gray-500 whitespace-nowrap">{formatDate(revision.timestamp)}</span>
+                    <span className="ml-auto flex items-center gap-2">
+                      {stats.added > 0 && <span className="text-green-400">+{stats.added}</span>}
+                      {stats.removed > 0 && <span className="text-red-400">-{stats.removed}</span>}
+                      {stats.changed > 0 && <span className="text-yellow-400">~{stats.changed}</span>}
+                      {stats.moved > 0 && <span className="text-blue-400">↕{stats.moved}</span>}
+                      {stats.equivalent > 0 && <span className="text-teal-400">≈{stats.equivalent}</span>}
+                      {revision.changes.length > 0 && (
+                        <button
+                          onClick={() => toggleExpanded(revision.hash)}
+                          className="text-gray-400 hover:text-gray-200"
+                        >
+                          {isExpanded ? 'Hide paths' : 'Paths'}
+                        </button>
+                      )}
+                      <button
+                        onClick={() => handleOpen(revision)}
+                        className="px-2 py-0.5 rounded bg-gray-800 text-gray-300 hover:bg-gray-700 transition-colors"
+                        title="Open this commit's changes in the diff view"
+                      >
+                        Open Diff
+                      </button>
+                    </span>
+                  </div>
+                  {revision.error && <div className="text-xs text-red-400">{revision.error}</div>}
+                  {isExpanded && (
+                    <ul className="pt-1 font-mono text-xs space-y-0.5">
+                      {revision.changes.map((change) => (
+                        <li key={`${change.diffType}:${change.path}`} className={changeClasses[change.diffType]}>
+                          {change.path || '(root)'}
+                        </li>
+                      ))}
+                      {total > revision.changes.length && (
+                        <li className="text-gray-500">…and {total - revision.changes.length} more</li>
+                      )}
+                    </ul>
+                  )}
+                </div>
+              </li>
+            )
+          })}
+        </ol>
+      )}
+    </div>
+  )
+}
@@ -0,0 +1,150 @@
+import { useState, useCallback, useMemo } from 'react'
+import { type GitCommit, gitFileLog, gitRefs, gitShowFile, pickFilePath, readTextFile } from '../utils/backend'
+
+/** Both sides of a comparison, read from a file's git history. */
+export interface RevisionPair {
+  left: string
+  right: string
+  leftLabel: string
+  rightLabel: string
+}
+
+interface GitRevisionPanelProps {
+  filters: { name: string; extensions: string[] }[]
+  onLoad: (pair: RevisionPair) => void
+  onClose: () => void
+}
+
+type Side = 'left' | 'right'
+
+function formatDate(timestamp: number): string {
+  return new Date(timestamp * 1000).toLocaleString()
+}
+
+/** Picks a file in a git work tree and two of its revisions; an empty revision is the working copy. */
+export default function GitRevisionPanel({ filters, onLoad, onClose }: GitRevisionPanelProps) {
+  const [file, setFile] = useState<{ path: string; name: string } | null>(null)
+  const [commits, setCommits] = useState<GitCommit[]>([])
+  const [refs, setRefs] = useState<string[]>([])
+  const [revs, setRevs] = useState<Record<Side, string>>({ left: 'HEAD', right: '' })
+  const [loading, setLoading] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+  const commitsByHash = useMemo(() => new Map(commits.map((c) => [c.hash, c])), [commits])
+
+  const handlePick = useCallback(async () => {
+    try {
+      const path = await pickFilePath(filters)
+      if (!path) return
+      const [log, names] = await Promise.all([gitFileLog(path), gitRefs(path)])
+      setFile({ path, name: path.split(/[/\\]/).pop() ?? path })
+      setCommits(log)
+      setRefs(names)
+      setRevs({ left: 'HEAD', right: '' })
+      setError(null)
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e))
+    }
+  }, [filters])
+
+  const handleLoad = useCallback(async () => {
+    if (!file) return
+    setLoading(true)
+    try {
+      const read = (rev: string) =>
+        rev ? gitShowFile(file.path, rev, commitsByHash.get(rev)?.path) : readTextFile(file.path)
+      const label = (rev: string) => `${file.name} @ ${commitsByHash.get(rev)?.shortHash ?? (rev || 'working copy')}`
+      const left = revs.left.trim()
+      const right = revs.right.trim()
+      const [leftText, rightText] = await Promise.all([read(left), read(right)])
+      onLoad({ left: leftText, right: rightText, leftLabel: label(left), rightLabel: label(right) })
+      setError(null)
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e))
+    } finally {
+      setLoading(false)
+    }
+  }, [file, revs, commitsByHash, onLoad])
+
+  return (
+    <div className="rounded-lg border border-gray-800 bg-gray-950 p-3 space-y-3">
+      <div className="flex items-center gap-2">
+        <button
+          onClick={handlePick}
+          className="px-3 py-1.5 text-xs font-medium rounded-md bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700 transition-colors max-w-[300px] truncate"
+          title={file?.path}
+        >
+          {file ? file.name : 'Choose file in a git repository…'}
+        </button>
+        {(['left', 'right'] as const).map((side) => (
+          <label key={side} className="flex items-center gap-1 text-xs text-gray-400">
+            {side === 'left' ? 'Original' : 'Modified'}
+            <input
+              type="text"
+              list="git-revisions"
+              value={revs[side]}
+              onChange={(e) => setRevs((prev) => ({ ...prev, [side]: e.target.value }))}
+              placeholder="Working copy"
+              spellCheck={false}
+              className="w-40 px-2 py-1 font-mono text-xs rounded-md border border-gray-700 bg-gray-900 text-gray-200 placeholder-gray-600 focus:outline-none focus:ring-1 focus:ring-violet-500"
+            />
+          </label>
+        ))}
+        <datalist id="git-revisions">
+          {refs.map((name) => (
+            <option key={name} value={name} />
+          ))}
+        </datalist>
+        <button
+          onClick={handleLoad}
+          disabled={!file || loading}
+          className="px-3 py-1.5 text-xs font-medium rounded-md bg-violet-600 text-white hover:bg-violet-700 transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+        >
+          {loading ? 'Loading…' : 'Compare'}
+        </button>
+        <button onClick={onClose} className="ml-auto text-xs text-gray-500 hover:text-gray-300" title="Close">
+          ✕
+        </button>
+      </div>
+
+      {error && (
+        <div className="p-2 text-xs bg-red-950/50 border border-red-900/50 rounded-md text-red-400">{error}</div>
+      )}
+
+      {commits.length > 0 && (
+        <div className="max-h-[240px] overflow-auto rounded-md border border-gray-800">
+          <table className="w-full text-xs">
+            <tbody>
+              {commits.map((commit) => (
+                <tr key={commit.hash} className="border-b border-gray-800/50 hover:bg-gray-900/60">
+                  <td className="px-2 py-1 font-mono text-violet-300">{commit.shortHash}</td>
+                  <td className="px-2 py-1 text-gray-200 truncate max-w-[400px]" title={commit.subject}>
+                    {commit.subject}
+                  </td>
+                  <td className="px-2 py-1 text-gray-400" title={commit.email}>
+                    {commit.author}
+                  </td>
+                  <td className="px-2 py-1 text-gray-500 whitespace-nowrap">{formatDate(commit.timestamp)}</td>
+                  <td className="px-2 py-1 whitespace-nowrap text-right">
+                    {(['left', 'right'] as const).map((side) => (
+                      <button
+                        key={side}
+                        onClick={() => setRevs((prev) => ({ ...prev, [side]: commit.hash }))}
+                        className={`ml-1 px-2 py-0.5 rounded transition-colors ${
+                          revs[side] === commit.hash
+                            ? 'bg-violet-600 text-white'
+                            : 'bg-gray-800 text-gray-300 hover:bg-gray-700'
+                        }`}
+                      >
+                        {side === 'left' ? 'Original' : 'Modified'}
+                      </button>
+                    ))}
+                  </td>
+                </tr>
+              ))}
+            </tbody>
+          </table>
+        </div>
+      )}
+    </div>
+  )
+}
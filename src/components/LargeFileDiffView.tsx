@@ -0,0 +1,362 @@
+import { useState, useCallback, useEffect, useRef } from 'react'
+import type { DiffType } from '../utils/diffTree'
+import {
+  type StreamDiffEvent,
+  type StreamDiffRow,
+  type StreamDiffSummary,
+  closeStreamDiff,
+  isTauriEnv,
+  pickFilePath,
+  requestStreamDiffRows,
+  startStreamDiff
+} from '../utils/backend'
+
+const ROW_HEIGHT = 24
+const VIEWPORT_HEIGHT = 600
+const PAGE_SIZE = 200
+/** Rows kept around the visible window; the rest are dropped and refetched on demand. */
+const CACHE_RADIUS = 2000
+/** Browsers cap element heights, so very long lists scroll proportionally instead of per row. */
+const MAX_SCROLL_HEIGHT = 10_000_000
+
+type ProgressEvent = Extract<StreamDiffEvent, { event: 'progress' }>
+
+/** Extensions that switch the JSON Lines mode on when picked. */
+const JSON_LINES_EXTENSIONS = ['jsonl', 'ndjson']
+
+const phaseLabels: Record<ProgressEvent['phase'], string> = {
+  readingLeft: 'Reading left file',
+  readingRight: 'Reading right file',
+  comparing: 'Comparing'
+}
+
+function rowClass(diffType: DiffType): string {
+  switch (diffType) {
+    case 'added':
+      return 'bg-green-900/30'
+    case 'removed':
+      return 'bg-red-900/30'
+    case 'changed':
+      return 'bg-yellow-900/20'
+    default:
+      return ''
+  }
+}
+
+function formatCell(row: StreamDiffRow, side: 'left' | 'right'): string {
+  if (!(side in row)) return ''
+  return JSON.stringify(row[side])
+}
+
+function fileName(path: string | null): string {
+  return path ? (path.split(/[/\\]/).pop() ?? path) : 'Choose file…'
+}
+
+export default function LargeFileDiffView() {
+  const available = isTauriEnv()
+  const [paths, setPaths] = useState<{ left: string | null; right: string | null }>({ left: null, right: null })
+  const [session, setSession] = useState<{ id: number; summary: StreamDiffSummary } | null>(null)
+  const [progress, setProgress] = useState<ProgressEvent | null>(null)
+  const [running, setRunning] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+  const [changesOnly, setChangesOnly] = useState(true)
+  const [jsonLines, setJsonLines] = useState(false)
+  const [keyField, setKeyField] = useState('')
+  const [rows, setRows] = useState<Map<number, StreamDiffRow>>(new Map())
+  const [scrollTop, setScrollTop] = useState(0)
+  const scrollRef = useRef<HTMLDivElement>(null)
+  const sessionRef = useRef<number | null>(null)
+  const changesOnlyRef = useRef(changesOnly)
+  const requestedPages = useRef(new Set<number>())
+  const firstVisibleRef = useRef(0)
+
+  const total = session ? (changesOnly ? session.summary.changes : session.summary.rows) : 0
+  const visibleCount = Math.ceil(VIEWPORT_HEIGHT / ROW_HEIGHT) + 1
+  const fullHeight = total * ROW_HEIGHT
+  const scaled = fullHeight > MAX_SCROLL_HEIGHT
+  const scrollHeight = Math.min(fullHeight, MAX_SCROLL_HEIGHT)
+  const first = scaled
+    ? Math.floor((scrollTop / Math.max(1, scrollHeight - VIEWPORT_HEIGHT)) * Math.max(0, total - visibleCount))
+    : Math.floor(scrollTop / ROW_HEIGHT)
+  const last = Math.min(total, first + visibleCount)
+  firstVisibleRef.current = first
+
+  const handleEvent = useCallback((event: StreamDiffEvent) => {
+    if (event.event === 'progress') {
+      setProgress(event)
+      return
+    }
+    if (event.changesOnly !== changesOnlyRef.current) return
+    setRows((prev) => {
+      const next = new Map(prev)
+      event.rows.forEach((row, idx) => next.set(event.start + idx, row))
+      if (next.size > CACHE_RADIUS * 2) {
+        const center = firstVisibleRef.current
+        for (const idx of next.keys()) {
+          if (Math.abs(idx - center) > CACHE_RADIUS) {
+            next.delete(idx)
+            requestedPages.current.delete(Math.floor(idx / PAGE_SIZE))
+          }
+        }
+      }
+      return next
+    })
+  }, [])
+
+  const resetRows = useCallback(() => {
+    setRows(new Map())
+    requestedPages.current = new Set()
+    setScrollTop(0)
+    if (scrollRef.current) scrollRef.current.scrollTop = 0
+  }, [])
+
+  useEffect(() => {
+    return () => {
+      if (sessionRef.current != null) closeStreamDiff(sessionRef.current).catch(() => {})
+    }
+  }, [])
+
+  useEffect(() => {
+    if (!session) return
+    for (let page = Math.floor(first / PAGE_SIZE); page * PAGE_SIZE < last; page++) {
+      if (requestedPages.current.has(page)) continue
+      requestedPages.current.add(page)
+      requestStreamDiffRows(session.id, page * PAGE_SIZE, PAGE_SIZE, changesOnly).catch((e) => {
+        requestedPages.current.delete(page)
+        console.error('Failed to load rows:', e)
+      })
+    }
+  }, [session, first, last, changesOnly])
+
+  const handlePick = useCallback(async (side: 'left' | 'right') => {
+    try {
+      const path = await pickFilePath([
+        { name: 'JSON', extensions: ['json'] },
+        { name: 'JSON Lines', extensions: JSON_LINES_EXTENSIONS },
+        { name: 'All', extensions: ['*'] }
+      ])
+      if (!path) return
+      setPaths((prev) => ({ ...prev, [side]: path }))
+      const ext = path.split('.').pop()?.toLowerCase() ?? ''
+      if (JSON_LINES_EXTENSIONS.includes(ext)) setJsonLines(true)
+    } catch (e) {
+      console.error('Failed to pick file:', e)
+    }
+  }, [])
+
+  const handleCompare = useCallback(async () => {
+    if (!paths.left || !paths.right) return
+    if (sessionRef.current != null) {
+      closeStreamDiff(sessionRef.current).catch(() => {})
+      sessionRef.current = null
+    }
+    setSession(null)
+    resetRows()
+    setError(null)
+    setProgress(null)
+    setRunning(true)
+    try {
+      const started = await startStreamDiff(paths.left, paths.right, handleEvent, {
+        jsonLines,
+        keyField: jsonLines && keyField.trim() ? keyField.trim() : undefined
+      })
+      sessionRef.current = started.session
+      setSession({ id: started.session, summary: started.summary })
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e))
+    } finally {
+      setRunning(false)
+    }
+  }, [paths, jsonLines, keyField, handleEvent, resetRows])
+
+  const toggleChangesOnly = useCallback(() => {
+    changesOnlyRef.current = !changesOnlyRef.current
+    setChangesOnly(changesOnlyRef.current)
+    resetRows()
+  }, [resetRows])
+
+  if (!available) {
+    return (
+      <div className="max-w-[1800px] mx-auto px-4 py-6 w-full">
+        <h1 className="text-lg font-medium text-gray-200 mb-4">Large File Diff</h1>
+        <p className="text-xs text-gray-500">Diffing files from disk is only available in the desktop app.</p>
+      </div>
+    )
+  }
+
+  const visibleRows: JSX.Element[] = []
+  for (let idx = first; idx < last; idx++) {
+    const row = rows.get(idx)
+    // With JSON Lines each record's values follow a depth-0 header row holding its overall status.
+    if (row && session?.summary.records && row.depth === 0) {
+      visibleRows.push(
+        <div
+          key={idx}
+          className={`flex items-center gap-3 px-3 font-mono text-xs border-b border-gray-800 bg-gray-900/60 ${rowClass(
+            row.diffType
+          )}`}
+          style={{ height: ROW_HEIGHT }}
+        >
+          <span className="truncate font-medium text-gray-200" title={row.path}>
+            {row.path}
+          </span>
+          <span className="text-gray-500">{row.diffType}</span>
+        </div>
+      )
+      continue
+    }
+    visibleRows.push(
+      <div
+        key={idx}
+        className={`grid grid-cols-3 gap-2 px-3 font-mono text-xs items-center border-b border-gray-800/50 ${
+          row ? rowClass(row.diffType) : ''
+        }`}
+        style={{ height: ROW_HEIGHT }}
+      >
+        {row ? (
+          <>
+            <span className="truncate text-purple-400" title={row.path}>
+              {row.path || '(root)'}
+            </span>
+            <span className="truncate text-gray-200">{formatCell(row, 'left')}</span>
+            <span className="truncate text-gray-200">{formatCell(row, 'right')}</span>
+          </>
+        ) : (
+          <span className="text-gray-600">…</span>
+        )}
+      </div>
+    )
+  }
+
+  return (
+    <div className="max-w-[1800px] mx-auto px-4 py-6 w-full space-y-4">
+      <div className="flex items-center justify-between">
+        <h1 className="text-lg font-medium text-gray-200">Large File Diff</h1>
+        <div className="flex items-center gap-2">
+          {(['left', 'right'] as const).map((side) => (
+            <button
+              key={side}
+              onClick={() => handlePick(side)}
+              disabled={running}
+              className="px-3 py-1.5 text-xs font-medium rounded-md bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700 transition-colors max-w-[240px] truncate"
+              title={paths[side] ?? undefined}
+            >
+              {side === 'left' ? 'Left: ' : 'Right: '}
+              {fileName(paths[side])}
+            </button>
+          ))}
+          <button
+            onClick={() => setJsonLines((on) => !on)}
+            disabled={running}
+            className={`px-2 py-1.5 text-xs font-medium rounded transition-colors ${
+              jsonLines ? 'bg-violet-600 text-white' : 'text-gray-400 hover:text-gray-200 hover:bg-gray-800'
+            }`}
+            title="Read each line as a record (JSONL, NDJSON)"
+          >
+            JSON Lines
+          </button>
+          {jsonLines && (
+            <input
+              type="text"
+              value={keyField}
+              onChange={(e) => setKeyField(e.target.value)}
+              disabled={running}
+              placeholder="Key field (blank: by position)"
+              spellCheck={false}
+              className="w-56 px-2 py-1 font-mono text-xs rounded-md border border-gray-700 bg-gray-900 text-gray-200 placeholder-gray-600 focus:outline-none focus:ring-1 focus:ring-violet-500"
+            />
+          )}
+          <button
+            onClick={handleCompare}
+            disabled={running || !paths.left || !paths.right}
+            className="px-3 py-1.5 text-xs font-medium rounded-md bg-violet-600 text-white hover:bg-violet-700 disabled:opacity-50 transition-colors"
+          >
+            Compare
+          </button>
+        </div>
+      </div>
+
+      {running && progress && (
+        <div className="space-y-1 text-xs text-gray-400">
+          <div>
+            {phaseLabels[progress.phase]}
+            {progress.total > 0
+              ? ` — ${Math.round((progress.done / progress.total) * 100)}%`
+              : ` — ${progress.done.toLocaleString()} rows`}
+          </div>
+          {progress.total > 0 && (
+            <div className="h-1 rounded bg-gray-800 overflow-hidden">
+              <div className="h-full bg-violet-500" style={{ width: `${(progress.done / progress.total) * 100}%` }} />
+            </div>
+          )}
+        </div>
+      )}
+
+      {error && (
+        <div className="p-2 text-xs bg-red-950/50 border border-red-900/50 rounded-md text-red-400">{error}</div>
+      )}
+
+      {session && (
+        <div className="rounded-lg border border-gray-800 overflow-hidden bg-gray-950">
+          <div className="flex items-center justify-between px-3 py-2 bg-gray-900/50 border-b border-gray-800">
+            <div className="flex items-center gap-3 text-xs">
+              {session.summary.records && (
+                <>
+                  <span className="font-medium text-gray-500">
+                    {session.summary.records.total.toLocaleString()} records
+                  </span>
+                  {session.summary.records.added > 0 && (
+                    <span className="text-green-400">+{session.summary.records.added.toLocaleString()}</span>
+                  )}
+                  {session.summary.records.removed > 0 && (
+                    <span className="text-red-400">-{session.summary.records.removed.toLocaleString()}</span>
+                  )}
+                  {session.summary.records.changed > 0 && (
+                    <span className="text-yellow-400">~{session.summary.records.changed.toLocaleString()}</span>
+                  )}
+                  <span className="text-gray-700">|</span>
+                </>
+              )}
+              <span className="font-medium text-gray-500">
+                {(session.summary.rows - (session.summary.records?.total ?? 0)).toLocaleString()} values
+              </span>
+              {session.summary.added > 0 && <span className="text-green-400">+{session.summary.added.toLocaleString()}</span>}
+              {session.summary.removed > 0 && <span className="text-red-400">-{session.summary.removed.toLocaleString()}</span>}
+              {session.summary.changed > 0 && <span className="text-yellow-400">~{session.summary.changed.toLocaleString()}</span>}
+              {session.summary.changes === 0 && <span className="text-green-400">No differences</span>}
+            </div>
+            <button
+              onClick={toggleChangesOnly}
+              className={`px-2 py-1.5 text-xs font-medium rounded transition-colors ${
+                changesOnly ? 'bg-violet-600 text-white' : 'text-gray-400 hover:text-gray-200 hover:bg-gray-800'
+              }`}
+            >
+              Only Changes
+            </button>
+          </div>
+          <div className="grid grid-cols-3 gap-2 px-3 py-1 text-xs font-medium text-gray-500 border-b border-gray-800">
+            <span>Path</span>
+            <span>{fileName(paths.left)}</span>
+            <span>{fileName(paths.right)}</span>
+          </div>
+          <div
+            ref={scrollRef}
+            onScroll={(e) => setScrollTop(e.currentTarget.scrollTop)}
+            className="overflow-auto relative"
+            style={{ height: VIEWPORT_HEIGHT }}
+          >
+            <div style={{ height: scrollHeight, position: 'relative' }}>
+              <div
+                style={{ position: 'absolute', left: 0, right: 0, top: scaled
+                    ? Math.min(scrollTop, scrollHeight - visibleCount * ROW_HEIGHT)
+                    : first * ROW_HEIGHT }}
+              >
+                {visibleRows}
+              </div>
+            </div>
+          </div>
+        </div>
+      )}
+    </div>
+  )
+}
@@ -0,0 +1,225 @@
+import { useState, useCallback, useEffect, useMemo } from 'react'
+import {
+  type DocumentFormat,
+  type MergeOutput,
+  type MergeSide,
+  isTauriEnv,
+  mergeDocuments,
+  openTextFile,
+  parseArrayKeys,
+  saveTextFile
+} from '../utils/backend'
+
+const sides: { id: MergeSide; label: string }[] = [
+  { id: 'base', label: 'Base' },
+  { id: 'ours', label: 'Ours' },
+  { id: 'theirs', label: 'Theirs' }
+]
+
+function formatConflictValue(value: unknown): string {
+  if (value === undefined) return '(deleted)'
+  return JSON.stringify(value, null, 2)
+}
+
+export default function MergeView() {
+  const [format, setFormat] = useState<DocumentFormat>('json')
+  const [texts, setTexts] = useState<Record<MergeSide, string>>({ base: '', ours: '', theirs: '' })
+  const [resolutions, setResolutions] = useState<Record<string, MergeSide>>({})
+  const [arrayKeysText, setArrayKeysText] = useState('')
+  const arrayKeys = useMemo(() => parseArrayKeys(arrayKeysText), [arrayKeysText])
+  const [result, setResult] = useState<MergeOutput | null>(null)
+  const [error, setError] = useState<string | null>(null)
+  const available = isTauriEnv()
+
+  useEffect(() => {
+    if (!available) return
+    if (!texts.base.trim() && !texts.ours.trim() && !texts.theirs.trim()) {
+      setResult(null)
+      setError(null)
+      return
+    }
+    let cancelled = false
+    const timer = setTimeout(() => {
+      mergeDocuments(texts.base, texts.ours, texts.theirs, format, resolutions, arrayKeys)
+        .then((output) => {
+          if (cancelled) return
+          setResult(output)
+          setError(null)
+        })
+        .catch((e) => {
+          if (cancelled) return
+          setResult(null)
+          setError(e instanceof Error ? e.message : String(e))
+        })
+    }, 150)
+    return () => {
+      cancelled = true
+      clearTimeout(timer)
+    }
+  }, [available, texts, format, resolutions, arrayKeys])
+
+  const setText = useCallback((side: MergeSide, text: string) => {
+    setTexts((prev) => ({ ...prev, [side]: text }))
+    setResolutions({})
+  }, [])
+
+  const handleOpen = useCallback(
+    async (side: MergeSide) => {
+      try {
+        const file = await openTextFile([
+          { name: format === 'json' ? 'JSON' : 'YAML', extensions: format === 'json' ? ['json'] : ['yaml', 'yml'] },
+          { name: 'All', extensions: ['*'] }
+        ])
+        if (file) setText(side, file.content)
+      } catch (e) {
+        console.error('Failed to open file:', e)
+      }
+    },
+    [format, setText]
+  )
+
+  const handleSave = useCallback(async () => {
+    if (!result) return
+    try {
+      const extension = format === 'json' ? 'json' : 'yaml'
+      await saveTextFile(result.merged, `merged.${extension}`, [
+        { name: format.toUpperCase(), extensions: [extension] }
+      ])
+    } catch (e) {
+      console.error('Failed to save merged file:', e)
+    }
+  }, [result, format])
+
+  if (!available) {
+    return (
+      <div className="max-w-[1800px] mx-auto px-4 py-6 w-full">
+        <h1 className="text-lg font-medium text-gray-200 mb-4">Three-way Merge</h1>
+        <p className="text-xs text-gray-500">Three-way merge is only available in the desktop app.</p>
+      </div>
+    )
+  }
+
+  const unresolved = result?.conflicts.filter((c) => !(c.pointer in resolutions)).length ?? 0
+
+  return (
+    <div className="max-w-[1800px] mx-auto px-4 py-6 w-full space-y-4">
+      <div className="flex items-center justify-between">
+        <h1 className="text-lg font-medium text-gray-200">Three-way Merge</h1>
+        <div className="flex items-center gap-2">
+          <input
+            type="text"
+            value={arrayKeysText}
+            onChange={(e) => {
+              setArrayKeysText(e.target.value)
+              setResolutions({})
+            }}
+            placeholder="Array keys: items[*]=id"
+            title="Merge array elements paired by an identity field, e.g. spec.containers[*]=name (comma separated); other arrays are aligned by content"
+            className="w-56 px-2 py-1 text-xs font-mono bg-gray-950 text-gray-100 rounded border border-gray-700 focus:outline-none focus:border-violet-500"
+          />
+          {(['json', 'yaml'] as const).map((f) => (
+            <button
+              key={f}
+              onClick={() => {
+                setFormat(f)
+                setResolutions({})
+              }}
+              className={`px-3 py-1.5 text-xs font-medium rounded-md transition-colors ${
+                format === f
+                  ? 'bg-violet-600 text-white'
+                  : 'bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700'
+              }`}
+            >
+              {f.toUpperCase()}
+            </button>
+          ))}
+        </div>
+      </div>
+
+      <div className="grid gap-4 grid-cols-1 lg:grid-cols-3">
+        {sides.map(({ id, label }) => (
+          <div key={id} className="space-y-2">
+            <div className="flex items-center justify-between">
+              <span className="text-xs font-medium text-gray-300">{label}</span>
+              <button
+                onClick={() => handleOpen(id)}
+                className="px-3 py-1.5 text-xs font-medium rounded-md bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700 transition-colors"
+              >
+                Open
+              </button>
+            </div>
+            <textarea
+              value={texts[id]}
+              onChange={(e) => setText(id, e.target.value)}
+              spellCheck={false}
+              className="w-full h-[300px] p-3 font-mono text-sm rounded-lg border border-gray-800 bg-gray-950 text-gray-100 placeholder-gray-600 focus:outline-none focus:ring-2 focus:ring-violet-500 resize-none"
+            />
+          </div>
+        ))}
+      </div>
+
+      {error && (
+        <div className="p-2 text-xs bg-red-950/50 border border-red-900/50 rounded-md text-red-400">{error}</div>
+      )}
+
+      {result && (
+        <div className="rounded-lg border border-gray-800 overflow-hidden bg-gray-950">
+          <div className="flex items-center justify-between px-3 py-2 bg-gray-900/50 border-b border-gray-800">
+            <div className="flex items-center gap-3 text-xs">
+              <span className="font-medium text-gray-500">Conflicts</span>
+              {result.conflicts.length === 0 ? (
+                <span className="text-green-400">Merged cleanly</span>
+              ) : (
+                <span className={unresolved > 0 ? 'text-yellow-400' : 'text-green-400'}>
+                  {result.conflicts.length - unresolved} / {result.conflicts.length} resolved
+                </span>
+              )}
+            </div>
+            <button
+              onClick={handleSave}
+              className="px-2 py-1.5 text-xs font-medium rounded bg-violet-600 text-white hover:bg-violet-700 transition-colors"
+            >
+              Save Merged
+            </button>
+          </div>
+
+          {result.conflicts.map((conflict) => (
+            <div key={conflict.pointer} className="border-b border-gray-800/50 px-3 py-2">
+              <div className="text-xs font-mono text-purple-400 mb-2">{conflict.path || '(root)'}</div>
+              <div className="grid gap-2 grid-cols-3">
+                {sides.map(({ id, label }) => (
+                  <button
+                    key={id}
+                    onClick={() => setResolutions((prev) => ({ ...prev, [conflict.pointer]: id }))}
+                    className={`text-left p-2 rounded border font-mono text-xs transition-colors ${
+                      conflict.resolution === id && conflict.pointer in resolutions
+                        ? 'border-violet-500 bg-violet-900/20'
+                        : conflict.resolution === id
+                          ? 'border-yellow-600/60 bg-gray-900'
+                          : 'border-gray-800 bg-gray-900 hover:border-gray-600'
+                    }`}
+                  >
+                    <div className="text-gray-500 mb-1">{label}</div>
+                    <pre className="whitespace-pre-wrap break-words text-gray-200">
+                      {formatConflictValue(conflict[id])}
+                    </pre>
+                  </button>
+                ))}
+              </div>
+            </div>
+          ))}
+
+          <div className="p-3">
+            <div className="text-xs font-medium text-gray-500 mb-2">Merged</div>
+            <textarea
+              value={result.merged}
+              readOnly
+              spellCheck={false}
+              className="w-full h-[300px] p-3 font-mono text-sm rounded-lg border border-gray-800 bg-gray-950 text-gray-100 focus:outline-none resize-none"
+            />
+          </div>
+        </div>
+      )}
+    </div>
+  )
+}
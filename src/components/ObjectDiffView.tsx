@@ -4,9 +4,31 @@ import {
   type DiffNode,
   type FlatDiffRow,
   type DiffType,
+  type EmbeddedEncoding,
+  type Equivalence,
   buildJsonTree,
   compareNodes
 } from '../utils/diffTree'
+import {
+  type ArrayMode,
+  type DocumentFormat,
+  type EquivalenceRules,
+  type LineRange,
+  type ParseError,
+  type ParseWarning,
+  type PatchKind,
+  applyPatch,
+  canonicalizeDocument,
+  createPatch,
+  diffDocuments,
+  isTauriEnv,
+  openTextFile,
+  parseArrayKeys,
+  parseDocument,
+  saveTextFile,
+  splitList
+} from '../utils/backend'
+import GitRevisionPanel, { type RevisionPair } from './GitRevisionPanel'
 
 export type ParseResult = { valid: boolean; error: string | null; parsed: unknown }
 export type ParseFn = (text: string) => ParseResult
@@ -16,9 +38,17 @@ interface ObjectDiffViewProps {
   title: string
   parseFn: ParseFn
   formatFn: FormatFn
+  /** When set and running under Tauri, the diff is computed by the Rust backend. */
+  format?: DocumentFormat
+  /** `parseFn` only accepts everything; the panels show the backend's parse errors instead. */
+  validateInBackend?: boolean
+  /** Key under which per-view settings such as ignored paths are kept in localStorage. */
+  storageKey?: string
   placeholder?: string
   emptyMessage?: string
   errorMessage?: string
+  /** Documents to load and compare, e.g. a file pair opened from the directory diff. */
+  initialDocuments?: { left: string; right: string; leftLabel: string; rightLabel: string }
 }
 
 function getIndent(depth: number) {
@@ -40,11 +70,54 @@ function getRowBgClass(diffType: DiffType, side: 'left' | 'right'): string {
       return side === 'left' ? 'bg-red-900/30' : 'bg-gray-800/30'
     case 'changed':
       return side === 'left' ? 'bg-red-900/20' : 'bg-green-900/20'
+    case 'moved':
+      return 'bg-blue-900/20'
+    case 'equivalent':
+      return 'bg-teal-900/20'
     default:
       return ''
   }
 }
 
+const gitFileFilters: Record<DocumentFormat, { name: string; extensions: string[] }[]> = {
+  json: [
+    { name: 'JSON', extensions: ['json', 'jsonc', 'json5'] },
+    { name: 'All', extensions: ['*'] }
+  ],
+  yaml: [
+    { name: 'YAML', extensions: ['yaml', 'yml'] },
+    { name: 'All', extensions: ['*'] }
+  ],
+  toml: [
+    { name: 'TOML', extensions: ['toml'] },
+    { name: 'All', extensions: ['*'] }
+  ],
+  xml: [
+    { name: 'XML', extensions: ['xml', 'pom', 'xsd', 'wsdl', 'svg'] },
+    { name: 'All', extensions: ['*'] }
+  ]
+}
+
+const equivalenceLabels: Record<Equivalence, string> = {
+  tolerance: 'within numeric tolerance',
+  coercion: 'equal after string/number coercion',
+  caseInsensitive: 'equal ignoring case',
+  whitespace: 'equal ignoring whitespace',
+  nullMissing: 'null equals missing'
+}
+
+const encodingLabels: Record<EmbeddedEncoding, string> = {
+  json: 'JSON',
+  yaml: 'YAML',
+  base64Json: 'base64 JSON'
+}
+
+/** Parses an optional non-negative number from a text input. */
+function parseTolerance(text: string): number | undefined {
+  const value = Number(text)
+  return text.trim() && Number.isFinite(value) && value >= 0 ? value : undefined
+}
+
 function highlightText(text: string, query: string): React.ReactNode {
   if (!query) return text
   const lower = text.toLowerCase()
@@ -68,13 +141,87 @@ function highlightText(text: string, query: string): React.ReactNode {
   return parts
 }
 
+/** Backend parse of one panel: where each path sits in the text, or where parsing failed. */
+function useSourceMap(text: string, format: DocumentFormat | undefined, enabled: boolean) {
+  const [lines, setLines] = useState<Record<string, LineRange>>({})
+  const [error, setError] = useState<ParseError | null>(null)
+
+  useEffect(() => {
+    if (!enabled || !format) return
+    let cancelled = false
+    const timer = setTimeout(() => {
+      parseDocument(text, format)
+        .then((map) => {
+          if (cancelled) return
+          setLines(map)
+          setError(null)
+        })
+        .catch((e: ParseError) => {
+          if (cancelled) return
+          setLines({})
+          setError(e)
+        })
+    }, 150)
+    return () => {
+      cancelled = true
+      clearTimeout(timer)
+    }
+  }, [text, format, enabled])
+
+  return { lines, error }
+}
+
+function describeParseError(error: ParseError): string {
+  const at = error.line != null ? ` (line ${error.line}, column ${error.column ?? 1})` : ''
+  const expected = error.expected ? ` — expected ${error.expected}` : ''
+  return `${error.message}${at}${expected}`
+}
+
+/** Selects 1-based lines `start`..`end` (or a single column on `start`) and scrolls them into view. */
+function revealInTextarea(textarea: HTMLTextAreaElement | null, start: number, end: number, column?: number) {
+  if (!textarea) return
+  const lines = textarea.value.split('\n')
+  const offsetOf = (line: number) =>
+    lines.slice(0, Math.max(0, line - 1)).reduce((sum, l) => sum + l.length + 1, 0)
+  const from = offsetOf(start) + (column != null ? Math.max(0, column - 1) : 0)
+  const to = column != null ? from + 1 : offsetOf(end) + (lines[end - 1]?.length ?? 0)
+  const lineHeight = parseFloat(getComputedStyle(textarea).lineHeight) || 20
+  textarea.focus()
+  textarea.setSelectionRange(from, Math.min(to, textarea.value.length))
+  textarea.scrollTop = Math.max(0, (start - 1) * lineHeight - textarea.clientHeight / 3)
+}
+
+/** The backend's positioned error when there is one (clicking it jumps to the spot), else the webview's message. */
+function ErrorMessage({
+  error,
+  sourceError,
+  onReveal
+}: {
+  error: string
+  sourceError: ParseError | null
+  onReveal: (line: number, column?: number) => void
+}) {
+  if (!sourceError) return <span>{error}</span>
+  const { line, column } = sourceError
+  if (line == null) return <span>{describeParseError(sourceError)}</span>
+  return (
+    <button onClick={() => onReveal(line, column)} className="text-left hover:underline" title="Show in the text">
+      {describeParseError(sourceError)}
+    </button>
+  )
+}
+
 export default function ObjectDiffView({
   title,
   parseFn,
   formatFn,
+  format,
+  validateInBackend = false,
+  storageKey,
   placeholder = '{"key": "value"}',
   emptyMessage = 'Enter content in both panels to compare',
-  errorMessage = 'Fix parse errors to see diff'
+  errorMessage = 'Fix parse errors to see diff',
+  initialDocuments
 }: ObjectDiffViewProps) {
   const [leftText, setLeftText] = useState('')
   const [rightText, setRightText] = useState('')
@@ -93,6 +240,61 @@ export default function ObjectDiffView({
   const [currentMatchIdx, setCurrentMatchIdx] = useState(0)
   const [totalMatches, setTotalMatches] = useState(0)
   const searchInputRef = useRef<HTMLInputElement>(null)
+  const useBackend = !!format && isTauriEnv()
+  const [backendDiff, setBackendDiff] = useState<DiffNode | null>(null)
+  const leftTextareaRef = useRef<HTMLTextAreaElement>(null)
+  const rightTextareaRef = useRef<HTMLTextAreaElement>(null)
+  const leftSource = useSourceMap(leftText, format, useBackend)
+  const rightSource = useSourceMap(rightText, format, useBackend)
+  const leftProblem =
+    leftError ?? (validateInBackend && useBackend && leftSource.error ? describeParseError(leftSource.error) : null)
+  const rightProblem =
+    rightError ?? (validateInBackend && useBackend && rightSource.error ? describeParseError(rightSource.error) : null)
+  const [parseWarnings, setParseWarnings] = useState<{ side: 'left' | 'right'; warning: ParseWarning }[]>([])
+  const [arrayMode, setArrayMode] = useState<ArrayMode>('index')
+  const [detectMoves, setDetectMoves] = useState(true)
+  const [decodeEmbedded, setDecodeEmbedded] = useState(false)
+  const [digests, setDigests] = useState<{ left: string; right: string } | null>(null)
+  const [canonicalNotice, setCanonicalNotice] = useState<string | null>(null)
+  const [patchError, setPatchError] = useState<string | null>(null)
+  const [gitOpen, setGitOpen] = useState(false)
+  const [arrayKeysText, setArrayKeysText] = useState('')
+  const arrayKeys = useMemo(() => parseArrayKeys(arrayKeysText), [arrayKeysText])
+  const [unorderedPathsText, setUnorderedPathsText] = useState('')
+  const unorderedPaths = useMemo(() => splitList(unorderedPathsText), [unorderedPathsText])
+  const [ignorePathsText, setIgnorePathsText] = useState(() =>
+    storageKey ? (localStorage.getItem(`${storageKey}:ignorePaths`) ?? '') : ''
+  )
+  const ignorePaths = useMemo(() => splitList(ignorePathsText), [ignorePathsText])
+  const [absoluteTolerance, setAbsoluteTolerance] = useState('')
+  const [relativeTolerance, setRelativeTolerance] = useState('')
+  const [equivalenceFlags, setEquivalenceFlags] = useState({
+    coerceStringNumbers: false,
+    caseInsensitive: false,
+    collapseWhitespace: false,
+    nullEqualsMissing: false
+  })
+  const equivalence: EquivalenceRules = useMemo(
+    () => ({
+      absoluteTolerance: parseTolerance(absoluteTolerance),
+      relativeTolerance: parseTolerance(relativeTolerance),
+      ...equivalenceFlags
+    }),
+    [absoluteTolerance, relativeTolerance, equivalenceFlags]
+  )
+
+  useEffect(() => {
+    if (!initialDocuments) return
+    setLeftText(initialDocuments.left)
+    setRightText(initialDocuments.right)
+    setLeftLabel(initialDocuments.leftLabel)
+    setRightLabel(initialDocuments.rightLabel)
+    setShowDiff(true)
+  }, [initialDocuments])
+
+  useEffect(() => {
+    if (storageKey) localStorage.setItem(`${storageKey}:ignorePaths`, ignorePathsText)
+  }, [storageKey, ignorePathsText])
 
   const leftValidation = useMemo(() => {
     const result = parseFn(leftText)
@@ -106,7 +308,83 @@ export default function ObjectDiffView({
     return result
   }, [rightText, parseFn])
 
+  useEffect(() => {
+    if (!useBackend || !format || !showDiff) return
+    if (!leftValidation.valid || !rightValidation.valid) {
+      setBackendDiff(null)
+      setParseWarnings([])
+      return
+    }
+    let cancelled = false
+    const timer = setTimeout(() => {
+      diffDocuments(leftText, rightText, format, {
+        arrayMode,
+        arrayKeys,
+        unorderedPaths,
+        ignorePaths,
+        equivalence,
+        detectMoves,
+        decodeEmbedded
+      })
+        .then((output) => {
+          if (cancelled) return
+          setBackendDiff(output.root)
+          setParseWarnings([
+            ...output.leftWarnings.map((warning) => ({ side: 'left' as const, warning })),
+            ...output.rightWarnings.map((warning) => ({ side: 'right' as const, warning }))
+          ])
+        })
+        .catch((e) => {
+          console.error('Failed to diff documents:', e)
+          if (cancelled) return
+          setBackendDiff(null)
+          setParseWarnings([])
+        })
+    }, 150)
+    return () => {
+      cancelled = true
+      clearTimeout(timer)
+    }
+  }, [useBackend, format, showDiff, leftText, rightText, leftValidation.valid, rightValidation.valid, arrayMode, arrayKeys, unorderedPaths, ignorePaths, equivalence, detectMoves, decodeEmbedded])
+
+  useEffect(() => {
+    if (!useBackend || !format || !showDiff || !leftValidation.valid || !rightValidation.valid) {
+      setDigests(null)
+      return
+    }
+    let cancelled = false
+    const timer = setTimeout(() => {
+      Promise.all([canonicalizeDocument(leftText, format), canonicalizeDocument(rightText, format)])
+        .then(([left, right]) => {
+          if (!cancelled) setDigests({ left: left.sha256, right: right.sha256 })
+        })
+        .catch(() => {
+          if (!cancelled) setDigests(null)
+        })
+    }, 150)
+    return () => {
+      cancelled = true
+      clearTimeout(timer)
+    }
+  }, [useBackend, format, showDiff, leftText, rightText, leftValidation.valid, rightValidation.valid])
+
+  const copyCanonical = useCallback(
+    async (side: 'left' | 'right', path: string) => {
+      if (!format) return
+      try {
+        const { canonical, sha256 } = await canonicalizeDocument(side === 'left' ? leftText : rightText, format, path)
+        await navigator.clipboard.writeText(canonical)
+        setCanonicalNotice(`Copied canonical ${path || side} · SHA-256 ${sha256.slice(0, 12)}…`)
+      } catch (e) {
+        setCanonicalNotice(`Copy failed: ${e instanceof Error ? e.message : String(e)}`)
+      }
+      setTimeout(() => setCanonicalNotice(null), 2500)
+    },
+    [format, leftText, rightText]
+  )
+
   const diffTree = useMemo(() => {
+    if (useBackend) return backendDiff
     if (!leftValidation.valid || !rightValidation.valid) return null
     if (!leftValidation.parsed && !rightValidation.parsed) return null
 
@@ -118,7 +396,7 @@ export default function ObjectDiffView({
       : undefined
 
     return compareNodes(leftTree, rightTree, 'root', 0)
-  }, [leftValidation, rightValidation])
+  }, [useBackend, backendDiff, leftValidation, rightValidation])
 
   const flatDiffRows = useMemo(() => {
     if (!diffTree) return []
@@ -141,6 +419,46 @@ export default function ObjectDiffView({
     return rows
   }, [diffTree, collapsed])
 
+  const exportPatch = useCallback(
+    async (kind: PatchKind) => {
+      if (!format) return
+      try {
+        const patch = await createPatch(leftText, rightText, format, kind)
+        const defaultPath = kind === 'jsonPatch' ? 'changes.patch.json' : 'changes.merge-patch.json'
+        await saveTextFile(patch, defaultPath, [{ name: 'JSON', extensions: ['json'] }])
+      } catch (e) {
+        console.error('Failed to export patch:', e)
+      }
+    },
+    [format, leftText, rightText]
+  )
+
+  const loadPatchedDocument = useCallback(async () => {
+    if (!format) return
+    try {
+      const file = await openTextFile([
+        { name: 'JSON Patch / Merge Patch', extensions: ['json', 'yaml', 'yml'] },
+        { name: 'All', extensions: ['*'] }
+      ])
+      if (!file) return
+      const patched = await applyPatch(leftText, format, file.content)
+      setRightText(patched)
+      setRightLabel(`Patched (${file.name})`)
+      setPatchError(null)
+      setShowDiff(true)
+    } catch (e) {
+      setPatchError(e instanceof Error ? e.message : String(e))
+    }
+  }, [format, leftText])
+
+  const loadRevisions = useCallback((pair: RevisionPair) => {
+    setLeftText(pair.left)
+    setRightText(pair.right)
+    setLeftLabel(pair.leftLabel)
+    setRightLabel(pair.rightLabel)
+    setShowDiff(true)
+  }, [])
+
   const toggleCollapse = useCallback((path: string) => {
     setCollapsed((prev) => {
       const next = new Set(prev)
@@ -164,18 +482,25 @@ export default function ObjectDiffView({
   const expandAll = useCallback(() => setCollapsed(new Set()), [])
 
   const stats = useMemo(() => {
-    if (!diffTree) return { added: 0, removed: 0, changed: 0 }
+    if (!diffTree) return { added: 0, removed: 0, changed: 0, moved: 0, equivalent: 0 }
     let added = 0,
       removed = 0,
-      changed = 0
+      changed = 0,
+      moved = 0,
+      equivalent = 0
     const count = (node: DiffNode) => {
       if (node.diffType === 'added' && !node.childDiffs) added++
       else if (node.diffType === 'removed' && !node.childDiffs) removed++
       else if (node.diffType === 'changed' && !node.isCollapsible) changed++
+      else if (node.diffType === 'moved') {
+        // A move shows up at both ends; count it once, at its destination.
+        if (node.movedTo != null) return
+        moved++
+      } else if (node.diffType === 'equivalent' && node.equivalence) equivalent++
       node.childDiffs?.forEach(count)
     }
     count(diffTree)
-    return { added, removed, changed }
+    return { added, removed, changed, moved, equivalent }
   }, [diffTree])
 
   useEffect(() => {
@@ -302,6 +627,36 @@ export default function ObjectDiffView({
     container.scrollTo({ top: targetScroll, behavior: 'smooth' })
   }, [])
 
+  const revealNode = useCallback(
+    (side: 'left' | 'right', path: string | undefined) => {
+      if (path == null) return
+      const range = (side === 'left' ? leftSource : rightSource).lines[path]
+      const textarea = side === 'left' ? leftTextareaRef.current : rightTextareaRef.current
+      if (range) revealInTextarea(textarea, range.start, range.end)
+    },
+    [leftSource, rightSource]
+  )
+
+  /** Scrolls to the other end of a moved subtree, expanding its parents first. */
+  const jumpToMove = useCallback((node: DiffNode) => {
+    const target = node.movedTo ?? node.movedFrom
+    if (target == null) return
+    const isAncestor = (path: string) =>
+      path === '' || (target.startsWith(path) && ['.', '['].includes(target[path.length]))
+    setCollapsed((prev) => new Set([...prev].filter((path) => !isAncestor(path))))
+    const selector =
+      node.movedTo != null
+        ? `tr[data-moved-from="${CSS.escape(node.path)}"]`
+        : `tr[data-moved-to="${CSS.escape(node.path)}"]`
+    requestAnimationFrame(() => {
+      const row = scrollContainerRef.current?.querySelector(selector)
+      if (!row) return
+      row.scrollIntoView({ block: 'center', behavior: 'smooth' })
+      row.classList.add('diff-move-target')
+      setTimeout(() => row.classList.remove('diff-move-target'), 1500)
+    })
+  }, [])
+
   const renderDiffNode = useCallback(
     (node: DiffNode, index: number): JSX.Element[] => {
       const rows: JSX.Element[] = []
@@ -325,8 +680,20 @@ export default function ObjectDiffView({
           }
         }
 
+        if (n.decoded) {
+          content.push(
+            <span
+              key="decoded"
+              className="mr-1 px-1 rounded text-[10px] bg-amber-900/40 text-amber-400"
+              title={`Parsed from a string holding ${encodingLabels[n.decoded]}`}
+            >
+              {encodingLabels[n.decoded]}
+            </span>
+          )
+        }
+
         if (n.type === 'object') {
-          const childCount = n.children?.length || 0
+          const childCount = n.children?.length ?? n.childCount ?? 0
           if (isCollapsed) {
             content.push(
               <span key="collapsed" className="text-gray-400">
@@ -338,7 +705,7 @@ export default function ObjectDiffView({
             content.push(<span key="open" className="text-gray-400">{'{'}</span>)
           }
         } else if (n.type === 'array') {
-          const childCount = n.children?.length || 0
+          const childCount = n.children?.length ?? n.childCount ?? 0
           if (isCollapsed) {
             content.push(
               <span key="collapsed" className="text-gray-400">
@@ -353,7 +720,7 @@ export default function ObjectDiffView({
             )
           }
         } else {
-          const val = formatValue(n.value)
+          const val = n.raw ?? formatValue(n.value)
           const valClass =
             typeof n.value === 'string'
               ? 'text-green-400'
@@ -368,13 +735,42 @@ export default function ObjectDiffView({
         return <span>{content}</span>
       }
 
+      const renderCopyCanonical = (side: 'left' | 'right', n: JsonNode | undefined) =>
+        useBackend &&
+        n && (
+          <button
+            onClick={() => copyCanonical(side, n.path)}
+            className="ml-2 text-xs text-gray-600 hover:text-gray-300 opacity-0 group-hover:opacity-100"
+            title="Copy the canonical (RFC 8785) form of this value"
+          >
+            ⧉
+          </button>
+        )
+
+      const renderMoveLink = (target: string | undefined, label: string) =>
+        target != null && (
+          <button
+            onClick={() => jumpToMove(node)}
+            className="ml-2 text-xs text-blue-400 hover:underline"
+            title="Show the other end of this move"
+          >
+            {label} {target || '(root)'}
+          </button>
+        )
+
       rows.push(
         <tr
           key={`${node.path}-${index}`}
-          className="border-b border-gray-800/50 hover:bg-gray-800/30"
+          className="group border-b border-gray-800/50 hover:bg-gray-800/30"
           data-path={node.path}
+          data-moved-from={node.movedFrom}
+          data-moved-to={node.movedTo}
+          title={node.equivalence ? `Equivalent: ${equivalenceLabels[node.equivalence]}` : undefined}
         >
-          <td className={`py-1 px-2 font-mono text-sm align-top break-words ${getRowBgClass(node.diffType, 'left')}`}>
+          <td
+            className={`py-1 px-2 font-mono text-sm align-top break-words ${getRowBgClass(node.diffType, 'left')}`}
+            onDoubleClick={() => revealNode('left', leftNode?.path)}
+          >
             <div className="flex items-start min-w-0" style={getIndent(node.depth)}>
               {node.isCollapsible && leftNode && (
                 <button
@@ -395,10 +791,17 @@ export default function ObjectDiffView({
                 </button>
               )}
               {!node.isCollapsible && <span className="w-4 mr-1 flex-shrink-0" />}
-              <span className="min-w-0 break-words">{renderSide(leftNode)}</span>
+              <span className="min-w-0 break-words">
+                {renderSide(leftNode)}
+                {renderMoveLink(node.movedFrom, '← moved from')}
+                {renderCopyCanonical('left', leftNode)}
+              </span>
             </div>
           </td>
-          <td className={`py-1 px-2 font-mono text-sm align-top break-words ${getRowBgClass(node.diffType, 'right')}`}>
+          <td
+            className={`py-1 px-2 font-mono text-sm align-top break-words ${getRowBgClass(node.diffType, 'right')}`}
+            onDoubleClick={() => revealNode('right', rightNode?.path)}
+          >
             <div className="flex items-start min-w-0" style={getIndent(node.depth)}>
               {node.isCollapsible && rightNode && (
                 <button
@@ -419,7 +822,11 @@ export default function ObjectDiffView({
                 </button>
               )}
               {!node.isCollapsible && <span className="w-4 mr-1 flex-shrink-0" />}
-              <span className="min-w-0 break-words">{renderSide(rightNode)}</span>
+              <span className="min-w-0 break-words">
+                {renderSide(rightNode)}
+                {renderMoveLink(node.movedTo, '→ moved to')}
+                {renderCopyCanonical('right', rightNode)}
+              </span>
             </div>
           </td>
         </tr>
@@ -469,7 +876,7 @@ export default function ObjectDiffView({
       }
       return rows
     },
-    [collapsed, toggleCollapse, searchQuery]
+    [collapsed, toggleCollapse, searchQuery, revealNode, jumpToMove, useBackend, copyCanonical]
   )
 
   const viewportRatio = scrollInfo.clientHeight / scrollInfo.scrollHeight
@@ -481,6 +888,12 @@ export default function ObjectDiffView({
         <h1 className="text-lg font-medium text-gray-200">{title}</h1>
       </div>
 
+      {useBackend && format && gitOpen && (
+        <div className="mb-4">
+          <GitRevisionPanel filters={gitFileFilters[format]} onLoad={loadRevisions} onClose={() => setGitOpen(false)} />
+        </div>
+      )}
+
       <div
         className={`grid gap-4 mb-6 ${showDiff ? 'grid-cols-1 lg:grid-cols-2' : 'grid-cols-1'}`}
       >
@@ -500,6 +913,28 @@ export default function ObjectDiffView({
               >
                 {showDiff ? 'Hide Diff' : 'Show Diff'}
               </button>
+              {useBackend && (
+                <button
+                  onClick={loadPatchedDocument}
+                  className="px-3 py-1.5 text-xs font-medium rounded-md bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700 transition-colors"
+                  title="Apply a JSON Patch or Merge Patch file to this document and show the result on the right"
+                >
+                  Apply Patch
+                </button>
+              )}
+              {useBackend && (
+                <button
+                  onClick={() => setGitOpen(!gitOpen)}
+                  className={`px-3 py-1.5 text-xs font-medium rounded-md transition-colors ${
+                    gitOpen
+                      ? 'bg-violet-600 text-white'
+                      : 'bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700'
+                  }`}
+                  title="Compare a file against another git revision: HEAD, a branch, a commit or a stash"
+                >
+                  Git
+                </button>
+              )}
               <button
                 onClick={() => formatFn(leftText, setLeftText, setLeftError)}
                 className="px-3 py-1.5 text-xs font-medium rounded-md bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700 transition-colors"
@@ -509,15 +944,16 @@ export default function ObjectDiffView({
             </div>
           </div>
           <textarea
+            ref={leftTextareaRef}
             value={leftText}
             onChange={(e) => setLeftText(e.target.value)}
             placeholder={placeholder}
             spellCheck={false}
             className={`w-full h-[576px] p-3 font-mono text-sm rounded-lg border bg-gray-950 text-gray-100 placeholder-gray-600 focus:outline-none focus:ring-2 focus:ring-violet-500 resize-none ${
-              leftError ? 'border-red-500/50 focus:ring-red-500' : 'border-gray-800'
+              leftProblem ? 'border-red-500/50 focus:ring-red-500' : 'border-gray-800'
             }`}
           />
-          {leftError && (
+          {leftProblem && (
             <div className="flex items-center gap-2 p-2 text-xs bg-red-950/50 border border-red-900/50 rounded-md text-red-400">
               <svg className="w-4 h-4 flex-shrink-0" fill="currentColor" viewBox="0 0 20 20">
                 <path
@@ -526,7 +962,19 @@ export default function ObjectDiffView({
                   clipRule="evenodd"
                 />
               </svg>
-              <span>{leftError}</span>
+              <ErrorMessage
+                error={leftProblem}
+                sourceError={useBackend ? leftSource.error : null}
+                onReveal={(line, column) => revealInTextarea(leftTextareaRef.current, line, line, column)}
+              />
+            </div>
+          )}
+          {patchError && (
+            <div className="flex items-center gap-2 p-2 text-xs bg-red-950/50 border border-red-900/50 rounded-md text-red-400">
+              <span className="flex-1">Patch failed: {patchError}</span>
+              <button onClick={() => setPatchError(null)} className="text-red-300 hover:text-red-100" title="Dismiss">
+                ✕
+              </button>
             </div>
           )}
         </div>
@@ -549,15 +997,16 @@ export default function ObjectDiffView({
               </button>
             </div>
             <textarea
+              ref={rightTextareaRef}
               value={rightText}
               onChange={(e) => setRightText(e.target.value)}
               placeholder={placeholder}
               spellCheck={false}
               className={`w-full h-[576px] p-3 font-mono text-sm rounded-lg border bg-gray-950 text-gray-100 placeholder-gray-600 focus:outline-none focus:ring-2 focus:ring-violet-500 resize-none ${
-                rightError ? 'border-red-500/50 focus:ring-red-500' : 'border-gray-800'
+                rightProblem ? 'border-red-500/50 focus:ring-red-500' : 'border-gray-800'
               }`}
             />
-            {rightError && (
+            {rightProblem && (
               <div className="flex items-center gap-2 p-2 text-xs bg-red-950/50 border border-red-900/50 rounded-md text-red-400">
                 <svg className="w-4 h-4 flex-shrink-0" fill="currentColor" viewBox="0 0 20 20">
                   <path
@@ -566,7 +1015,11 @@ export default function ObjectDiffView({
                     clipRule="evenodd"
                   />
                 </svg>
-                <span>{rightError}</span>
+                <ErrorMessage
+                  error={rightProblem}
+                  sourceError={useBackend ? rightSource.error : null}
+                  onReveal={(line, column) => revealInTextarea(rightTextareaRef.current, line, line, column)}
+                />
               </div>
             )}
           </div>
@@ -583,11 +1036,54 @@ export default function ObjectDiffView({
                   {stats.added > 0 && <span className="text-green-400">+{stats.added}</span>}
                   {stats.removed > 0 && <span className="text-red-400">-{stats.removed}</span>}
                   {stats.changed > 0 && <span className="text-yellow-400">~{stats.changed}</span>}
+                  {stats.moved > 0 && <span className="text-blue-400">↕{stats.moved}</span>}
+                  {stats.equivalent > 0 && <span className="text-teal-400">≈{stats.equivalent}</span>}
                 </div>
               )}
+              {digests && (
+                <div className="flex items-center gap-1 text-xs font-mono">
+                  <span className="font-sans text-gray-500">SHA-256</span>
+                  <button
+                    onClick={() => copyCanonical('left', '')}
+                    className="text-gray-400 hover:text-gray-200"
+                    title={`Canonical (RFC 8785) SHA-256 of the left document: ${digests.left}\nClick to copy the canonical form`}
+                  >
+                    {digests.left.slice(0, 8)}
+                  </button>
+                  <span className={digests.left === digests.right ? 'text-green-400' : 'text-gray-600'}>
+                    {digests.left === digests.right ? '=' : '≠'}
+                  </span>
+                  <button
+                    onClick={() => copyCanonical('right', '')}
+                    className="text-gray-400 hover:text-gray-200"
+                    title={`Canonical (RFC 8785) SHA-256 of the right document: ${digests.right}\nClick to copy the canonical form`}
+                  >
+                    {digests.right.slice(0, 8)}
+                  </button>
+                </div>
+              )}
+              {canonicalNotice && <span className="text-xs text-gray-400">{canonicalNotice}</span>}
             </div>
             {diffTree && (
               <div className="flex items-center gap-2">
+                {useBackend && (
+                  <>
+                    <button
+                      onClick={() => exportPatch('jsonPatch')}
+                      className="px-2 py-1.5 text-xs font-medium rounded bg-gray-700 text-gray-300 hover:bg-gray-600 transition-colors"
+                      title="Save an RFC 6902 JSON Patch that turns the left document into the right one"
+                    >
+                      Export JSON Patch
+                    </button>
+                    <button
+                      onClick={() => exportPatch('mergePatch')}
+                      className="px-2 py-1.5 text-xs font-medium rounded bg-gray-700 text-gray-300 hover:bg-gray-600 transition-colors"
+                      title="Save an RFC 7386 Merge Patch that turns the left document into the right one"
+                    >
+                      Export Merge Patch
+                    </button>
+                  </>
+                )}
                 <button
                   onClick={() => {
                     setSearchOpen(true)
@@ -617,6 +1113,136 @@ export default function ObjectDiffView({
             )}
           </div>
 
+          {useBackend && (
+            <div className="flex items-center gap-2 px-3 py-2 bg-gray-900/30 border-b border-gray-800">
+              <input
+                type="text"
+                value={ignorePathsText}
+                onChange={(e) => setIgnorePathsText(e.target.value)}
+                placeholder="Ignore: $..updatedAt, metadata.resourceVersion"
+                title="Paths left out of the diff (JSONPath or glob, comma separated)"
+                className="flex-1 px-2 py-1 text-xs font-mono bg-gray-950 text-gray-100 rounded border border-gray-700 focus:outline-none focus:border-violet-500"
+              />
+              <input
+                type="text"
+                value={arrayKeysText}
+                onChange={(e) => setArrayKeysText(e.target.value)}
+                placeholder="Array keys: items[*]=id"
+                title="Pair array elements by an identity field, e.g. spec.containers[*]=name (comma separated)"
+                className="w-56 px-2 py-1 text-xs font-mono bg-gray-950 text-gray-100 rounded border border-gray-700 focus:outline-none focus:border-violet-500"
+              />
+              <button
+                onClick={() => setArrayMode((m) => (m === 'lcs' ? 'index' : 'lcs'))}
+                className={`px-2 py-1.5 text-xs font-medium rounded transition-colors ${
+                  arrayMode === 'lcs'
+                    ? 'bg-violet-600 text-white hover:bg-violet-700'
+                    : 'bg-gray-700 text-gray-300 hover:bg-gray-600'
+                }`}
+                title="Align array elements by content instead of by index"
+              >
+                Align Arrays
+              </button>
+              <input
+                type="text"
+                value={unorderedPathsText}
+                onChange={(e) => setUnorderedPathsText(e.target.value)}
+                placeholder="Unordered: $..tags"
+                title="Arrays compared as sets, ignoring order (JSONPath or glob, comma separated)"
+                className="w-44 px-2 py-1 text-xs font-mono bg-gray-950 text-gray-100 rounded border border-gray-700 focus:outline-none focus:border-violet-500"
+              />
+              <button
+                onClick={() => setArrayMode((m) => (m === 'multiset' ? 'index' : 'multiset'))}
+                className={`px-2 py-1.5 text-xs font-medium rounded transition-colors ${
+                  arrayMode === 'multiset'
+                    ? 'bg-violet-600 text-white hover:bg-violet-700'
+                    : 'bg-gray-700 text-gray-300 hover:bg-gray-600'
+                }`}
+                title="Compare every array as a set: ignore order and only report missing or extra elements"
+              >
+                Unordered Arrays
+              </button>
+              <button
+                onClick={() => setDetectMoves((d) => !d)}
+                className={`px-2 py-1.5 text-xs font-medium rounded transition-colors ${
+                  detectMoves
+                    ? 'bg-violet-600 text-white hover:bg-violet-700'
+                    : 'bg-gray-700 text-gray-300 hover:bg-gray-600'
+                }`}
+                title="Report renamed keys and subtrees moved to another parent as moves"
+              >
+                Detect Moves
+              </button>
+              <button
+                onClick={() => setDecodeEmbedded((d) => !d)}
+                className={`px-2 py-1.5 text-xs font-medium rounded transition-colors ${
+                  decodeEmbedded
+                    ? 'bg-violet-600 text-white hover:bg-violet-700'
+                    : 'bg-gray-700 text-gray-300 hover:bg-gray-600'
+                }`}
+                title="Parse strings holding serialized JSON, YAML or base64 JSON and compare their contents"
+              >
+                Decode Strings
+              </button>
+            </div>
+          )}
+
+          {useBackend && (
+            <div className="flex items-center gap-3 px-3 py-2 bg-gray-900/30 border-b border-gray-800 text-xs text-gray-400">
+              <span className="font-medium text-gray-500">Equivalent if</span>
+              <label className="flex items-center gap-1">
+                |Δ| ≤
+                <input
+                  type="text"
+                  inputMode="decimal"
+                  value={absoluteTolerance}
+                  onChange={(e) => setAbsoluteTolerance(e.target.value)}
+                  placeholder="abs"
+                  className="w-16 px-1.5 py-0.5 font-mono bg-gray-950 text-gray-100 rounded border border-gray-700 focus:outline-none focus:border-violet-500"
+                />
+              </label>
+              <label className="flex items-center gap-1">
+                |Δ|/max ≤
+                <input
+                  type="text"
+                  inputMode="decimal"
+                  value={relativeTolerance}
+                  onChange={(e) => setRelativeTolerance(e.target.value)}
+                  placeholder="rel"
+                  className="w-16 px-1.5 py-0.5 font-mono bg-gray-950 text-gray-100 rounded border border-gray-700 focus:outline-none focus:border-violet-500"
+                />
+              </label>
+              {(
+                [
+                  ['coerceStringNumbers', '"42" = 42'],
+                  ['caseInsensitive', 'ignore case'],
+                  ['collapseWhitespace', 'ignore whitespace'],
+                  ['nullEqualsMissing', 'null = missing']
+                ] as const
+              ).map(([flag, label]) => (
+                <label key={flag} className="flex items-center gap-1 cursor-pointer">
+                  <input
+                    type="checkbox"
+                    checked={equivalenceFlags[flag]}
+                    onChange={(e) => setEquivalenceFlags((prev) => ({ ...prev, [flag]: e.target.checked }))}
+                    className="accent-violet-500"
+                  />
+                  {label}
+                </label>
+              ))}
+            </div>
+          )}
+
+          {useBackend && parseWarnings.length > 0 && (
+            <div className="px-3 py-2 bg-yellow-950/30 border-b border-yellow-900/50 text-xs text-yellow-400 space-y-0.5">
+              {parseWarnings.map(({ side, warning }, idx) => (
+                <div key={idx}>
+                  <span className="text-yellow-600">{side === 'left' ? leftLabel : rightLabel}</span>{' '}
+                  <span className="font-mono text-purple-400">{warning.path || '(root)'}</span>: {warning.message}
+                </div>
+              ))}
+            </div>
+          )}
+
           {searchOpen && (
             <div className="flex items-center gap-2 px-3 py-2 bg-gray-900 border-b border-gray-800">
               <input
@@ -689,7 +1315,7 @@ export default function ObjectDiffView({
                   </svg>
                   <p className="text-xs">{emptyMessage}</p>
                 </div>
-              ) : leftError || rightError ? (
+              ) : leftProblem || rightProblem ? (
                 <div className="flex flex-col items-center justify-center py-16 text-yellow-500">
                   <svg
                     className="w-12 h-12 mb-3"
@@ -710,7 +1336,11 @@ export default function ObjectDiffView({
                 <div className="flex flex-col items-center justify-center py-16 text-gray-500">
                   <p className="text-xs">No content to compare</p>
                 </div>
-              ) : stats.added === 0 && stats.removed === 0 && stats.changed === 0 ? (
+              ) : stats.added === 0 &&
+                stats.removed === 0 &&
+                stats.changed === 0 &&
+                stats.moved === 0 &&
+                stats.equivalent === 0 ? (
                 <div className="flex flex-col items-center justify-center py-16 text-green-500">
                   <svg
                     className="w-12 h-12 mb-3"
@@ -761,7 +1391,11 @@ export default function ObjectDiffView({
 
             {diffTree &&
               flatDiffRows.length > 0 &&
-              (stats.added > 0 || stats.removed > 0 || stats.changed > 0) && (
+              (stats.added > 0 ||
+                stats.removed > 0 ||
+                stats.changed > 0 ||
+                stats.moved > 0 ||
+                stats.equivalent > 0) && (
                 <div
                   className="w-3 bg-gray-800 border-l border-gray-700 cursor-pointer relative flex-shrink-0"
                   onClick={handleOverviewClick}
@@ -775,7 +1409,11 @@ export default function ObjectDiffView({
                         ? 'bg-green-500'
                         : row.diffType === 'removed'
                           ? 'bg-red-500'
-                          : 'bg-yellow-500'
+                          : row.diffType === 'moved'
+                            ? 'bg-blue-500'
+                            : row.diffType === 'equivalent'
+                              ? 'bg-teal-500'
+                              : 'bg-yellow-500'
                     return (
                       <div
                         key={`${row.path}-${idx}`}
@@ -3,11 +3,19 @@ import { render, screen, fireEvent } from '@testing-library/react'
 import Sidebar from './Sidebar'
 
 describe('Sidebar', () => {
-  it('renders view buttons with letters J, Y, Md, M, E', () => {
+  it('renders view buttons with letters J, Y, Tm, X, CSV, 3W, LF, T, D, H, Md, M, E', () => {
     const onViewChange = vi.fn()
     render(<Sidebar currentView="json-diff" onViewChange={onViewChange} />)
     expect(screen.getByTitle('JSON Diff')).toHaveTextContent('J')
     expect(screen.getByTitle('YAML Diff')).toHaveTextContent('Y')
+    expect(screen.getByTitle('TOML Diff')).toHaveTextContent('Tm')
+    expect(screen.getByTitle('XML Diff')).toHaveTextContent('X')
+    expect(screen.getByTitle('Table Diff')).toHaveTextContent('CSV')
+    expect(screen.getByTitle('Three-way Merge')).toHaveTextContent('3W')
+    expect(screen.getByTitle('Large File Diff')).toHaveTextContent('LF')
+    expect(screen.getByTitle('Text Diff')).toHaveTextContent('T')
+    expect(screen.getByTitle('Directory Diff')).toHaveTextContent('D')
+    expect(screen.getByTitle('File History')).toHaveTextContent('H')
     expect(screen.getByTitle('Markdown Viewer')).toHaveTextContent('Md')
     expect(screen.getByTitle('Mermaid')).toHaveTextContent('M')
     expect(screen.getByTitle('Epoch Converter')).toHaveTextContent('E')
@@ -1,4 +1,4 @@
-type ViewType = 'json-diff' | 'yaml-diff' | 'markdown' | 'mermaid' | 'epoch'
+type ViewType = 'json-diff' | 'yaml-diff' | 'toml-diff' | 'xml-diff' | 'table-diff' | 'merge' | 'large-diff' | 'text-diff' | 'dir-diff' | 'history' | 'markdown' | 'mermaid' | 'epoch'
 
 interface SidebarProps {
   currentView: ViewType
@@ -9,6 +9,14 @@ interface SidebarProps {
 const views: { id: ViewType; label: string; letter: string; shortcutHint: string }[] = [
   { id: 'json-diff', label: 'JSON Diff', letter: 'J', shortcutHint: 'J' },
   { id: 'yaml-diff', label: 'YAML Diff', letter: 'Y', shortcutHint: 'Y' },
+  { id: 'toml-diff', label: 'TOML Diff', letter: 'Tm', shortcutHint: '⇧T' },
+  { id: 'xml-diff', label: 'XML Diff', letter: 'X', shortcutHint: '⇧X' },
+  { id: 'table-diff', label: 'Table Diff', letter: 'CSV', shortcutHint: '⇧C' },
+  { id: 'merge', label: 'Three-way Merge', letter: '3W', shortcutHint: 'G' },
+  { id: 'large-diff', label: 'Large File Diff', letter: 'LF', shortcutHint: 'L' },
+  { id: 'text-diff', label: 'Text Diff', letter: 'T', shortcutHint: 'T' },
+  { id: 'dir-diff', label: 'Directory Diff', letter: 'D', shortcutHint: 'D' },
+  { id: 'history', label: 'File History', letter: 'H', shortcutHint: '⇧H' },
   { id: 'markdown', label: 'Markdown Viewer', letter: 'Md', shortcutHint: '⇧M' },
   { id: 'mermaid', label: 'Mermaid', letter: 'M', shortcutHint: 'M' },
   { id: 'epoch', label: 'Epoch Converter', letter: 'E', shortcutHint: 'E' },
@@ -0,0 +1,260 @@
+import { useState, useCallback, useEffect } from 'react'
+import type { DiffType } from '../utils/diffTree'
+import {
+  type TableDiff,
+  type TableDialect,
+  type TableInfo,
+  type TableRowDiff,
+  diffTables,
+  isTauriEnv,
+  pickFilePath
+} from '../utils/backend'
+
+type Side = 'left' | 'right'
+
+/** Rows rendered at once; large files are summarized past this. */
+const MAX_ROWS = 2000
+
+const tableFilters = [
+  { name: 'CSV / TSV', extensions: ['csv', 'tsv', 'tab', 'txt'] },
+  { name: 'All files', extensions: ['*'] }
+]
+
+const delimiterNames: Record<string, string> = {
+  ',': 'comma',
+  '\t': 'tab',
+  ';': 'semicolon',
+  '|': 'pipe'
+}
+
+const rowClasses: Partial<Record<DiffType, string>> = {
+  added: 'bg-green-950/30',
+  removed: 'bg-red-950/30'
+}
+
+const statusMarks: Partial<Record<DiffType, string>> = {
+  added: '+',
+  removed: '-',
+  changed: '~'
+}
+
+const markClasses: Partial<Record<DiffType, string>> = {
+  added: 'text-green-400',
+  removed: 'text-red-400',
+  changed: 'text-yellow-400'
+}
+
+function fileName(path: string | null): string {
+  return path ? (path.split(/[/\\]/).pop() ?? path) : 'Choose file…'
+}
+
+function describeDialect({ delimiter, quote, encoding, bom }: TableDialect): string {
+  const parts = [delimiterNames[delimiter] ?? `'${delimiter}'`, `${quote === "'" ? 'single' : 'double'} quotes`, encoding]
+  if (bom) parts.push('BOM')
+  return parts.join(' · ')
+}
+
+function Cell({ row, index }: { row: TableRowDiff; index: number }) {
+  const left = row.left?.[index] ?? ''
+  const right = row.right?.[index] ?? ''
+  if (row.changed.includes(index)) {
+    return (
+      <td className="px-2 py-1 bg-yellow-950/20">
+        <span className="text-red-400 line-through">{left}</span> <span className="text-green-400">{right}</span>
+      </td>
+    )
+  }
+  const value = row.status === 'removed' ? left : right
+  const color = row.status === 'added' ? 'text-green-400' : row.status === 'removed' ? 'text-red-400' : 'text-gray-300'
+  return <td className={`px-2 py-1 ${color}`}>{value}</td>
+}
+
+export default function TableDiffView() {
+  const available = isTauriEnv()
+  const [files, setFiles] = useState<Record<Side, string | null>>({ left: null, right: null })
+  const [keyColumns, setKeyColumns] = useState<string[]>([])
+  const [changesOnly, setChangesOnly] = useState(true)
+  const [result, setResult] = useState<TableDiff | null>(null)
+  const [compared, setCompared] = useState(false)
+  const [running, setRunning] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+
+  const handlePick = useCallback(async (side: Side) => {
+    const path = await pickFilePath(tableFilters)
+    if (!path) return
+    setFiles((prev) => ({ ...prev, [side]: path }))
+    setResult(null)
+    setCompared(false)
+  }, [])
+
+  const runDiff = useCallback(async () => {
+    if (!files.left || !files.right) return
+    setRunning(true)
+    setError(null)
+    try {
+      setResult(await diffTables(files.left, files.right, { keyColumns, includeUnchanged: !changesOnly }))
+    } catch (e) {
+      setResult(null)
+      setError(e instanceof Error ? e.message : String(e))
+    } finally {
+      setRunning(false)
+    }
+  }, [files, keyColumns, changesOnly])
+
+  // Once compared, changing the key or the filter reruns the diff.
+  useEffect(() => {
+    if (compared) runDiff()
+  }, [compared, runDiff])
+
+  const toggleKey = useCallback((name: string) => {
+    setKeyColumns((prev) => (prev.includes(name) ? prev.filter((k) => k !== name) : [...prev, name]))
+  }, [])
+
+  if (!available) {
+    return (
+      <div className="max-w-[1800px] mx-auto px-4 py-6 w-full">
+        <h1 className="text-lg font-medium text-gray-200 mb-4">Table Diff</h1>
+        <p className="text-xs text-gray-500">Table diff is only available in the desktop app.</p>
+      </div>
+    )
+  }
+
+  // Only columns present in both files can pair rows.
+  const keyCandidates = result?.columns.filter((c) => c.status === 'unchanged').map((c) => c.name) ?? []
+  const shown = result?.rows.slice(0, MAX_ROWS) ?? []
+
+  const renderInfo = (label: string, info: TableInfo) => (
+    <span className="text-gray-500">
+      {label}: {info.rows} rows · {describeDialect(info.dialect)}
+    </span>
+  )
+
+  return (
+    <div className="max-w-[1800px] mx-auto px-4 py-6 w-full space-y-4">
+      <div className="flex items-center justify-between">
+        <h1 className="text-lg font-medium text-gray-200">Table Diff</h1>
+        <button
+          onClick={() => setChangesOnly((v) => !v)}
+          className={`px-3 py-1.5 text-xs font-medium rounded-md transition-colors ${
+            changesOnly
+              ? 'bg-violet-600 text-white'
+              : 'bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700'
+          }`}
+        >
+          Changes Only
+        </button>
+      </div>
+
+      <div className="flex items-center gap-2">
+        {(['left', 'right'] as const).map((side) => (
+          <button
+            key={side}
+            onClick={() => handlePick(side)}
+            className="px-3 py-1.5 text-xs font-medium rounded-md bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700 transition-colors max-w-[300px] truncate"
+            title={files[side] ?? undefined}
+          >
+            {side === 'left' ? 'Original: ' : 'Modified: '}
+            {fileName(files[side])}
+          </button>
+        ))}
+        <button
+          onClick={() => (compared ? runDiff() : setCompared(true))}
+          disabled={!files.left || !files.right || running}
+          className="px-3 py-1.5 text-xs font-medium rounded-md bg-violet-600 text-white hover:bg-violet-500 transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+        >
+          {running ? 'Comparing…' : 'Compare'}
+        </button>
+      </div>
+
+      {keyCandidates.length > 0 && (
+        <div className="flex flex-wrap items-center gap-2 text-xs">
+          <span className="text-gray-500">Key columns:</span>
+          {keyCandidates.map((name) => (
+            <label key={name} className="flex items-center gap-1 text-gray-300">
+              <input
+                type="checkbox"
+                checked={keyColumns.includes(name)}
+                onChange={() => toggleKey(name)}
+                className="accent-violet-600"
+              />
+              {name}
+            </label>
+          ))}
+          {keyColumns.length === 0 && <span className="text-gray-500">(rows paired by position)</span>}
+        </div>
+      )}
+
+      {error && (
+        <div className="p-2 text-xs bg-red-950/50 border border-red-900/50 rounded-md text-red-400">{error}</div>
+      )}
+
+      {result && result.duplicateKeys.length > 0 && (
+        <div className="p-2 text-xs bg-yellow-950/40 border border-yellow-900/50 rounded-md text-yellow-400">
+          Keys that match more than one row, paired in file order: {result.duplicateKeys.join(', ')}
+        </div>
+      )}
+
+      {result && (
+        <div className="rounded-lg border border-gray-800 overflow-hidden bg-gray-950">
+          <div className="flex flex-wrap items-center gap-3 px-3 py-2 bg-gray-900/50 border-b border-gray-800 text-xs">
+            <span className="text-green-400">{result.stats.added} added</span>
+            <span className="text-red-400">{result.stats.removed} removed</span>
+            <span className="text-yellow-400">
+              {result.stats.changed} changed ({result.stats.changedCells} cells)
+            </span>
+            <span className="text-gray-500">{result.stats.unchanged} unchanged</span>
+            <span className="ml-auto flex gap-3">
+              {renderInfo('Original', result.left)}
+              {renderInfo('Modified', result.right)}
+            </span>
+          </div>
+          {result.rows.length === 0 ? (
+            <p className="px-3 py-2 text-xs text-green-400">No differences</p>
+          ) : (
+            <div className="max-h-[600px] overflow-auto">
+              <table className="w-full text-xs font-mono">
+                <thead className="sticky top-0 bg-gray-900">
+                  <tr className="text-left">
+                    <th className="w-4 px-2 py-1" />
+                    <th className="px-2 py-1 text-gray-500">{keyColumns.length ? keyColumns.join(' / ') : '#'}</th>
+                    {result.columns.map((column) => (
+                      <th
+                        key={column.name}
+                        className={`px-2 py-1 ${markClasses[column.status] ?? 'text-gray-400'}`}
+                        title={column.status === 'unchanged' ? undefined : `Column ${column.status}`}
+                      >
+                        {column.name}
+                      </th>
+                    ))}
+                  </tr>
+                </thead>
+                <tbody>
+                  {shown.map((row, i) => (
+                    <tr
+                      key={`${row.key}:${i}`}
+                      className={`border-t border-gray-800/60 ${rowClasses[row.status] ?? ''}`}
+                      title={[row.leftLine && `original line ${row.leftLine}`, row.rightLine && `modified line ${row.rightLine}`]
+                        .filter(Boolean)
+                        .join(', ')}
+                    >
+                      <td className={`px-2 py-1 ${markClasses[row.status] ?? ''}`}>{statusMarks[row.status] ?? ''}</td>
+                      <td className="px-2 py-1 text-gray-500">{row.key}</td>
+                      {result.columns.map((column, index) => (
+                        <Cell key={column.name} row={row} index={index} />
+                      ))}
+                    </tr>
+                  ))}
+                </tbody>
+              </table>
+              {result.rows.length > shown.length && (
+                <p className="px-3 py-2 text-xs text-gray-500">
+                  Showing the first {shown.length} of {result.rows.length} rows.
+                </p>
+              )}
+            </div>
+          )}
+        </div>
+      )}
+    </div>
+  )
+}
@@ -0,0 +1,326 @@
+import { useState, useCallback, useEffect } from 'react'
+import {
+  type FilePatch,
+  type LineAlgorithm,
+  type TextDiffResult,
+  type TextDiffRow,
+  type TextLine,
+  type TextRowKind,
+  diffText,
+  isTauriEnv,
+  openTextFile,
+  parseUnifiedDiff,
+  saveTextFile,
+  writeUnifiedDiff
+} from '../utils/backend'
+import GitRevisionPanel, { type RevisionPair } from './GitRevisionPanel'
+
+type Side = 'left' | 'right'
+
+function cellClass(kind: TextRowKind, side: Side): string {
+  switch (kind) {
+    case 'added':
+      return side === 'right' ? 'bg-green-900/30' : 'bg-gray-800/30'
+    case 'removed':
+      return side === 'left' ? 'bg-red-900/30' : 'bg-gray-800/30'
+    case 'changed':
+      return side === 'left' ? 'bg-red-900/20' : 'bg-green-900/20'
+    default:
+      return ''
+  }
+}
+
+function renderLine(line: TextLine | undefined, side: Side) {
+  if (!line) return null
+  if (!line.spans) return line.text
+  return line.spans.map((span, idx) =>
+    span.changed ? (
+      <span key={idx} className={side === 'left' ? 'bg-red-700/50 rounded-sm' : 'bg-green-700/50 rounded-sm'}>
+        {span.text}
+      </span>
+    ) : (
+      span.text
+    )
+  )
+}
+
+function renderRow(row: TextDiffRow, key: number) {
+  return (
+    <tr key={key} className="border-b border-gray-800/50 align-top">
+      <td className={`px-2 text-right text-xs text-gray-600 select-none ${cellClass(row.kind, 'left')}`}>
+        {row.left?.number}
+      </td>
+      <td className={`px-2 whitespace-pre-wrap break-all text-gray-200 ${cellClass(row.kind, 'left')}`}>
+        {renderLine(row.left, 'left')}
+      </td>
+      <td className={`px-2 text-right text-xs text-gray-600 select-none border-l border-gray-800 ${cellClass(row.kind, 'right')}`}>
+        {row.right?.number}
+      </td>
+      <td className={`px-2 whitespace-pre-wrap break-all text-gray-200 ${cellClass(row.kind, 'right')}`}>
+        {renderLine(row.right, 'right')}
+      </td>
+    </tr>
+  )
+}
+
+function patchTitle(file: FilePatch): string {
+  if (!file.oldPath) return `${file.newPath} (new file)`
+  if (!file.newPath) return `${file.oldPath} (deleted)`
+  return file.oldPath === file.newPath ? file.newPath : `${file.oldPath} → ${file.newPath}`
+}
+
+function renderStats(stats: { added: number; removed: number; changed: number }) {
+  return (
+    <>
+      {stats.added > 0 && <span className="text-green-400">+{stats.added}</span>}
+      {stats.removed > 0 && <span className="text-red-400">-{stats.removed}</span>}
+      {stats.changed > 0 && <span className="text-yellow-400">~{stats.changed}</span>}
+    </>
+  )
+}
+
+const allFiles = [{ name: 'All', extensions: ['*'] }]
+
+const columns = (
+  <colgroup>
+    <col className="w-12" />
+    <col />
+    <col className="w-12" />
+    <col />
+  </colgroup>
+)
+
+export default function TextDiffView() {
+  const available = isTauriEnv()
+  const [texts, setTexts] = useState<Record<Side, string>>({ left: '', right: '' })
+  const [labels, setLabels] = useState<Record<Side, string>>({ left: 'Original', right: 'Modified' })
+  const [algorithm, setAlgorithm] = useState<LineAlgorithm>('myers')
+  const [ignoreWhitespace, setIgnoreWhitespace] = useState(false)
+  const [result, setResult] = useState<TextDiffResult | null>(null)
+  const [error, setError] = useState<string | null>(null)
+  const [patch, setPatch] = useState<{ name: string; files: FilePatch[] } | null>(null)
+  const [gitOpen, setGitOpen] = useState(false)
+
+  useEffect(() => {
+    if (!available) return
+    if (!texts.left && !texts.right) {
+      setResult(null)
+      setError(null)
+      return
+    }
+    let cancelled = false
+    const timer = setTimeout(() => {
+      diffText(texts.left, texts.right, { algorithm, ignoreWhitespace })
+        .then((output) => {
+          if (cancelled) return
+          setResult(output)
+          setError(null)
+        })
+        .catch((e) => {
+          if (cancelled) return
+          setResult(null)
+          setError(e instanceof Error ? e.message : String(e))
+        })
+    }, 150)
+    return () => {
+      cancelled = true
+      clearTimeout(timer)
+    }
+  }, [available, texts, algorithm, ignoreWhitespace])
+
+  const handleOpen = useCallback(async (side: Side) => {
+    try {
+      const file = await openTextFile(allFiles)
+      if (!file) return
+      setTexts((prev) => ({ ...prev, [side]: file.content }))
+      setLabels((prev) => ({ ...prev, [side]: file.name }))
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e))
+    }
+  }, [])
+
+  const loadRevisions = useCallback((pair: RevisionPair) => {
+    setTexts({ left: pair.left, right: pair.right })
+    setLabels({ left: pair.leftLabel, right: pair.rightLabel })
+    setPatch(null)
+  }, [])
+
+  const handleOpenPatch = useCallback(async () => {
+    try {
+      const file = await openTextFile([
+        { name: 'Patch', extensions: ['diff', 'patch'] },
+        { name: 'All', extensions: ['*'] }
+      ])
+      if (!file) return
+      setPatch({ name: file.name, files: await parseUnifiedDiff(file.content) })
+      setError(null)
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e))
+    }
+  }, [])
+
+  const handleExport = useCallback(async () => {
+    try {
+      const content = await writeUnifiedDiff(texts.left, texts.right, labels.left, labels.right, {
+        algorithm,
+        ignoreWhitespace
+      })
+      await saveTextFile(content, 'changes.diff', [{ name: 'Diff', extensions: ['diff', 'patch'] }])
+    } catch (e) {
+      setError(e instanceof Error ? e.message : String(e))
+    }
+  }, [texts, labels, algorithm, ignoreWhitespace])
+
+  if (!available) {
+    return (
+      <div className="max-w-[1800px] mx-auto px-4 py-6 w-full">
+        <h1 className="text-lg font-medium text-gray-200 mb-4">Text Diff</h1>
+        <p className="text-xs text-gray-500">Text diff is only available in the desktop app.</p>
+      </div>
+    )
+  }
+
+  const noChanges = result && result.added === 0 && result.removed === 0 && result.changed === 0
+
+  return (
+    <div className="max-w-[1800px] mx-auto px-4 py-6 w-full space-y-4">
+      <div className="flex items-center justify-between">
+        <h1 className="text-lg font-medium text-gray-200">Text Diff</h1>
+        <div className="flex items-center gap-2">
+          {(['myers', 'patience'] as const).map((a) => (
+            <button
+              key={a}
+              onClick={() => setAlgorithm(a)}
+              className={`px-3 py-1.5 text-xs font-medium rounded-md transition-colors ${
+                algorithm === a
+                  ? 'bg-violet-600 text-white'
+                  : 'bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700'
+              }`}
+              title={a === 'patience' ? 'Anchor on unique lines; reads better for moved blocks and reformatted code' : 'Minimal line diff'}
+            >
+              {a === 'myers' ? 'Myers' : 'Patience'}
+            </button>
+          ))}
+          <button
+            onClick={() => setIgnoreWhitespace((v) => !v)}
+            className={`px-3 py-1.5 text-xs font-medium rounded-md transition-colors ${
+              ignoreWhitespace
+                ? 'bg-violet-600 text-white'
+                : 'bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700'
+            }`}
+          >
+            Ignore Whitespace
+          </button>
+          <button
+            onClick={() => setGitOpen((v) => !v)}
+            className={`px-3 py-1.5 text-xs font-medium rounded-md transition-colors ${
+              gitOpen
+                ? 'bg-violet-600 text-white'
+                : 'bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700'
+            }`}
+            title="Compare a file against another git revision: HEAD, a branch, a commit or a stash"
+          >
+            Git
+          </button>
+          <button
+            onClick={handleOpenPatch}
+            className="px-3 py-1.5 text-xs font-medium rounded-md bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700 transition-colors"
+            title="View a .diff or .patch file"
+          >
+            Open Patch
+          </button>
+          <button
+            onClick={handleExport}
+            disabled={!result || !!noChanges}
+            className="px-3 py-1.5 text-xs font-medium rounded-md bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700 transition-colors disabled:opacity-50 disabled:cursor-not-allowed"
+            title="Save the changes as a unified diff"
+          >
+            Export Diff
+          </button>
+        </div>
+      </div>
+
+      {gitOpen && <GitRevisionPanel filters={allFiles} onLoad={loadRevisions} onClose={() => setGitOpen(false)} />}
+
+      {patch ? (
+        <div className="space-y-4">
+          <div className="flex items-center justify-between">
+            <span className="text-xs font-medium text-gray-300 truncate">
+              {patch.name} · {patch.files.length} {patch.files.length === 1 ? 'file' : 'files'}
+            </span>
+            <button
+              onClick={() => setPatch(null)}
+              className="px-3 py-1.5 text-xs font-medium rounded-md bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700 transition-colors"
+            >
+              Close Patch
+            </button>
+          </div>
+          {patch.files.map((file, fileIdx) => (
+            <div key={fileIdx} className="rounded-lg border border-gray-800 overflow-hidden bg-gray-950">
+              <div className="flex items-center gap-3 px-3 py-2 bg-gray-900/50 border-b border-gray-800 text-xs">
+                <span className="font-mono text-gray-300 truncate">{patchTitle(file)}</span>
+                {renderStats(file)}
+              </div>
+              <table className="w-full table-fixed font-mono text-sm">
+                {columns}
+                {file.hunks.map((hunk, hunkIdx) => (
+                  <tbody key={hunkIdx}>
+                    <tr className="border-b border-gray-800/50">
+                      <td colSpan={4} className="px-2 py-0.5 text-xs text-blue-400 bg-blue-950/30">
+                        {hunk.header}
+                      </td>
+                    </tr>
+                    {hunk.rows.map(renderRow)}
+                  </tbody>
+                ))}
+              </table>
+            </div>
+          ))}
+        </div>
+      ) : (
+        <div className="grid gap-4 grid-cols-1 lg:grid-cols-2">
+          {(['left', 'right'] as const).map((side) => (
+            <div key={side} className="space-y-2">
+              <div className="flex items-center justify-between">
+                <span className="text-xs font-medium text-gray-300 truncate">{labels[side]}</span>
+                <button
+                  onClick={() => handleOpen(side)}
+                  className="px-3 py-1.5 text-xs font-medium rounded-md bg-gray-800 text-gray-300 hover:bg-gray-700 border border-gray-700 transition-colors"
+                >
+                  Open
+                </button>
+              </div>
+              <textarea
+                value={texts[side]}
+                onChange={(e) => setTexts((prev) => ({ ...prev, [side]: e.target.value }))}
+                placeholder="Paste text or open a file"
+                spellCheck={false}
+                className="w-full h-[240px] p-3 font-mono text-sm rounded-lg border border-gray-800 bg-gray-950 text-gray-100 placeholder-gray-600 focus:outline-none focus:ring-2 focus:ring-violet-500 resize-none"
+              />
+            </div>
+          ))}
+        </div>
+      )}
+
+      {error && (
+        <div className="p-2 text-xs bg-red-950/50 border border-red-900/50 rounded-md text-red-400">{error}</div>
+      )}
+
+      {!patch && result && (
+        <div className="rounded-lg border border-gray-800 overflow-hidden bg-gray-950">
+          <div className="flex items-center gap-3 px-3 py-2 bg-gray-900/50 border-b border-gray-800 text-xs">
+            <span className="font-medium text-gray-500">Diff View</span>
+            {renderStats(result)}
+            {noChanges && <span className="text-green-400">No differences</span>}
+          </div>
+          <div className="max-h-[600px] overflow-auto">
+            <table className="w-full table-fixed font-mono text-sm">
+              {columns}
+              <tbody>{result.rows.map(renderRow)}</tbody>
+            </table>
+          </div>
+        </div>
+      )}
+    </div>
+  )
+}
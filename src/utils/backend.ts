@@ -0,0 +1,495 @@
+import type { DiffNode, DiffType } from './diffTree'
+
+export type DocumentFormat = 'json' | 'yaml' | 'toml' | 'xml'
+
+export type ArrayMode = 'index' | 'lcs' | 'multiset'
+
+/** Pairs elements of arrays matching `path` (e.g. `spec.containers[*]`) by their `key` field. */
+export interface ArrayKey {
+  path: string
+  key: string
+}
+
+/** Splits a comma or newline separated list, dropping blank entries. */
+export function splitList(text: string): string[] {
+  return text
+    .split(/[,\n]/)
+    .map((entry) => entry.trim())
+    .filter(Boolean)
+}
+
+/** Parses `path=key` pairs such as `spec.containers[*]=name`. */
+export function parseArrayKeys(text: string): ArrayKey[] {
+  return splitList(text)
+    .map((entry) => entry.split('='))
+    .filter((parts) => parts.length === 2 && parts[0].trim() && parts[1].trim())
+    .map(([path, key]) => ({ path: path.trim(), key: key.trim() }))
+}
+
+/** Loosened primitive comparisons; matches are reported as `equivalent`. */
+export interface EquivalenceRules {
+  absoluteTolerance?: number
+  relativeTolerance?: number
+  coerceStringNumbers?: boolean
+  caseInsensitive?: boolean
+  /** Strings equal after trimming and collapsing runs of whitespace, e.g. reflowed XML text. */
+  collapseWhitespace?: boolean
+  nullEqualsMissing?: boolean
+}
+
+export interface DiffOptions {
+  arrayMode?: ArrayMode
+  arrayKeys?: ArrayKey[]
+  /** Patterns of arrays compared as multisets (`$..tags`): order is ignored, only missing/extra elements count. */
+  unorderedPaths?: string[]
+  /** JSONPath or glob patterns (`$..updatedAt`, `metadata.resourceVersion`) left out of the diff. */
+  ignorePaths?: string[]
+  equivalence?: EquivalenceRules
+  /** Report renamed keys and subtrees moved to another parent as moved instead of removed + added. */
+  detectMoves?: boolean
+  /** Parse strings holding serialized JSON, YAML or base64 JSON and compare them structurally. */
+  decodeEmbedded?: boolean
+}
+
+export function isTauriEnv(): boolean {
+  if (typeof window === 'undefined') return false
+  const w = window as unknown as { __TAURI_INTERNALS__?: unknown; __TAURI__?: unknown }
+  return w.__TAURI_INTERNALS__ != null || w.__TAURI__ != null
+}
+
+/** 1-based, inclusive source lines. */
+export interface LineRange {
+  start: number
+  end: number
+}
+
+/** Structured parse failure; positions are missing when the parser couldn't tell. */
+export interface ParseError {
+  message: string
+  line?: number
+  column?: number
+  offset?: number
+  expected?: string
+}
+
+/** Validates `text` and maps each diff path to the source lines it spans. Rejects with a `ParseError`. */
+export async function parseDocument(text: string, format: DocumentFormat): Promise<Record<string, LineRange>> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  try {
+    return await invoke<Record<string, LineRange>>('parse_document', { text, format })
+  } catch (e) {
+    if (typeof e === 'object' && e !== null && 'message' in e) throw e as ParseError
+    throw { message: String(e) } satisfies ParseError
+  }
+}
+
+/** Something the parser resolved silently: a repeated key or a number it couldn't hold exactly. */
+export interface ParseWarning {
+  kind: 'duplicateKey' | 'precisionLoss'
+  path: string
+  message: string
+}
+
+export interface DiffOutput {
+  root: DiffNode | null
+  leftWarnings: ParseWarning[]
+  rightWarnings: ParseWarning[]
+}
+
+export async function diffDocuments(
+  left: string,
+  right: string,
+  format: DocumentFormat,
+  options: DiffOptions = {}
+): Promise<DiffOutput> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<DiffOutput>('diff_documents', { left, right, format, options })
+}
+
+/** Changes in a diff, counted the way the diff header counts them. */
+export interface DiffStats {
+  added: number
+  removed: number
+  changed: number
+  moved: number
+  equivalent: number
+}
+
+/** A file or directory in a directory diff. `children` is only set on directories. */
+export interface DirDiffNode {
+  name: string
+  /** Relative to both roots, `/`-separated; empty for the roots. */
+  path: string
+  status: DiffType
+  /** Summed over the files of a directory. */
+  stats: DiffStats
+  format?: DocumentFormat
+  error?: string
+  children?: DirDiffNode[]
+}
+
+/** Diffs every JSON, YAML, TOML and XML file under two directories, paired by relative path. */
+export async function diffDirectories(left: string, right: string, options: DiffOptions = {}): Promise<DirDiffNode> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<DirDiffNode>('diff_directories', { left, right, options })
+}
+
+export interface TableDiffOptions {
+  /** Header names whose values identify a row; rows are paired by position without any. */
+  keyColumns?: string[]
+  /** Also return rows that didn't change; they're only counted otherwise. */
+  includeUnchanged?: boolean
+}
+
+/** How a CSV/TSV file was read. `encoding` is the WHATWG name, e.g. `UTF-8`. */
+export interface TableDialect {
+  delimiter: string
+  quote: string
+  encoding: string
+  bom: boolean
+}
+
+export interface TableInfo {
+  dialect: TableDialect
+  headers: string[]
+  /** Data rows, not counting the header. */
+  rows: number
+}
+
+export interface TableColumn {
+  name: string
+  status: DiffType
+}
+
+/** A pair of rows, or a row on one side only. Cells follow `TableDiff.columns`. */
+export interface TableRowDiff {
+  status: DiffType
+  key: string
+  leftLine?: number
+  rightLine?: number
+  left?: string[]
+  right?: string[]
+  /** Indexes of the cells that differ. */
+  changed: number[]
+}
+
+export interface TableStats {
+  added: number
+  removed: number
+  changed: number
+  unchanged: number
+  changedCells: number
+}
+
+export interface TableDiff {
+  left: TableInfo
+  right: TableInfo
+  columns: TableColumn[]
+  rows: TableRowDiff[]
+  stats: TableStats
+  duplicateKeys: string[]
+}
+
+/** Row diff of two CSV/TSV files; encoding, delimiter and quoting are sniffed per file. */
+export async function diffTables(left: string, right: string, options: TableDiffOptions = {}): Promise<TableDiff> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<TableDiff>('diff_tables', { left, right, options })
+}
+
+export type PatchKind = 'jsonPatch' | 'mergePatch'
+
+export async function createPatch(
+  left: string,
+  right: string,
+  format: DocumentFormat,
+  kind: PatchKind
+): Promise<string> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<string>('create_patch', { left, right, format, kind })
+}
+
+/** Asks for a destination with the save dialog and writes `content` there. Returns false if cancelled. */
+export async function saveTextFile(
+  content: string,
+  defaultPath: string,
+  filters: { name: string; extensions: string[] }[]
+): Promise<boolean> {
+  const { save } = await import('@tauri-apps/plugin-dialog')
+  const { invoke } = await import('@tauri-apps/api/core')
+  const path = await save({ defaultPath, filters })
+  if (!path) return false
+  await invoke('write_file_content', { path, content })
+  return true
+}
+
+export async function applyPatch(
+  document: string,
+  format: DocumentFormat,
+  patch: string,
+  kind?: PatchKind
+): Promise<string> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<string>('apply_patch', { document, format, patch, kind })
+}
+
+export interface CanonicalForm {
+  /** RFC 8785 (JCS) serialization. */
+  canonical: string
+  /** Hex SHA-256 of `canonical`. */
+  sha256: string
+}
+
+/** Reformats a document in the backend; TOML and XML keep their comments and layout. Rejects with a `ParseError`. */
+export async function formatDocument(text: string, format: DocumentFormat): Promise<string> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  try {
+    return await invoke<string>('format_document', { text, format })
+  } catch (e) {
+    if (typeof e === 'object' && e !== null && 'message' in e) throw e as ParseError
+    throw { message: String(e) } satisfies ParseError
+  }
+}
+
+/** Canonical JSON and digest of a document, or of the subtree at a display path such as `spec.containers[0]`. */
+export async function canonicalizeDocument(
+  text: string,
+  format: DocumentFormat,
+  path?: string
+): Promise<CanonicalForm> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<CanonicalForm>('canonicalize_document', { text, format, path })
+}
+
+export type LineAlgorithm = 'myers' | 'patience'
+
+export interface TextDiffOptions {
+  algorithm?: LineAlgorithm
+  /** Lines that only differ in whitespace count as equal. */
+  ignoreWhitespace?: boolean
+}
+
+export type TextRowKind = 'added' | 'removed' | 'changed' | 'unchanged'
+
+export interface TextLine {
+  number: number
+  text: string
+  /** Word-level highlights, only on changed rows. */
+  spans?: { text: string; changed: boolean }[]
+}
+
+export interface TextDiffRow {
+  kind: TextRowKind
+  left?: TextLine
+  right?: TextLine
+}
+
+export interface TextDiffResult {
+  rows: TextDiffRow[]
+  added: number
+  removed: number
+  changed: number
+}
+
+export async function diffText(left: string, right: string, options: TextDiffOptions = {}): Promise<TextDiffResult> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<TextDiffResult>('diff_text', { left, right, options })
+}
+
+/** One file's changes from a `.diff`/`.patch` file. A missing path means the file was created or deleted. */
+export interface FilePatch {
+  oldPath?: string
+  newPath?: string
+  hunks: { header: string; rows: TextDiffRow[] }[]
+  added: number
+  removed: number
+  changed: number
+}
+
+export async function parseUnifiedDiff(text: string): Promise<FilePatch[]> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<FilePatch[]>('parse_unified_diff', { text })
+}
+
+/** Unified diff of two texts, empty when they match. */
+export async function writeUnifiedDiff(
+  left: string,
+  right: string,
+  leftName: string,
+  rightName: string,
+  options: TextDiffOptions = {}
+): Promise<string> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<string>('write_unified_diff', { left, right, leftName, rightName, options })
+}
+
+/** Picks a file with the open dialog and reads it. Returns null if cancelled. */
+export async function openTextFile(
+  filters: { name: string; extensions: string[] }[]
+): Promise<{ path: string; name: string; content: string } | null> {
+  const { open } = await import('@tauri-apps/plugin-dialog')
+  const { invoke } = await import('@tauri-apps/api/core')
+  const selected = await open({ multiple: false, filters })
+  if (!selected || typeof selected !== 'string') return null
+  const content = await invoke<string>('read_file_content', { path: selected })
+  return { path: selected, name: selected.split(/[/\\]/).pop() ?? selected, content }
+}
+
+/** A commit that touched a file. `path` is the file's repository path at that commit, which differs before a rename. */
+export interface GitCommit {
+  hash: string
+  shortHash: string
+  author: string
+  email: string
+  /** Author date, seconds since the epoch. */
+  timestamp: number
+  subject: string
+  path: string
+}
+
+export async function gitFileLog(path: string, limit?: number): Promise<GitCommit[]> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<GitCommit[]>('git_file_log', { path, limit })
+}
+
+/** HEAD, branches, tags and stashes of the repository holding `path`. */
+export async function gitRefs(path: string): Promise<string[]> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<string[]>('git_refs', { path })
+}
+
+/** Contents of the file at `path` as of `rev` (`HEAD`, `main`, a hash, `stash@{0}`). */
+export async function gitShowFile(path: string, rev: string, atPath?: string): Promise<string> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<string>('git_show_file', { path, rev, atPath })
+}
+
+/** A commit in a file's history with what it changed, compared with the file's previous version. */
+export interface GitRevision extends GitCommit {
+  /** The commit compared with; absent for the file's first. */
+  previousHash?: string
+  previousPath?: string
+  stats: DiffStats
+  /** The first changed rows, by display path. */
+  changes: { path: string; diffType: DiffType }[]
+  error?: string
+}
+
+export async function gitFileHistory(
+  path: string,
+  format: DocumentFormat,
+  options: DiffOptions = {},
+  limit?: number
+): Promise<GitRevision[]> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<GitRevision[]>('git_file_history', { path, format, options, limit })
+}
+
+export type MergeSide = 'base' | 'ours' | 'theirs'
+
+/** A path changed differently on both sides. A missing side deleted the entry. */
+export interface MergeConflict {
+  path: string
+  pointer: string
+  base?: unknown
+  ours?: unknown
+  theirs?: unknown
+  resolution: MergeSide
+}
+
+export interface MergeOutput {
+  merged: string
+  conflicts: MergeConflict[]
+}
+
+/** Three-way merge. Elements of arrays matching `arrayKeys` are paired by identity field, others by content. */
+export async function mergeDocuments(
+  base: string,
+  ours: string,
+  theirs: string,
+  format: DocumentFormat,
+  resolutions: Record<string, MergeSide> = {},
+  arrayKeys: ArrayKey[] = []
+): Promise<MergeOutput> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<MergeOutput>('merge_documents', { base, ours, theirs, format, arrayKeys, resolutions })
+}
+
+/** One leaf of a streaming diff: a primitive or empty container present on either side. */
+export interface StreamDiffRow {
+  path: string
+  depth: number
+  diffType: DiffType
+  left?: unknown
+  right?: unknown
+}
+
+export interface StreamDiffSummary {
+  rows: number
+  changes: number
+  added: number
+  removed: number
+  changed: number
+  /** Present when the files were read as JSON Lines; counts whole records. */
+  records?: { total: number; added: number; removed: number; changed: number }
+}
+
+export interface StreamDiffOptions {
+  /** Reads each line as a record instead of the whole file as one document. */
+  jsonLines?: boolean
+  /** Pairs JSON Lines records by this field (a dotted path reaches nested objects) instead of by position. */
+  keyField?: string
+}
+
+export type StreamDiffEvent =
+  | { event: 'progress'; phase: 'readingLeft' | 'readingRight' | 'comparing'; done: number; total: number }
+  | { event: 'rows'; start: number; changesOnly: boolean; rows: StreamDiffRow[] }
+
+/**
+ * Diffs two JSON or JSON Lines files on disk without loading them into the webview. Progress and the
+ * row windows requested with `requestStreamDiffRows` arrive through `onEvent`.
+ */
+export async function startStreamDiff(
+  leftPath: string,
+  rightPath: string,
+  onEvent: (event: StreamDiffEvent) => void,
+  options: StreamDiffOptions = {}
+): Promise<{ session: number; summary: StreamDiffSummary }> {
+  const { invoke, Channel } = await import('@tauri-apps/api/core')
+  const events = new Channel<StreamDiffEvent>()
+  events.onmessage = onEvent
+  return invoke('stream_diff', { leftPath, rightPath, options, events })
+}
+
+export async function requestStreamDiffRows(
+  session: number,
+  start: number,
+  count: number,
+  changesOnly: boolean
+): Promise<void> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  await invoke('stream_diff_rows', { session, start, count, changesOnly })
+}
+
+export async function closeStreamDiff(session: number): Promise<void> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  await invoke('close_stream_diff', { session })
+}
+
+export async function readTextFile(path: string): Promise<string> {
+  const { invoke } = await import('@tauri-apps/api/core')
+  return invoke<string>('read_file_content', { path })
+}
+
+/** Picks a directory with the open dialog. Returns null if cancelled. */
+export async function pickDirectory(): Promise<string | null> {
+  const { open } = await import('@tauri-apps/plugin-dialog')
+  const selected = await open({ directory: true, multiple: false })
+  return typeof selected === 'string' ? selected : null
+}
+
+/** Picks a file with the open dialog without reading it. Returns null if cancelled. */
+export async function pickFilePath(filters: { name: string; extensions: string[] }[]): Promise<string | null> {
+  const { open } = await import('@tauri-apps/plugin-dialog')
+  const selected = await open({ multiple: false, filters })
+  return typeof selected === 'string' ? selected : null
+}
@@ -1,4 +1,10 @@
-export type DiffType = 'added' | 'removed' | 'changed' | 'unchanged'
+export type DiffType = 'added' | 'removed' | 'changed' | 'unchanged' | 'moved' | 'equivalent'
+
+/** Rule under which an `equivalent` pair matched (backend diffs only). */
+export type Equivalence = 'tolerance' | 'coercion' | 'caseInsensitive' | 'whitespace' | 'nullMissing'
+
+/** How a string value was parsed into structure (backend diffs only). */
+export type EmbeddedEncoding = 'json' | 'yaml' | 'base64Json'
 
 export interface JsonNode {
   key: string
@@ -7,6 +13,12 @@ export interface JsonNode {
   type: 'object' | 'array' | 'primitive'
   depth: number
   children?: JsonNode[]
+  /** Set instead of `children` on nodes coming from the Rust backend. */
+  childCount?: number
+  /** The number as written when a JS number would round it (backend diffs only). */
+  raw?: string
+  /** Set when the value was a string holding a serialized document. */
+  decoded?: EmbeddedEncoding
   isLast: boolean
 }
 
@@ -18,6 +30,11 @@ export interface DiffNode {
   depth: number
   isCollapsible: boolean
   childDiffs?: DiffNode[]
+  equivalence?: Equivalence
+  /** Old path of a renamed or moved subtree, set on the row at its new path. */
+  movedFrom?: string
+  /** New path of a renamed or moved subtree, set on the row at its old path. */
+  movedTo?: string
 }
 
 export interface FlatDiffRow {